@@ -0,0 +1,316 @@
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use parking_lot::RwLock;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+
+pub const WHITELIST_FILE: &str = "whitelist.json";
+pub const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+pub const BANNED_IPS_FILE: &str = "banned-ips.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WhitelistEntry {
+    pub uuid: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerBan {
+    pub uuid: String,
+    pub name: String,
+    /// Who issued the ban.
+    pub source: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IpBan {
+    pub ip: String,
+    /// Who issued the ban.
+    pub source: String,
+    pub reason: String,
+}
+
+/// The whitelist and ban lists.
+///
+/// Each list is persisted as a JSON file in the server's root directory (see
+/// [crate::utils::get_root_path]), and saved every time it's edited. Edits made to the files while
+/// the server is running can be loaded with [AccessLists::reload].
+///
+/// Players are matched by their (offline) UUID, or by name if the UUID doesn't match.
+pub struct AccessLists {
+    dir: PathBuf,
+    whitelist: RwLock<Vec<WhitelistEntry>>,
+    banned_players: RwLock<Vec<PlayerBan>>,
+    banned_ips: RwLock<Vec<IpBan>>,
+}
+
+impl AccessLists {
+    /// Load the lists from the given directory. Missing files are treated as empty lists.
+    pub fn load(dir: impl Into<PathBuf>) -> Result<Self> {
+        let lists = Self {
+            dir: dir.into(),
+            whitelist: RwLock::new(Vec::new()),
+            banned_players: RwLock::new(Vec::new()),
+            banned_ips: RwLock::new(Vec::new()),
+        };
+        lists.reload()?;
+        Ok(lists)
+    }
+
+    /// Reload all the lists from disk.
+    pub fn reload(&self) -> Result<()> {
+        let whitelist = read_list(&self.dir.join(WHITELIST_FILE))?;
+        let banned_players = read_list(&self.dir.join(BANNED_PLAYERS_FILE))?;
+        let banned_ips = read_list(&self.dir.join(BANNED_IPS_FILE))?;
+
+        debug!(
+            "Loaded {} whitelisted players, {} banned players and {} banned IPs",
+            whitelist.len(),
+            banned_players.len(),
+            banned_ips.len()
+        );
+
+        *self.whitelist.write() = whitelist;
+        *self.banned_players.write() = banned_players;
+        *self.banned_ips.write() = banned_ips;
+        Ok(())
+    }
+
+    pub fn is_whitelisted(&self, uuid: &str, name: &str) -> bool {
+        self.whitelist
+            .read()
+            .iter()
+            .any(|entry| matches_player(&entry.uuid, &entry.name, uuid, name))
+    }
+
+    pub fn whitelist(&self) -> Vec<WhitelistEntry> {
+        self.whitelist.read().clone()
+    }
+
+    /// Returns false if the player was already whitelisted.
+    pub fn add_to_whitelist(&self, name: &str) -> Result<bool> {
+        let uuid = Player::offline_uuid(name).to_string();
+        let mut whitelist = self.whitelist.write();
+        if whitelist
+            .iter()
+            .any(|entry| matches_player(&entry.uuid, &entry.name, &uuid, name))
+        {
+            return Ok(false);
+        }
+        whitelist.push(WhitelistEntry {
+            uuid,
+            name: name.to_string(),
+        });
+        write_list(&self.dir.join(WHITELIST_FILE), &whitelist)?;
+        Ok(true)
+    }
+
+    /// Returns false if the player wasn't whitelisted.
+    pub fn remove_from_whitelist(&self, name: &str) -> Result<bool> {
+        let uuid = Player::offline_uuid(name).to_string();
+        let mut whitelist = self.whitelist.write();
+        let len = whitelist.len();
+        whitelist.retain(|entry| !matches_player(&entry.uuid, &entry.name, &uuid, name));
+        if whitelist.len() == len {
+            return Ok(false);
+        }
+        write_list(&self.dir.join(WHITELIST_FILE), &whitelist)?;
+        Ok(true)
+    }
+
+    pub fn get_player_ban(&self, uuid: &str, name: &str) -> Option<PlayerBan> {
+        self.banned_players
+            .read()
+            .iter()
+            .find(|ban| matches_player(&ban.uuid, &ban.name, uuid, name))
+            .cloned()
+    }
+
+    pub fn banned_players(&self) -> Vec<PlayerBan> {
+        self.banned_players.read().clone()
+    }
+
+    /// Returns false if the player was already banned.
+    pub fn ban_player(&self, name: &str, source: &str, reason: &str) -> Result<bool> {
+        let uuid = Player::offline_uuid(name).to_string();
+        let mut banned_players = self.banned_players.write();
+        if banned_players
+            .iter()
+            .any(|ban| matches_player(&ban.uuid, &ban.name, &uuid, name))
+        {
+            return Ok(false);
+        }
+        info!("{} banned {}: {}", source, name, reason);
+        banned_players.push(PlayerBan {
+            uuid,
+            name: name.to_string(),
+            source: source.to_string(),
+            reason: reason.to_string(),
+        });
+        write_list(&self.dir.join(BANNED_PLAYERS_FILE), &banned_players)?;
+        Ok(true)
+    }
+
+    /// Returns false if the player wasn't banned.
+    pub fn pardon_player(&self, name: &str) -> Result<bool> {
+        let uuid = Player::offline_uuid(name).to_string();
+        let mut banned_players = self.banned_players.write();
+        let len = banned_players.len();
+        banned_players.retain(|ban| !matches_player(&ban.uuid, &ban.name, &uuid, name));
+        if banned_players.len() == len {
+            return Ok(false);
+        }
+        write_list(&self.dir.join(BANNED_PLAYERS_FILE), &banned_players)?;
+        Ok(true)
+    }
+
+    pub fn get_ip_ban(&self, ip: &IpAddr) -> Option<IpBan> {
+        self.banned_ips
+            .read()
+            .iter()
+            .find(|ban| ban.ip.parse::<IpAddr>().is_ok_and(|banned| banned == *ip))
+            .cloned()
+    }
+
+    pub fn banned_ips(&self) -> Vec<IpBan> {
+        self.banned_ips.read().clone()
+    }
+
+    /// Returns false if the IP was already banned.
+    pub fn ban_ip(&self, ip: &IpAddr, source: &str, reason: &str) -> Result<bool> {
+        let mut banned_ips = self.banned_ips.write();
+        if banned_ips
+            .iter()
+            .any(|ban| ban.ip.parse::<IpAddr>().is_ok_and(|banned| banned == *ip))
+        {
+            return Ok(false);
+        }
+        info!("{} banned IP {}: {}", source, ip, reason);
+        banned_ips.push(IpBan {
+            ip: ip.to_string(),
+            source: source.to_string(),
+            reason: reason.to_string(),
+        });
+        write_list(&self.dir.join(BANNED_IPS_FILE), &banned_ips)?;
+        Ok(true)
+    }
+
+    /// Returns false if the IP wasn't banned.
+    pub fn pardon_ip(&self, ip: &IpAddr) -> Result<bool> {
+        let mut banned_ips = self.banned_ips.write();
+        let len = banned_ips.len();
+        banned_ips.retain(|ban| !ban.ip.parse::<IpAddr>().is_ok_and(|banned| banned == *ip));
+        if banned_ips.len() == len {
+            return Ok(false);
+        }
+        write_list(&self.dir.join(BANNED_IPS_FILE), &banned_ips)?;
+        Ok(true)
+    }
+}
+
+fn matches_player(entry_uuid: &str, entry_name: &str, uuid: &str, name: &str) -> bool {
+    entry_uuid.eq_ignore_ascii_case(uuid) || entry_name.eq_ignore_ascii_case(name)
+}
+
+fn read_list<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = std::fs::read_to_string(path)?;
+    if contents.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&contents).map_err(|e| {
+        Error::DeserializationError(format!("Failed to read {}: {}", path.display(), e))
+    })
+}
+
+fn write_list<T: Serialize>(path: &Path, list: &[T]) -> Result<()> {
+    let contents = serde_json::to_string_pretty(list)
+        .map_err(|e| Error::SerializationError(format!("{}", e)))?;
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ferrumc-access-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_whitelist_is_persisted() {
+        let dir = temp_dir();
+        let lists = AccessLists::load(&dir).unwrap();
+        let uuid = Player::offline_uuid("Steve").to_string();
+
+        assert!(!lists.is_whitelisted(&uuid, "Steve"));
+        assert!(lists.add_to_whitelist("Steve").unwrap());
+        assert!(!lists.add_to_whitelist("steve").unwrap());
+        assert!(lists.is_whitelisted(&uuid, "Steve"));
+
+        let reloaded = AccessLists::load(&dir).unwrap();
+        assert!(reloaded.is_whitelisted(&uuid, "Steve"));
+        assert!(reloaded.remove_from_whitelist("Steve").unwrap());
+        assert!(!reloaded.is_whitelisted(&uuid, "Steve"));
+
+        lists.reload().unwrap();
+        assert!(!lists.is_whitelisted(&uuid, "Steve"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_bans() {
+        let dir = temp_dir();
+        let lists = AccessLists::load(&dir).unwrap();
+        let uuid = Player::offline_uuid("Alex").to_string();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+
+        assert!(lists.ban_player("Alex", "Server", "Griefing").unwrap());
+        assert!(lists.ban_ip(&ip, "Server", "Spam").unwrap());
+        assert!(!lists.ban_ip(&ip, "Server", "Spam").unwrap());
+
+        let reloaded = AccessLists::load(&dir).unwrap();
+        assert_eq!(
+            reloaded.get_player_ban(&uuid, "Alex").unwrap().reason,
+            "Griefing"
+        );
+        assert_eq!(reloaded.get_ip_ban(&ip).unwrap().reason, "Spam");
+        assert!(reloaded.get_ip_ban(&"127.0.0.2".parse().unwrap()).is_none());
+
+        assert!(reloaded.pardon_player("Alex").unwrap());
+        assert!(reloaded.pardon_ip(&ip).unwrap());
+        assert!(!reloaded.pardon_ip(&ip).unwrap());
+        assert!(reloaded.get_player_ban(&uuid, "Alex").is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_reload_picks_up_file_edits() {
+        let dir = temp_dir();
+        let lists = AccessLists::load(&dir).unwrap();
+        std::fs::write(
+            dir.join(WHITELIST_FILE),
+            r#"[{"uuid": "00000000-0000-0000-0000-000000000000", "name": "Herobrine"}]"#,
+        )
+        .unwrap();
+
+        assert!(!lists.is_whitelisted("", "Herobrine"));
+        lists.reload().unwrap();
+        assert!(lists.is_whitelisted("", "herobrine"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
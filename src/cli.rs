@@ -0,0 +1,134 @@
+//! The command line interface.
+//!
+//! Running `ferrumc` without a subcommand is the same as `ferrumc run`.
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::utils::config::ConfigOverrides;
+use crate::world::importing::DEFAULT_BATCH_SIZE;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "ferrumc",
+    version,
+    about = "A Minecraft server written in Rust"
+)]
+pub struct Cli {
+    /// The directory the server keeps its data in. Defaults to the directory of the executable.
+    #[arg(long, global = true, env = "FERRUMC_ROOT")]
+    pub root: Option<PathBuf>,
+    /// The config file to use. Defaults to config.toml in the root directory.
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// The log level: trace, debug, info, warn or error.
+    #[arg(long, global = true)]
+    pub log: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the server.
+    Run(RunArgs),
+    /// Create the config file and the directories the server needs, then exit.
+    Setup,
+    /// Import the chunks of a world's region files (.mca) into the database.
+    Import(ImportArgs),
+    /// Export the chunks in the database to region files.
+    Export(ExportArgs),
+    /// Inspect the database.
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+}
+
+/// Overrides for the config file, for this run only.
+#[derive(Debug, Default, Clone, Args)]
+pub struct RunArgs {
+    /// The address to bind to.
+    #[arg(long)]
+    pub host: Option<String>,
+    /// The port to bind to.
+    #[arg(long)]
+    pub port: Option<u32>,
+    /// The maximum number of players that can be connected at once.
+    #[arg(long)]
+    pub max_players: Option<i32>,
+    /// The world to load.
+    #[arg(long)]
+    pub world: Option<String>,
+}
+
+impl From<RunArgs> for ConfigOverrides {
+    fn from(args: RunArgs) -> Self {
+        Self {
+            host: args.host,
+            port: args.port,
+            max_players: args.max_players,
+            world: args.world,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ImportArgs {
+    /// The directory with the region files, usually `<world>/region`.
+    pub region_dir: PathBuf,
+    /// How many chunks to process at once.
+    #[arg(long, default_value_t = DEFAULT_BATCH_SIZE)]
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ExportArgs {
+    /// Where to write the region files. Defaults to `export` in the root directory.
+    pub output_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum DbCommand {
+    /// Show how many chunks are stored and how big the database is.
+    Stats,
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse() {
+        let cli = Cli::parse_from(["ferrumc", "run", "--port", "25566", "--root", "/srv/mc"]);
+        assert_eq!(cli.root, Some(PathBuf::from("/srv/mc")));
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected the run command");
+        };
+        assert_eq!(args.port, Some(25566));
+
+        let cli = Cli::parse_from(["ferrumc", "import", "world/region", "--batch-size", "20"]);
+        let Some(Command::Import(args)) = cli.command else {
+            panic!("expected the import command");
+        };
+        assert_eq!(args.region_dir, PathBuf::from("world/region"));
+        assert_eq!(args.batch_size, 20);
+
+        let cli = Cli::parse_from(["ferrumc", "db", "stats"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Db {
+                command: DbCommand::Stats
+            })
+        ));
+        assert!(Cli::try_parse_from(["ferrumc", "import"]).is_err());
+    }
+}
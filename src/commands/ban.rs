@@ -0,0 +1,266 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use crate::commands::{usage_error, Command, CommandSender};
+use crate::net::packets::outgoing::disconnect::Disconnect;
+use crate::net::{drop_conn, Connection, ConnectionWrapper};
+use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+const DEFAULT_REASON: &str = "Banned by an operator.";
+
+/// `/ban <player> [reason]`. Kicks the player if they're online.
+pub struct BanCommand;
+
+#[async_trait]
+impl Command for BanCommand {
+    fn name(&self) -> &'static str {
+        "ban"
+    }
+
+    fn usage(&self) -> &'static str {
+        "<player> [reason]"
+    }
+
+    async fn execute(
+        &self,
+        sender: &CommandSender,
+        args: &[&str],
+        state: GlobalState,
+    ) -> Result<String> {
+        let Some((name, reason)) = args.split_first() else {
+            return Err(usage_error(self));
+        };
+        let reason = join_reason(reason);
+        let source = sender.name(&state).await;
+
+        if !state.access.ban_player(name, &source, &reason)? {
+            return Ok(format!("{} is already banned", name));
+        }
+
+        kick_matching(&state, &reason, |player, _| {
+            player.username.eq_ignore_ascii_case(name)
+        })
+        .await;
+
+        Ok(format!("Banned {}: {}", name, reason))
+    }
+}
+
+/// `/ban-ip <ip|player> [reason]`. Kicks everyone connected from that IP.
+pub struct BanIpCommand;
+
+#[async_trait]
+impl Command for BanIpCommand {
+    fn name(&self) -> &'static str {
+        "ban-ip"
+    }
+
+    fn usage(&self) -> &'static str {
+        "<ip|player> [reason]"
+    }
+
+    async fn execute(
+        &self,
+        sender: &CommandSender,
+        args: &[&str],
+        state: GlobalState,
+    ) -> Result<String> {
+        let Some((target, reason)) = args.split_first() else {
+            return Err(usage_error(self));
+        };
+        let ip = match target.parse::<IpAddr>() {
+            Ok(ip) => ip,
+            Err(_) => find_player_ip(&state, target).await.ok_or_else(|| {
+                Error::InvalidCommand(format!("{} is not a valid IP or online player", target))
+            })?,
+        };
+        let reason = join_reason(reason);
+        let source = sender.name(&state).await;
+
+        if !state.access.ban_ip(&ip, &source, &reason)? {
+            return Ok(format!("{} is already banned", ip));
+        }
+
+        kick_matching(&state, &reason, |_, conn| {
+            conn.metadata
+                .address
+                .is_some_and(|address| address.ip() == ip)
+        })
+        .await;
+
+        Ok(format!("Banned IP {}: {}", ip, reason))
+    }
+}
+
+/// `/pardon <player>`
+pub struct PardonCommand;
+
+#[async_trait]
+impl Command for PardonCommand {
+    fn name(&self) -> &'static str {
+        "pardon"
+    }
+
+    fn usage(&self) -> &'static str {
+        "<player>"
+    }
+
+    async fn execute(
+        &self,
+        _sender: &CommandSender,
+        args: &[&str],
+        state: GlobalState,
+    ) -> Result<String> {
+        let [name] = args else {
+            return Err(usage_error(self));
+        };
+        Ok(if state.access.pardon_player(name)? {
+            format!("Unbanned {}", name)
+        } else {
+            format!("{} is not banned", name)
+        })
+    }
+}
+
+/// `/pardon-ip <ip>`
+pub struct PardonIpCommand;
+
+#[async_trait]
+impl Command for PardonIpCommand {
+    fn name(&self) -> &'static str {
+        "pardon-ip"
+    }
+
+    fn usage(&self) -> &'static str {
+        "<ip>"
+    }
+
+    async fn execute(
+        &self,
+        _sender: &CommandSender,
+        args: &[&str],
+        state: GlobalState,
+    ) -> Result<String> {
+        let [ip] = args else {
+            return Err(usage_error(self));
+        };
+        let ip = ip
+            .parse::<IpAddr>()
+            .map_err(|_| Error::InvalidCommand(format!("{} is not a valid IP", ip)))?;
+        Ok(if state.access.pardon_ip(&ip)? {
+            format!("Unbanned IP {}", ip)
+        } else {
+            format!("{} is not banned", ip)
+        })
+    }
+}
+
+/// `/banlist [players|ips]`
+pub struct BanListCommand;
+
+#[async_trait]
+impl Command for BanListCommand {
+    fn name(&self) -> &'static str {
+        "banlist"
+    }
+
+    fn usage(&self) -> &'static str {
+        "[players|ips]"
+    }
+
+    async fn execute(
+        &self,
+        _sender: &CommandSender,
+        args: &[&str],
+        state: GlobalState,
+    ) -> Result<String> {
+        let entries = match args {
+            [] | ["players"] => state
+                .access
+                .banned_players()
+                .into_iter()
+                .map(|ban| format!("{} ({})", ban.name, ban.reason))
+                .collect::<Vec<_>>(),
+            ["ips"] => state
+                .access
+                .banned_ips()
+                .into_iter()
+                .map(|ban| format!("{} ({})", ban.ip, ban.reason))
+                .collect::<Vec<_>>(),
+            _ => return Err(usage_error(self)),
+        };
+        Ok(format!(
+            "There are {} ban(s): {}",
+            entries.len(),
+            entries.join(", ")
+        ))
+    }
+}
+
+fn join_reason(words: &[&str]) -> String {
+    if words.is_empty() {
+        DEFAULT_REASON.to_string()
+    } else {
+        words.join(" ")
+    }
+}
+
+async fn find_player_ip(state: &GlobalState, name: &str) -> Option<IpAddr> {
+    let query = state.world.query::<(&Player, &ConnectionWrapper)>();
+    for (_, (player, conn)) in query.iter().await {
+        if player.username.eq_ignore_ascii_case(name) {
+            return conn
+                .0
+                .read()
+                .await
+                .metadata
+                .address
+                .map(|address| address.ip());
+        }
+    }
+    None
+}
+
+/// Disconnects every online player matching the predicate, with the configured ban message.
+async fn kick_matching(
+    state: &GlobalState,
+    reason: &str,
+    predicate: impl Fn(&Player, &Connection) -> bool,
+) {
+    let mut to_kick: Vec<Arc<RwLock<Connection>>> = Vec::new();
+    {
+        // Collect the connections first, since dropping them deletes their entities.
+        let query = state.world.query::<(&Player, &ConnectionWrapper)>();
+        for (_, (player, conn)) in query.iter().await {
+            if predicate(&player, &*conn.0.read().await) {
+                to_kick.push(conn.0.clone());
+            }
+        }
+    }
+
+    let message = get_global_config()
+        .disconnect_messages
+        .banned_message(reason);
+    for conn in to_kick {
+        let conn_id = {
+            let conn = conn.read().await;
+            if let Err(e) = conn
+                .send_packet(Disconnect::new_auto(message.clone()))
+                .await
+            {
+                warn!("Failed to send disconnect packet: {:?}", e);
+            }
+            conn.id
+        };
+        if let Err(e) = drop_conn(conn_id, state.clone()).await {
+            warn!("Failed to drop connection {}: {:?}", conn_id, e);
+        }
+    }
+}
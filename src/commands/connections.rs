@@ -0,0 +1,41 @@
+use async_trait::async_trait;
+
+use crate::commands::{usage_error, Command, CommandSender};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// `/connections`. Shows the connection throttle counters, to help tune `[connection_limits]`.
+pub struct ConnectionsCommand;
+
+#[async_trait]
+impl Command for ConnectionsCommand {
+    fn name(&self) -> &'static str {
+        "connections"
+    }
+
+    fn usage(&self) -> &'static str {
+        ""
+    }
+
+    async fn execute(
+        &self,
+        _sender: &CommandSender,
+        args: &[&str],
+        state: GlobalState,
+    ) -> Result<String> {
+        if !args.is_empty() {
+            return Err(usage_error(self));
+        }
+        let stats = state.throttle.stats();
+        Ok(format!(
+            "Accepted: {}, refused (too many open): {}, refused (connection rate): {}, \
+             refused (login rate): {}, login timeouts: {}, tracked IPs: {}",
+            stats.accepted_connections,
+            stats.rejected_too_many_connections,
+            stats.rejected_connection_rate,
+            stats.rejected_login_rate,
+            stats.login_timeouts,
+            stats.tracked_ips
+        ))
+    }
+}
@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use tracing::info;
+
+use crate::net::packets::ConnectionId;
+use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+pub mod ban;
+pub mod connections;
+pub mod snapshot;
+pub mod tps;
+pub mod whitelist;
+
+/// Who ran a command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandSender {
+    /// The server itself, e.g. the console. Always allowed to run every command.
+    Console,
+    Player(ConnectionId),
+}
+
+impl CommandSender {
+    /// The name shown as the source of bans etc.
+    pub async fn name(&self, state: &GlobalState) -> String {
+        match self {
+            CommandSender::Console => "Server".to_string(),
+            CommandSender::Player(conn_id) => state
+                .world
+                .get_component::<Player>(*conn_id)
+                .await
+                .map(|player| player.username.clone())
+                .unwrap_or_else(|_| "Unknown".to_string()),
+        }
+    }
+
+    /// Whether the sender is in the `operators` list in the config.
+    pub async fn is_operator(&self, state: &GlobalState) -> bool {
+        let CommandSender::Player(conn_id) = self else {
+            return true;
+        };
+        let Ok(player) = state.world.get_component::<Player>(*conn_id).await else {
+            return false;
+        };
+        get_global_config()
+            .operators
+            .iter()
+            .any(|operator| operator.eq_ignore_ascii_case(&player.username))
+    }
+}
+
+#[async_trait]
+pub trait Command: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// The arguments the command takes, e.g. `<player> [reason]`.
+    fn usage(&self) -> &'static str;
+    /// Whether only operators can run this command.
+    fn operator_only(&self) -> bool {
+        true
+    }
+    /// Runs the command, and returns the feedback to show to the sender.
+    ///
+    /// Return [Error::InvalidCommand] if the arguments are invalid.
+    async fn execute(
+        &self,
+        sender: &CommandSender,
+        args: &[&str],
+        state: GlobalState,
+    ) -> Result<String>;
+}
+
+pub static ALL_COMMANDS: &[&dyn Command] = &[
+    &whitelist::WhitelistCommand,
+    &ban::BanCommand,
+    &ban::BanIpCommand,
+    &ban::PardonCommand,
+    &ban::PardonIpCommand,
+    &ban::BanListCommand,
+    &connections::ConnectionsCommand,
+    &tps::TpsCommand,
+    &snapshot::SnapshotCommand,
+];
+
+pub fn get_command(name: &str) -> Option<&'static dyn Command> {
+    ALL_COMMANDS
+        .iter()
+        .find(|command| command.name().eq_ignore_ascii_case(name))
+        .copied()
+}
+
+/// Parses and runs a command line, e.g. `/ban Notch Being too good`. The leading slash is optional.
+///
+/// Returns the feedback to show to the sender.
+pub async fn dispatch_command(
+    input: &str,
+    sender: &CommandSender,
+    state: GlobalState,
+) -> Result<String> {
+    let input = input.trim();
+    let input = input.strip_prefix('/').unwrap_or(input);
+    let mut parts = input.split_whitespace();
+    let Some(name) = parts.next() else {
+        return Err(Error::InvalidCommand("No command given".to_string()));
+    };
+    let args = parts.collect::<Vec<_>>();
+
+    let Some(command) = get_command(name) else {
+        return Err(Error::InvalidCommand(format!("Unknown command: {}", name)));
+    };
+
+    if command.operator_only() && !sender.is_operator(&state).await {
+        return Err(Error::InvalidCommand(
+            "You don't have permission to use this command".to_string(),
+        ));
+    }
+
+    info!("{} ran command: /{}", sender.name(&state).await, input);
+
+    command.execute(sender, &args, state).await
+}
+
+/// Shorthand for the error returned when a command gets the wrong arguments.
+pub(crate) fn usage_error(command: &dyn Command) -> Error {
+    Error::InvalidCommand(format!("Usage: /{} {}", command.name(), command.usage()))
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+
+use crate::commands::{usage_error, Command, CommandSender};
+use crate::state::GlobalState;
+use crate::utils::get_root_path;
+use crate::utils::prelude::*;
+
+/// The directory under the server root snapshots are saved to.
+const SNAPSHOT_DIR: &str = "snapshots";
+
+/// `/snapshot [file]`. Dumps the ECS world as JSON, to inspect it when something goes wrong.
+///
+/// The file is always saved in the [SNAPSHOT_DIR], so the name can't contain path separators or
+/// `..`.
+pub struct SnapshotCommand;
+
+#[async_trait]
+impl Command for SnapshotCommand {
+    fn name(&self) -> &'static str {
+        "snapshot"
+    }
+
+    fn usage(&self) -> &'static str {
+        "[file]"
+    }
+
+    async fn execute(
+        &self,
+        _sender: &CommandSender,
+        args: &[&str],
+        state: GlobalState,
+    ) -> Result<String> {
+        let file_name = match args {
+            [] => "world_snapshot.json",
+            [file_name] => file_name,
+            _ => return Err(usage_error(self)),
+        };
+        let path = snapshot_path(file_name)?;
+
+        let snapshot = state.world.snapshot().await?;
+        tokio::fs::create_dir_all(get_root_path()?.join(SNAPSHOT_DIR)).await?;
+        tokio::fs::write(&path, snapshot.to_json()?).await?;
+        Ok(format!(
+            "Saved {} entities to {}",
+            snapshot.entities.len(),
+            path.display()
+        ))
+    }
+}
+
+/// Where a snapshot with the given file name is saved, if the name is a plain file name.
+fn snapshot_path(file_name: &str) -> Result<PathBuf> {
+    if !is_plain_file_name(file_name) {
+        return Err(Error::InvalidCommand(format!(
+            "Invalid file name: {}. Snapshots are saved in the {} directory, so the name can't \
+             contain path separators or ..",
+            file_name, SNAPSHOT_DIR
+        )));
+    }
+    Ok(get_root_path()?.join(SNAPSHOT_DIR).join(file_name))
+}
+
+fn is_plain_file_name(file_name: &str) -> bool {
+    !file_name.is_empty()
+        && !file_name.contains("..")
+        && !file_name.contains(['/', '\\', ':', '\0'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_names() {
+        assert!(is_plain_file_name("world_snapshot.json"));
+        assert!(is_plain_file_name("before-crash.json"));
+
+        assert!(!is_plain_file_name(""));
+        assert!(!is_plain_file_name(".."));
+        assert!(!is_plain_file_name("../server.properties"));
+        assert!(!is_plain_file_name("/etc/passwd"));
+        assert!(!is_plain_file_name("logs/latest.log"));
+        assert!(!is_plain_file_name("..\\config.toml"));
+        assert!(!is_plain_file_name("C:\\config.toml"));
+    }
+}
@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+
+use crate::commands::{usage_error, Command, CommandSender};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// `/tps`. Shows how many ticks per second the server manages, and how long ticks take.
+pub struct TpsCommand;
+
+#[async_trait]
+impl Command for TpsCommand {
+    fn name(&self) -> &'static str {
+        "tps"
+    }
+
+    fn usage(&self) -> &'static str {
+        ""
+    }
+
+    fn operator_only(&self) -> bool {
+        false
+    }
+
+    async fn execute(
+        &self,
+        _sender: &CommandSender,
+        args: &[&str],
+        state: GlobalState,
+    ) -> Result<String> {
+        if !args.is_empty() {
+            return Err(usage_error(self));
+        }
+        let stats = &state.tick_stats;
+        Ok(format!(
+            "TPS: {:.1}, MSPT: {:.2} ms, tick {} ({} skipped)",
+            stats.tps(),
+            stats.mspt(),
+            stats.current_tick(),
+            stats.skipped_ticks()
+        ))
+    }
+}
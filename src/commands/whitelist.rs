@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+
+use crate::commands::{usage_error, Command, CommandSender};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// `/whitelist add|remove <player>`, `/whitelist list` and `/whitelist reload`.
+///
+/// Whether the whitelist is enforced is set with `whitelist` in the config.
+pub struct WhitelistCommand;
+
+#[async_trait]
+impl Command for WhitelistCommand {
+    fn name(&self) -> &'static str {
+        "whitelist"
+    }
+
+    fn usage(&self) -> &'static str {
+        "<add|remove|list|reload> [player]"
+    }
+
+    async fn execute(
+        &self,
+        _sender: &CommandSender,
+        args: &[&str],
+        state: GlobalState,
+    ) -> Result<String> {
+        match args {
+            ["add", name] => Ok(if state.access.add_to_whitelist(name)? {
+                format!("Added {} to the whitelist", name)
+            } else {
+                format!("{} is already whitelisted", name)
+            }),
+            ["remove", name] => Ok(if state.access.remove_from_whitelist(name)? {
+                format!("Removed {} from the whitelist", name)
+            } else {
+                format!("{} is not whitelisted", name)
+            }),
+            ["list"] => {
+                let names = state
+                    .access
+                    .whitelist()
+                    .into_iter()
+                    .map(|entry| entry.name)
+                    .collect::<Vec<_>>();
+                Ok(format!(
+                    "There are {} whitelisted players: {}",
+                    names.len(),
+                    names.join(", ")
+                ))
+            }
+            ["reload"] => {
+                state.access.reload()?;
+                Ok("Reloaded the whitelist and ban lists".to_string())
+            }
+            _ => Err(usage_error(self)),
+        }
+    }
+}
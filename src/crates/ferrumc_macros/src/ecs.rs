@@ -14,26 +14,47 @@ pub fn derive_component(input: TokenStream) -> TokenStream {
     let get_method_name = format_ident!("get_{}_component", snake_case_name);
     let get_method_name_mut = format_ident!("get_{}_component_mut", snake_case_name);
 
+    // `#[component(serialize)]` registers the component for `World::snapshot`/`World::restore`
+    let mut serialize = false;
+    for attr in ast.attrs.iter().filter(|attr| attr.path().is_ident("component")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("serialize") {
+                serialize = true;
+                Ok(())
+            } else {
+                Err(meta.error("Unknown #[component] argument. Expected serialize"))
+            }
+        })
+        .expect("Failed to parse #[component] attribute");
+    }
+    let serialize_registration = serialize.then(|| {
+        quote! {
+            inventory::submit! {
+                crate::ecs::snapshot::SerializableComponent::new::<#name>(stringify!(#name))
+            }
+        }
+    });
+
 
     let gen = quote! {
         impl crate::ecs::component::Component for #name {}
 
+        #serialize_registration
+
         pub trait #ext_trait_name {
-            async fn #get_method_name<'a>(&'a self, entity_id: impl TryInto<usize>) -> crate::Result<crate::ecs::component::ComponentRef<'a, #name>>;
-            async fn #get_method_name_mut<'a>(&'a self, entity_id: impl TryInto<usize>) -> crate::Result<crate::ecs::component::ComponentRefMut<'a, #name>>;
+            async fn #get_method_name<'a>(&'a self, entity: crate::ecs::entity::Entity) -> crate::Result<crate::ecs::component::ComponentRef<'a, #name>>;
+            async fn #get_method_name_mut<'a>(&'a self, entity: crate::ecs::entity::Entity) -> crate::Result<crate::ecs::component::ComponentRefMut<'a, #name>>;
         }
 
         impl #ext_trait_name for crate::ecs::world::World {
-             async fn #get_method_name<'a>(&'a self, entity_id: impl TryInto<usize>) -> crate::Result<crate::ecs::component::ComponentRef<'a, #name>>{
-                let entity_id = entity_id.try_into().map_err(|_| crate::Error::ConversionError)?;
+             async fn #get_method_name<'a>(&'a self, entity: crate::ecs::entity::Entity) -> crate::Result<crate::ecs::component::ComponentRef<'a, #name>>{
                 let state = self.clone();
-                state.get_component::<#name>(entity_id).await
+                state.get_component::<#name>(entity).await
             }
 
-            async fn #get_method_name_mut<'a>(&'a self, entity_id: impl TryInto<usize>) -> crate::Result<crate::ecs::component::ComponentRefMut<'a, #name>>{
-                let entity_id = entity_id.try_into().map_err(|_| crate::Error::ConversionError)?;
+            async fn #get_method_name_mut<'a>(&'a self, entity: crate::ecs::entity::Entity) -> crate::Result<crate::ecs::component::ComponentRefMut<'a, #name>>{
                 let state = self.clone();
-                state.get_component_mut::<#name>(entity_id).await
+                state.get_component_mut::<#name>(entity).await
             }
         }
     };
@@ -1,29 +1,23 @@
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
-use regex::Regex;
-use syn::{parse_macro_input, Expr, Lit, Meta};
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Expr, Lit, Meta};
 use syn::punctuated::Punctuated;
 
+use crate::utils::priority_from_name;
+
 pub(super) fn event_handler(args: TokenStream, input: TokenStream) -> TokenStream {
     // Parse the attribute arguments
     let args = parse_macro_input!(args with Punctuated::<Meta, syn::Token![,]>::parse_terminated);
 
 
-    fn get_priority(priority: &str) -> u8 {
-        match priority {
-            "fastest" => 0,
-            "fast" => 64,
-            "normal" => 128,
-            "slow" => 192,
-            "slowest" => 255,
-            _ => 128,
-        }
-    }
-
     let mut event_priority = 128;
+    let mut ignore_cancelled = false;
 
     for arg in args.iter() {
         match arg {
+            Meta::Path(path) if path.is_ident("ignore_cancelled") => {
+                ignore_cancelled = true;
+            }
             Meta::NameValue(nv) => {
                 if !nv.path.is_ident("priority") {
                     continue;
@@ -39,7 +33,7 @@ pub(super) fn event_handler(args: TokenStream, input: TokenStream) -> TokenStrea
                 match value {
                     Lit::Str(val) => {
                         let value = val.value();
-                        event_priority = get_priority(&value);
+                        event_priority = priority_from_name(&value);
                     }
                     Lit::Int(int) => {
                         event_priority = int.base10_parse::<u8>().expect("Expected a number for the priority attribute");
@@ -58,16 +52,10 @@ pub(super) fn event_handler(args: TokenStream, input: TokenStream) -> TokenStrea
     let inputs = &input_fn.sig.inputs;
 
     let event_type = match inputs.first().expect("Expected event type as the argument. e.g. event: &mut MoveEvent") {
-        syn::FnArg::Typed(typed) => {
-            // &typed.ty
-            let event_type = &typed.ty;
-            // remove `Arc<` and `>` cuz i dont know how to do it in the event handling :skull:
-            let event_type = event_type.to_token_stream().to_string();
-            let regex = Regex::new(r#"Arc.<.(.*).>"#).unwrap();
-            let event_type = regex.replace_all(&event_type, "$1").to_string();
-            let event_type: syn::Type = syn::parse_str(&event_type).expect("Failed to parse the event type");
-            event_type
-        }
+        syn::FnArg::Typed(typed) => match &*typed.ty {
+            syn::Type::Reference(reference) if reference.mutability.is_some() => &reference.elem,
+            _ => panic!("Expected a mutable reference to the event, e.g. event: &mut MoveEvent"),
+        },
         _ => panic!("Expected a typed argument for the event handler")
     };
 
@@ -79,10 +67,12 @@ pub(super) fn event_handler(args: TokenStream, input: TokenStream) -> TokenStrea
         inventory::submit! {
             crate::events::creation::registry::EventContainer::new(
                 #event_priority,
+                #ignore_cancelled,
                 /*#handler_path {
                     handler: &#fn_name,
                 }*/
                 &#handler_path {
+                    name: concat!(module_path!(), "::", stringify!(#fn_name)),
                     handler: |event, state| Box::pin(#fn_name(event, state)),
                 }
             )
@@ -90,4 +80,47 @@ pub(super) fn event_handler(args: TokenStream, input: TokenStream) -> TokenStrea
     };
 
     TokenStream::from(expanded)
-}
\ No newline at end of file
+}
+
+/// `#[derive(Event)]`, with `#[event(cancellable)]` to also implement `Cancellable`, which needs a
+/// `cancelled: bool` field.
+pub(super) fn derive_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut cancellable = false;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("event")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("cancellable") {
+                cancellable = true;
+                Ok(())
+            } else {
+                Err(meta.error("Unknown #[event] argument. Expected cancellable"))
+            }
+        })
+        .expect("Failed to parse #[event] attribute");
+    }
+
+    let expanded = if cancellable {
+        quote! {
+            impl #impl_generics crate::events::creation::event::Event for #name #ty_generics #where_clause {
+                fn is_cancelled(&self) -> bool {
+                    self.cancelled
+                }
+            }
+
+            impl #impl_generics crate::events::creation::event::Cancellable for #name #ty_generics #where_clause {
+                fn set_cancelled(&mut self, cancelled: bool) {
+                    self.cancelled = cancelled;
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics crate::events::creation::event::Event for #name #ty_generics #where_clause {}
+        }
+    };
+
+    TokenStream::from(expanded)
+}
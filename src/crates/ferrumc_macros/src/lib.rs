@@ -9,6 +9,7 @@ mod nbt_decode;
 mod packet;
 mod utils;
 mod events;
+mod systems;
 
 #[proc_macro_derive(NetDecode)]
 pub fn decode_derive(input: TokenStream) -> TokenStream {
@@ -35,7 +36,7 @@ pub fn bake_packet_registry(input: TokenStream) -> TokenStream {
     packet::bake(input)
 }
 
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(component))]
 pub fn derive_component(input: TokenStream) -> TokenStream {
     ecs::derive_component(input)
 }
@@ -54,7 +55,22 @@ pub fn derive_getter(input: TokenStream) -> TokenStream {
     utils::derive_getter(input)
 }
 
+#[proc_macro_derive(Event, attributes(event))]
+pub fn derive_event(input: TokenStream) -> TokenStream {
+    events::derive_event(input)
+}
+
 #[proc_macro_attribute]
 pub fn event_handler(args: TokenStream, input: TokenStream) -> TokenStream {
     events::event_handler(args, input)
-}
\ No newline at end of file
+}
+
+#[proc_macro_attribute]
+pub fn system(args: TokenStream, input: TokenStream) -> TokenStream {
+    systems::system(args, input)
+}
+
+#[proc_macro_attribute]
+pub fn tick_phase(args: TokenStream, input: TokenStream) -> TokenStream {
+    systems::tick_phase(args, input)
+}
@@ -0,0 +1,97 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Expr, ItemStruct, Lit, Meta, Type};
+
+use crate::utils::priority_from_name;
+
+/// `#[system]`, registers a unit struct implementing `System` so it's started with the server.
+pub(super) fn system(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        panic!("#[system] takes no arguments, use #[tick_phase] to declare component access");
+    }
+    let input_struct = parse_macro_input!(input as ItemStruct);
+    let name = unit_struct_name(&input_struct, "system");
+
+    let expanded = quote! {
+        #input_struct
+
+        inventory::submit! {
+            crate::net::systems::SystemContainer::new(&#name)
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// `#[tick_phase(reads(A, B), writes(C), priority = "fast")]`, registers a unit struct
+/// implementing `TickPhase` with the scheduler, along with the components it accesses.
+pub(super) fn tick_phase(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args with Punctuated::<Meta, syn::Token![,]>::parse_terminated);
+
+    let mut priority = 128u8;
+    let mut reads = Vec::new();
+    let mut writes = Vec::new();
+
+    for arg in args.iter() {
+        match arg {
+            Meta::List(list) if list.path.is_ident("reads") || list.path.is_ident("writes") => {
+                let types = list
+                    .parse_args_with(Punctuated::<Type, syn::Token![,]>::parse_terminated)
+                    .expect("Expected a list of component types, e.g. reads(Position, Rotation)");
+                if list.path.is_ident("reads") {
+                    reads.extend(types);
+                } else {
+                    writes.extend(types);
+                }
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("priority") => {
+                let Expr::Lit(value) = &nv.value else {
+                    panic!("Expected a literal for the priority attribute");
+                };
+                priority = match &value.lit {
+                    Lit::Str(val) => priority_from_name(&val.value()),
+                    Lit::Int(int) => int
+                        .base10_parse::<u8>()
+                        .expect("Expected a number between 0 and 255 for the priority attribute"),
+                    _ => panic!("Expected a string or a number for the priority attribute. Possible values are: fastest, fast, normal, slow, slowest, or values between 0 and 255. Where 0 runs first and 255 runs last"),
+                };
+            }
+            _ => panic!(
+                "Unknown #[tick_phase] argument. Expected reads(..), writes(..) or priority = .."
+            ),
+        }
+    }
+
+    let input_struct = parse_macro_input!(input as ItemStruct);
+    let name = unit_struct_name(&input_struct, "tick_phase");
+
+    let expanded = quote! {
+        #input_struct
+
+        inventory::submit! {
+            crate::net::systems::scheduler::TickPhaseContainer::new(
+                &#name,
+                #priority,
+                {
+                    fn access() -> crate::net::systems::scheduler::SystemAccess {
+                        crate::net::systems::scheduler::SystemAccess::new()
+                            #(.read::<#reads>())*
+                            #(.write::<#writes>())*
+                    }
+                    access
+                },
+            )
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// Systems are registered as `&'static` instances, so they have to be unit structs.
+fn unit_struct_name<'a>(input: &'a ItemStruct, attribute: &str) -> &'a syn::Ident {
+    if !matches!(input.fields, syn::Fields::Unit) {
+        panic!("#[{}] can only be used on unit structs", attribute);
+    }
+    &input.ident
+}
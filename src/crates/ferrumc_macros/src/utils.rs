@@ -54,4 +54,16 @@ pub(crate) fn derive_getter(input: TokenStream) -> TokenStream {
     TokenStream::from(quote! {
         #(#expanded)*
     })
-}
\ No newline at end of file
+}
+
+/// Maps a named priority to its value, lower runs first. Unknown names are `normal`.
+pub(crate) fn priority_from_name(priority: &str) -> u8 {
+    match priority {
+        "fastest" => 0,
+        "fast" => 64,
+        "normal" => 128,
+        "slow" => 192,
+        "slowest" => 255,
+        _ => 128,
+    }
+}
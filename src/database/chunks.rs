@@ -8,6 +8,7 @@ use tracing::{trace, warn};
 
 use super::spawn_blocking_db;
 use crate::database::encoding::ZstdCodec;
+use crate::utils::metrics::METRICS;
 use crate::world::importing::SerializedChunk;
 use crate::{
     database::Database, utils::error::Error, utils::hash::hash, world::chunk_format::Chunk,
@@ -164,11 +165,13 @@ impl Database {
         let chunk = value.clone();
         let db = self.db.clone();
         let tsk_db = self.db.clone();
+        let start = std::time::Instant::now();
         spawn_blocking_db(tsk_db, move || {
             Self::insert_chunk_into_database(&db, &chunk)
         })
         .await
         .unwrap()?;
+        METRICS.database_put.observe(start.elapsed());
 
         // Insert into cache
         self.cache.insert(key, value).await;
@@ -205,7 +208,9 @@ impl Database {
         let key = hash((dimension, x, z));
         let db = self.db.clone();
 
+        let start = std::time::Instant::now();
         let res = Self::get_chunk_from_database(&db, &key).await?;
+        METRICS.database_get.observe(start.elapsed());
 
         Ok(res)
 
@@ -254,9 +259,11 @@ impl Database {
 
         // Check first cache
         if self.cache.contains_key(&key) {
+            METRICS.cache_hits.inc();
             Ok(true)
         // Else check persistent database and load it into cache
         } else {
+            METRICS.cache_misses.inc();
             /*let res = spawn_blocking_db(tsk_db, move || Self::get_chunk_from_database(&db, &key))
             .await
             .unwrap();*/
@@ -311,11 +318,13 @@ impl Database {
         let chunk = value.clone();
         let db = self.db.clone();
         let tsk_db = self.db.clone();
+        let start = std::time::Instant::now();
         spawn_blocking_db(tsk_db, move || {
             Self::insert_chunk_into_database(&db, &chunk)
         })
         .await
         .unwrap()?;
+        METRICS.database_put.observe(start.elapsed());
 
         // Insert new chunk state into cache
         self.cache.insert(key, value).await;
@@ -373,14 +382,41 @@ impl Database {
         }
         */
         // Then insert into persistent database
+        let start = std::time::Instant::now();
         spawn_blocking_db(tsk_db, move || {
             Self::insert_chunks_into_database(&db, &values)
         })
         .await
         .unwrap()?;
+        METRICS.database_put.observe(start.elapsed());
 
         Ok(())
     }
+
+    /// The keys of every chunk in the persistent database, e.g. to go through all of them with
+    /// [Database::get_chunk_by_key].
+    pub async fn chunk_keys(&self) -> Result<Vec<u64>, Error> {
+        let db = self.db.clone();
+        let keys = spawn_blocking_db(self.db.clone(), move || {
+            let ro_tx = db.read_txn()?;
+            let database = db
+                .open_database::<U64<LE>, Bytes>(&ro_tx, Some("chunks"))?
+                .expect("No table \"chunks\" found. The database should have been initialized");
+            let keys = database
+                .iter(&ro_tx)?
+                .map(|entry| entry.map(|(key, _)| key))
+                .collect::<Result<Vec<u64>, heed::Error>>();
+            keys
+        })
+        .await
+        .unwrap()?;
+        Ok(keys)
+    }
+
+    /// Fetch a chunk from the persistent database by its key, skipping the cache
+    pub async fn get_chunk_by_key(&self, key: u64) -> Result<Option<Chunk>, Error> {
+        Ok(Self::get_chunk_from_database(&self.db, &key).await?)
+    }
 }
 
 #[tokio::test]
@@ -389,12 +425,15 @@ async fn dump_chunk() {
     use crate::utils::setup_logger;
     use nbt_lib::NBTSerialize;
     use tokio::net::TcpListener;
-    setup_logger().unwrap();
+    setup_logger(None, &Default::default()).unwrap();
     let state = crate::create_state(TcpListener::bind("0.0.0.0:0").await.unwrap())
         .await
         .unwrap();
     let chunk = state
-        .database
+        .world
+        .resource::<Database>()
+        .await
+        .unwrap()
         .get_chunk(2, 2, "overworld".to_string())
         .await
         .unwrap()
@@ -5,9 +5,7 @@ use heed::types::{Bytes, U64};
 use heed::{Env as LMDBDatabase, Env, EnvFlags, EnvOpenOptions, MdbError};
 use moka::notification::{ListenerFuture, RemovalCause};
 use rayon::{ThreadPool, ThreadPoolBuilder};
-use std::env;
 use std::future::Future;
-use std::path::PathBuf;
 use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock};
 use std::time::Duration;
 use tokio::fs;
@@ -16,6 +14,7 @@ use tracing::{debug, info, trace, warn};
 
 use crate::utils::config::get_global_config;
 use crate::utils::error::Error;
+use crate::utils::get_root_path;
 
 use crate::world::chunk_format::Chunk;
 pub mod chunks;
@@ -36,7 +35,8 @@ static LMDB_READER_SYNC: LazyLock<Arc<RwLock<()>>> = LazyLock::new(|| Arc::new(R
 /// Global database structure
 ///
 /// Internally contain a handle to the persistent database and a
-/// cache for all in-memory updates
+/// cache for all in-memory updates. A resource of the world, see
+/// [World::resource](crate::ecs::world::World::resource).
 pub struct Database {
     db: LMDBDatabase,
     cache: Arc<moka::future::Cache<u64, Chunk>>,
@@ -57,17 +57,7 @@ fn evict_chunk(_key: Arc<u64>, value: Chunk, cause: RemovalCause) -> ListenerFut
 
 /// Start database
 pub async fn start_database() -> Result<Database, Error> {
-    // Parse root directory from environment variable
-    let root = if env::var("FERRUMC_ROOT").is_ok() {
-        PathBuf::from(env::var("FERRUMC_ROOT").unwrap())
-    } else {
-        PathBuf::from(
-            env::current_exe()
-                .unwrap()
-                .parent()
-                .ok_or(Error::Generic("Failed to get exe directory".to_string()))?,
-        )
-    };
+    let root = get_root_path()?;
 
     // Obtain global config to locate which world folder to load
     let world = get_global_config().world.clone();
@@ -134,6 +124,39 @@ pub async fn start_database() -> Result<Database, Error> {
     })
 }
 
+/// Numbers about the persistent database, shown by `ferrumc db stats`
+#[derive(Debug, Clone, Copy)]
+pub struct DatabaseStats {
+    /// The number of chunks stored
+    pub chunks: u64,
+    /// The size of the database file
+    pub disk_size: u64,
+    /// How much of the file is actually in use
+    pub used_size: u64,
+}
+
+impl Database {
+    /// Collect stats about the persistent database
+    pub async fn stats(&self) -> Result<DatabaseStats, Error> {
+        let db = self.db.clone();
+        let stats = spawn_blocking_db(self.db.clone(), move || {
+            let ro_tx = db.read_txn()?;
+            let chunks = db
+                .open_database::<U64<LE>, Bytes>(&ro_tx, Some("chunks"))?
+                .expect("No table \"chunks\" found. The database should have been initialized")
+                .len(&ro_tx)?;
+            Ok(DatabaseStats {
+                chunks,
+                disk_size: db.real_disk_size()?,
+                used_size: db.non_free_pages_size()?,
+            })
+        })
+        .await
+        .unwrap()?;
+        Ok(stats)
+    }
+}
+
 /// LMDB will follow a linear growth as opposed to MDBX which
 /// uses a geometric growth.
 pub(super) fn new_page_size(old_size: usize) -> usize {
@@ -0,0 +1,223 @@
+use parking_lot::Mutex;
+use tracing::warn;
+
+use crate::ecs::component::{Component, ComponentStorage};
+use crate::ecs::entity::Entity;
+use crate::ecs::resource::{Resource, Resources};
+use crate::ecs::world::World;
+use crate::utils::prelude::*;
+
+type Insert = Box<dyn FnOnce(Entity, &ComponentStorage) + Send>;
+type Remove = Box<dyn FnOnce(Entity, &ComponentStorage) -> Result<()> + Send>;
+
+enum Command {
+    Spawn(Vec<Insert>),
+    Insert(Entity, Insert),
+    Remove(Entity, Remove),
+    Despawn(Entity),
+    InsertResource(Box<dyn FnOnce(&Resources) + Send>),
+}
+
+/// A buffer of changes to the world, recorded while systems run and applied all at once at a sync
+/// point, i.e. after every stage of the tick schedule.
+///
+/// Recording never waits on a lock, so it's safe while holding components from a query, e.g. to
+/// despawn the entities a query is iterating over.
+///
+/// # Examples
+/// ```ignore
+/// let commands = world.commands();
+/// for (entity, health) in world.query::<&Health>().iter().await {
+///     if health.0 == 0 {
+///         commands.despawn(entity);
+///     }
+/// }
+/// commands.spawn().with(Position::new(0, 64, 0)).build();
+/// ```
+#[derive(Default)]
+pub struct Commands {
+    queue: Mutex<Vec<Command>>,
+}
+
+/// Records the components of an entity spawned through [Commands::spawn].
+pub struct SpawnBuilder<'a> {
+    commands: &'a Commands,
+    components: Vec<Insert>,
+}
+
+impl<'a> SpawnBuilder<'a> {
+    pub fn with<T: Component>(mut self, component: T) -> Self {
+        self.components.push(Box::new(move |entity, storage| {
+            storage.insert(entity, component);
+        }));
+        self
+    }
+
+    /// Records the spawn. The entity is only created once the commands are applied.
+    pub fn build(self) {
+        self.commands.push(Command::Spawn(self.components));
+    }
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&self, command: Command) {
+        self.queue.lock().push(command);
+    }
+
+    /// Records spawning an entity, see [SpawnBuilder].
+    pub fn spawn(&self) -> SpawnBuilder<'_> {
+        SpawnBuilder {
+            commands: self,
+            components: Vec::new(),
+        }
+    }
+
+    /// Records inserting a component, replacing the existing one.
+    pub fn insert<T: Component>(&self, entity: Entity, component: T) {
+        self.push(Command::Insert(
+            entity,
+            Box::new(move |entity, storage| {
+                storage.insert(entity, component);
+            }),
+        ));
+    }
+
+    /// Records removing the entity's `T`.
+    pub fn remove<T: Component>(&self, entity: Entity) {
+        self.push(Command::Remove(
+            entity,
+            Box::new(|entity, storage| storage.remove::<T>(entity)),
+        ));
+    }
+
+    /// Records deleting the entity along with its components.
+    pub fn despawn(&self, entity: Entity) {
+        self.push(Command::Despawn(entity));
+    }
+
+    /// Records inserting a resource, replacing the existing one.
+    pub fn insert_resource<T: Resource>(&self, resource: T) {
+        self.push(Command::InsertResource(Box::new(move |resources| {
+            resources.insert(resource);
+        })));
+    }
+
+    /// The number of recorded commands.
+    pub fn len(&self) -> usize {
+        self.queue.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().is_empty()
+    }
+
+    /// Applies the recorded commands in the order they were recorded. Commands that fail, e.g.
+    /// inserting into a despawned entity, are logged and skipped.
+    pub async fn apply(&self, world: &World) {
+        let queue = std::mem::take(&mut *self.queue.lock());
+        let storage = world.get_component_storage();
+
+        for command in queue {
+            match command {
+                Command::Spawn(components) => {
+                    let entity = world.create_entity().await.build();
+                    for insert in components {
+                        insert(entity, storage);
+                    }
+                }
+                Command::Insert(entity, insert) => {
+                    if world.entity_exists(entity).await {
+                        insert(entity, storage);
+                    } else {
+                        warn!(
+                            "Can't insert a component into entity {}, it doesn't exist",
+                            entity
+                        );
+                    }
+                }
+                Command::Remove(entity, remove) => {
+                    if let Err(e) = remove(entity, storage) {
+                        warn!("Can't remove a component from entity {}: {}", entity, e);
+                    }
+                }
+                Command::Despawn(entity) => {
+                    if let Err(e) = world.delete_entity(entity).await {
+                        warn!("Can't despawn entity {}: {}", entity, e);
+                    }
+                }
+                Command::InsertResource(insert) => insert(storage.resources()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ecs::world::World;
+    use crate::utils::encoding::position::Position;
+    use crate::utils::encoding::velocity::Velocity;
+
+    #[derive(Debug, PartialEq)]
+    struct Spawned(usize);
+
+    #[tokio::test]
+    async fn test_apply() {
+        let world = World::new();
+        let kept = world
+            .create_entity()
+            .await
+            .with(Position::new(0, 0, 0))
+            .build();
+        let despawned = world
+            .create_entity()
+            .await
+            .with(Position::new(1, 1, 1))
+            .build();
+
+        let commands = world.commands();
+        for (entity, position) in world.query::<&Position>().iter().await {
+            if position.x == 1 {
+                commands.despawn(entity);
+            } else {
+                commands.insert(entity, Velocity { x: 1, y: 0, z: 0 });
+            }
+        }
+        commands.spawn().with(Position::new(2, 2, 2)).build();
+        commands.insert_resource(Spawned(1));
+        assert_eq!(commands.len(), 4);
+
+        // Nothing happens until the commands are applied
+        assert!(world.get_component::<Velocity>(kept).await.is_err());
+        assert!(world.entity_exists(despawned).await);
+
+        world.apply_commands().await;
+        assert!(commands.is_empty());
+
+        assert_eq!(world.get_component::<Velocity>(kept).await.unwrap().x, 1);
+        assert!(!world.entity_exists(despawned).await);
+        assert_eq!(world.query::<&Position>().iter().await.count(), 2);
+        assert_eq!(*world.resource::<Spawned>().await.unwrap(), Spawned(1));
+
+        commands.remove::<Velocity>(kept);
+        world.apply_commands().await;
+        assert!(world.get_component::<Velocity>(kept).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stale_commands() {
+        let world = World::new();
+        let entity = world.create_entity().await.build();
+
+        world.commands().despawn(entity);
+        world.commands().despawn(entity);
+        world.commands().insert(entity, Position::new(0, 0, 0));
+        world.apply_commands().await;
+
+        assert!(!world.entity_exists(entity).await);
+        assert_eq!(world.entity_count().await, 0);
+    }
+}
@@ -2,11 +2,18 @@ use crate::utils::prelude::*;
 use std::any::TypeId;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
+use crate::ecs::entity::Entity;
 use crate::ecs::error::Error;
 use crate::ecs::helpers::sparse_set::SparseSet;
+use crate::ecs::hooks::{ComponentHooks, ErasedHooks, LifecycleEvent, LifecycleEvents};
+use crate::ecs::resource::Resources;
+use crate::events::creation::registry::HandlerTable;
 use dashmap::DashMap;
 use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tracing::debug;
 
 /// A trait for components in the ECS.
 pub trait Component: 'static + Send + Sync + Debug {}
@@ -24,7 +31,8 @@ pub struct ComponentRef<'a, T: Component + 'a> {
     _phantom: PhantomData<T>,
 }
 
-/// A mutable reference to a component.
+/// A mutable reference to a component. Mutating the component through it marks the component as
+/// changed, with a new change stamp, see [ComponentStorage::change_tick].
 ///
 /// # Examples
 /// ```ignore
@@ -34,6 +42,8 @@ pub struct ComponentRef<'a, T: Component + 'a> {
 #[derive(Debug)]
 pub struct ComponentRefMut<'a, T: Component> {
     write_guard: RwLockWriteGuard<'a, Box<dyn Component>>,
+    changed: &'a AtomicU64,
+    stamps: &'a AtomicU64,
     _phantom: PhantomData<T>,
 }
 
@@ -54,13 +64,70 @@ impl<'id, T: Component> std::ops::Deref for ComponentRefMut<'id, T> {
 
 impl<'id, T: Component> std::ops::DerefMut for ComponentRefMut<'id, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
+        self.changed
+            .store(next_stamp(self.stamps), Ordering::Relaxed);
         unsafe { &mut *(&mut **self.write_guard as *mut dyn Component as *mut T) }
     }
 }
 
+/// A component, along with the generation of the entity it belongs to and the change stamps it was
+/// added and last changed with.
+struct ComponentCell {
+    value: RwLock<Box<dyn Component>>,
+    generation: u32,
+    added: u64,
+    changed: AtomicU64,
+}
+
+impl ComponentCell {
+    /// The change stamp the component was added with.
+    pub fn added_tick(&self) -> u64 {
+        self.added
+    }
+
+    /// The change stamp the component was last mutably dereferenced with.
+    pub fn changed_tick(&self) -> u64 {
+        self.changed.load(Ordering::Relaxed)
+    }
+}
+
+fn next_stamp(stamps: &AtomicU64) -> u64 {
+    stamps.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+/// Looks up the entity's cell, checking that it belongs to this generation of the entity.
+fn get_cell(storage: &SparseSet<ComponentCell>, entity: Entity) -> Result<&ComponentCell> {
+    let cell = storage.get(entity.into()).ok_or(Error::ComponentNotFound)?;
+    if cell.generation != entity.generation {
+        return Err(Error::EntityNotFound(entity).into());
+    }
+    Ok(cell)
+}
+
 /// A storage structure for components in the ECS.
+///
+/// Components are keyed by entity id, and remember the generation of the entity they belong to,
+/// so a handle to a deleted entity can't reach the components of the entity that reused its id.
+///
+/// Every insert and mutation stamps the component with a new, increasing change stamp, which is
+/// what the `Added` and `Changed` query filters look at. The change tick is the stamp the current
+/// tick started at, and only the tick loop starts a new one, after every tick.
+///
+/// Inserting and removing components fires the type's [ComponentHooks], and records
+/// `ComponentAdded` and `ComponentRemoved` events for the event handlers listening for them.
+///
+/// The world's [Resources] live here too, so queries can fetch them alongside components.
 pub struct ComponentStorage {
-    storages: DashMap<TypeId, SparseSet<RwLock<Box<dyn Component>>>>,
+    storages: DashMap<TypeId, SparseSet<ComponentCell>>,
+    /// The last change stamp handed out.
+    stamps: AtomicU64,
+    change_tick: AtomicU64,
+    resources: Resources,
+    hooks: DashMap<TypeId, Arc<dyn ErasedHooks>>,
+    events: LifecycleEvents,
+    /// The generation each deleted entity's id is reused with, so inserts through a handle to a
+    /// deleted entity are ignored even while no component is left in its slot.
+    deleted: DashMap<u32, u32>,
 }
 
 // New + Insert
@@ -69,26 +136,127 @@ impl ComponentStorage {
     pub fn new() -> Self {
         Self {
             storages: DashMap::new(),
+            stamps: AtomicU64::new(0),
+            change_tick: AtomicU64::new(0),
+            resources: Resources::new(),
+            hooks: DashMap::new(),
+            events: LifecycleEvents::default(),
+            deleted: DashMap::new(),
         }
     }
 
-    /// Inserts a component for a given entity.
+    /// The world's resources, see [Resources].
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// The current change tick, i.e. the change stamp the current tick started at. Changes made
+    /// since have a stamp at least as big.
+    pub fn change_tick(&self) -> u64 {
+        self.change_tick.load(Ordering::Relaxed)
+    }
+
+    /// Starts a new change tick, returning it.
+    pub fn increment_change_tick(&self) -> u64 {
+        let tick = next_stamp(&self.stamps);
+        self.change_tick.store(tick, Ordering::Relaxed);
+        tick
+    }
+
+    /// The last change stamp handed out. Changes made afterwards get a bigger one.
+    pub fn last_change_stamp(&self) -> u64 {
+        self.stamps.load(Ordering::Relaxed)
+    }
+
+    /// Inserts a component for a given entity. Replacing an existing component counts as changing
+    /// it, not adding it. Inserting through a handle to a deleted entity does nothing, whether or
+    /// not its id has been reused.
     ///
     /// # Examples
     /// ```ignore
     /// let storage = ComponentStorage::new();
-    /// storage.insert(0, Position { x: 0.0, y: 0.0 });
+    /// storage.insert(entity, Position { x: 0.0, y: 0.0 });
     /// ```
-    pub fn insert<T: Component>(&self, entity_id: impl TryInto<usize>, component: T) -> &Self {
-        let entity_id = entity_id
-            .try_into()
-            .map_err(|_| Error::ConversionError)
-            .unwrap();
+    pub fn insert<T: Component>(&self, entity: Entity, component: T) -> &Self {
         let type_id = TypeId::of::<T>();
-        let mut storage = self.storages.entry(type_id).or_insert_with(SparseSet::new);
-        storage.insert(entity_id, RwLock::new(Box::new(component)));
+        let hooks = self.hooks_for::<T>();
+        let watches_add = hooks.watches_add(&self.events);
+
+        let stamp = next_stamp(&self.stamps);
+        let (previous, copy) = {
+            let mut storage = self.storages.entry(type_id).or_insert_with(SparseSet::new);
+            // Checked with the storage locked, so a `remove_all` that already marked the entity
+            // as deleted can't miss the component.
+            let stale = storage
+                .get(entity.into())
+                .is_some_and(|existing| existing.generation > entity.generation)
+                || self
+                    .deleted
+                    .get(&entity.id)
+                    .is_some_and(|next| *next > entity.generation);
+            if stale {
+                debug!("Not inserting a component for stale entity {}", entity);
+                return self;
+            }
+            let existing = get_cell(&storage, entity).ok();
+            // Copied before it's moved into the storage, while it's still known to be new
+            let copy = match existing {
+                None if watches_add => hooks.copy_added(&component),
+                _ => None,
+            };
+            let added = existing.map_or(stamp, |existing| existing.added);
+            let cell = ComponentCell {
+                value: RwLock::new(Box::new(component)),
+                generation: entity.generation,
+                added,
+                changed: AtomicU64::new(stamp),
+            };
+            let previous = match storage.get_mut(entity.into()) {
+                Some(existing) if existing.generation == entity.generation => {
+                    Some(std::mem::replace(existing, cell))
+                }
+                _ => {
+                    storage.insert(entity.into(), cell);
+                    None
+                }
+            };
+            (previous, copy)
+        };
+
+        if previous.is_none() && watches_add {
+            hooks.added(entity, copy, &self.events);
+        }
+        if let Some(previous) = previous {
+            hooks.replaced(entity, previous.value.into_inner());
+        }
         self
     }
+
+    /// Sets the hooks fired for components of type `T`, replacing the existing ones.
+    pub fn register_hooks<T: Component>(&self, hooks: ComponentHooks<T>) {
+        self.hooks.insert(TypeId::of::<T>(), Arc::new(hooks));
+    }
+
+    fn hooks_for<T: Component>(&self) -> Arc<dyn ErasedHooks> {
+        if let Some(hooks) = self.hooks.get(&TypeId::of::<T>()) {
+            return hooks.clone();
+        }
+        self.hooks
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Arc::new(ComponentHooks::<T>::new()))
+            .clone()
+    }
+
+    /// Only records the `ComponentAdded` and `ComponentRemoved` events these handlers listen for,
+    /// instead of the ones registered with `#[event_handler]`. Can only be set once.
+    pub fn set_event_handlers(&self, handlers: Arc<HandlerTable>) {
+        self.events.set_handlers(handlers);
+    }
+
+    /// Takes the `ComponentAdded` and `ComponentRemoved` events recorded since the last call.
+    pub fn take_events(&self) -> Vec<LifecycleEvent> {
+        self.events.take()
+    }
 }
 
 impl Default for ComponentStorage {
@@ -103,26 +271,22 @@ impl ComponentStorage {
     ///
     /// # Examples
     /// ```ignore
-    /// let position = storage.get::<Position>(0).await.unwrap();
+    /// let position = storage.get::<Position>(entity).await.unwrap();
     /// assert_eq!(position.x, 0.0);
     /// ```
-    pub async fn get<'a, T: Component + 'a>(
-        &self,
-        entity_id: impl TryInto<usize>,
-    ) -> Result<ComponentRef<'a, T>> {
+    pub async fn get<'a, T: Component + 'a>(&self, entity: Entity) -> Result<ComponentRef<'a, T>> {
         let type_id = TypeId::of::<T>();
-        let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
         let storage = self
             .storages
             .get(&type_id)
             .ok_or(Error::ComponentNotFound)?;
-        let component = storage.get(entity_id).ok_or(Error::ComponentNotFound)?;
+        let component = get_cell(&storage, entity)?;
 
         let read_guard = unsafe {
             std::mem::transmute::<
                 RwLockReadGuard<'_, Box<dyn Component>>,
                 RwLockReadGuard<'_, Box<dyn Component>>,
-            >(component.read().await)
+            >(component.value.read().await)
         };
 
         Ok(ComponentRef {
@@ -135,22 +299,20 @@ impl ComponentStorage {
     ///
     /// # Examples
     /// ```ignore
-    /// let mut position = storage.get_mut::<Position>(0).await.unwrap();
+    /// let mut position = storage.get_mut::<Position>(entity).await.unwrap();
     /// position.x = 1.0;
     /// ```
-    pub async fn get_mut<T: Component>(
-        &self,
-        entity_id: impl TryInto<usize>,
-    ) -> Result<ComponentRefMut<T>> {
+    pub async fn get_mut<T: Component>(&self, entity: Entity) -> Result<ComponentRefMut<'_, T>> {
         let type_id = TypeId::of::<T>();
-        let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
         let storage = self
             .storages
             .get(&type_id)
             .ok_or(Error::ComponentNotFound)?;
-        let component = storage.get(entity_id).ok_or(Error::ComponentNotFound)?;
+        let component = get_cell(&storage, entity)?;
 
-        let write = component.write().await;
+        let write = component.value.write().await;
+        // Lives as long as the write guard, which borrows from the same cell.
+        let changed = unsafe { &*(&component.changed as *const AtomicU64) };
 
         let write_guard = unsafe {
             std::mem::transmute::<
@@ -161,49 +323,94 @@ impl ComponentStorage {
 
         Ok(ComponentRefMut {
             write_guard,
+            changed,
+            stamps: &self.stamps,
             _phantom: PhantomData,
         })
     }
 }
 
+// Contains + Count + Entities + AddedTick + ChangedTick, none of which lock a component
+impl ComponentStorage {
+    /// Whether the entity has a component of type `T`.
+    pub fn contains<T: Component>(&self, entity: Entity) -> bool {
+        self.contains_type(TypeId::of::<T>(), entity)
+    }
+
+    /// Whether the entity has a component with the given type id.
+    pub fn contains_type(&self, type_id: TypeId, entity: Entity) -> bool {
+        self.storages
+            .get(&type_id)
+            .is_some_and(|storage| get_cell(&storage, entity).is_ok())
+    }
+
+    /// The number of components with the given type id.
+    pub fn count_type(&self, type_id: TypeId) -> usize {
+        self.storages
+            .get(&type_id)
+            .map_or(0, |storage| storage.len())
+    }
+
+    /// The entities that have a component with the given type id, in storage order.
+    pub fn entities_with_type(&self, type_id: TypeId) -> Vec<Entity> {
+        self.storages
+            .get(&type_id)
+            .map_or_else(Vec::new, |storage| {
+                storage
+                    .iter()
+                    .map(|(id, cell)| Entity::new(*id as u32, cell.generation))
+                    .collect()
+            })
+    }
+
+    /// The change stamp the entity's `T` was added with, if it has one.
+    pub fn added_tick<T: Component>(&self, entity: Entity) -> Option<u64> {
+        let storage = self.storages.get(&TypeId::of::<T>())?;
+        get_cell(&storage, entity)
+            .ok()
+            .map(ComponentCell::added_tick)
+    }
+
+    /// The change stamp the entity's `T` was last changed with, if it has one.
+    pub fn changed_tick<T: Component>(&self, entity: Entity) -> Option<u64> {
+        let storage = self.storages.get(&TypeId::of::<T>())?;
+        get_cell(&storage, entity)
+            .ok()
+            .map(ComponentCell::changed_tick)
+    }
+}
+
 // GetOrInsertWith + GetMutOrInsertWith
 impl ComponentStorage {
     pub async fn get_or_insert_with<'a, T: Component + 'a>(
         &self,
-        entity_id: impl Into<usize>,
+        entity: Entity,
         f: impl FnOnce() -> T,
     ) -> ComponentRef<'a, T> {
-        let entity_id = entity_id.into();
-
-        if let Ok(component) = self.get::<T>(entity_id).await {
+        if let Ok(component) = self.get::<T>(entity).await {
             return component;
         }
 
         let value = f();
 
-        self.insert(entity_id, value)
-            .get::<T>(entity_id)
+        self.insert(entity, value)
+            .get::<T>(entity)
             .await
             .expect("Component should've been inserted. Please report this as a bug.")
     }
     pub async fn get_mut_or_insert_with<T: Component>(
         &self,
-        entity_id: impl TryInto<usize>,
+        entity: Entity,
         f: impl FnOnce() -> T,
-    ) -> ComponentRefMut<T> {
-        let entity_id = entity_id
-            .try_into()
-            .ok()
-            .expect("Failed to convert entity_id to usize. This is a BUG!");
-
-        if let Ok(component) = self.get_mut::<T>(entity_id).await {
+    ) -> ComponentRefMut<'_, T> {
+        if let Ok(component) = self.get_mut::<T>(entity).await {
             return component;
         }
 
         let value = f();
 
-        self.insert(entity_id, value)
-            .get_mut::<T>(entity_id)
+        self.insert(entity, value)
+            .get_mut::<T>(entity)
             .await
             .expect("Component should've been inserted. Please report this as a bug.")
     }
@@ -215,30 +422,60 @@ impl ComponentStorage {
     ///
     /// # Examples
     /// ```ignore
-    /// storage.remove::<Position>(0);
+    /// storage.remove::<Position>(entity);
     /// ```
-    pub fn remove<T: Component>(&self, entity_id: impl Into<usize>) -> Result<()> {
+    pub fn remove<T: Component>(&self, entity: Entity) -> Result<()> {
         let type_id = TypeId::of::<T>();
-        let entity_id = entity_id.into();
-        if let Some(mut storage) = self.storages.get_mut(&type_id) {
-            let component = storage.get(entity_id);
-            let Some(component) = component else {
-                return Err(Error::ComponentNotFound)?;
-            };
-            if component.try_write().is_err() {
-                return Err(Error::ComponentLocked)?;
+        let removed = match self.storages.get_mut(&type_id) {
+            Some(mut storage) => {
+                let component = get_cell(&storage, entity)?;
+                if component.value.try_write().is_err() {
+                    return Err(Error::ComponentLocked.into());
+                }
+                storage.remove(entity.into())
             }
-            storage.remove(entity_id);
+            None => None,
+        };
+
+        if let Some(removed) = removed {
+            self.hooks_for::<T>()
+                .removed(entity, removed.value.into_inner(), false, &self.events);
         }
 
         Ok(())
     }
-    pub fn remove_all(&self, entity_id: impl Into<usize>) {
-        let entity_id = entity_id.into();
+
+    /// Removes all of the entity's components, firing their hooks as despawned. The entity is
+    /// deleted, so components inserted through its handle afterwards are ignored.
+    pub fn remove_all(&self, entity: Entity) {
+        // Before removing anything, so a concurrent insert either sees it or is removed below
+        self.deleted
+            .entry(entity.id)
+            .and_modify(|next| *next = (*next).max(entity.generation + 1))
+            .or_insert(entity.generation + 1);
+
+        let mut removed = Vec::new();
         for mut storage in self.storages.iter_mut() {
-            storage.remove(entity_id);
+            if get_cell(&storage, entity).is_ok() {
+                if let Some(cell) = storage.remove(entity.into()) {
+                    removed.push((*storage.key(), cell));
+                }
+            }
+        }
+
+        for (type_id, cell) in removed {
+            // Every type that was ever inserted has hooks
+            if let Some(hooks) = self.hooks.get(&type_id).map(|hooks| hooks.clone()) {
+                hooks.removed(entity, cell.value.into_inner(), true, &self.events);
+            }
         }
     }
+
+    /// Forgets which entities were deleted, for when the entities are replaced with ones that can
+    /// have older generations, like when restoring a snapshot.
+    pub(crate) fn forget_deleted(&self) {
+        self.deleted.clear();
+    }
 }
 
 #[cfg(test)]
@@ -251,7 +488,7 @@ mod tests {
     async fn test_basic_usage() {
         let component_storage = ComponentStorage::new();
 
-        let entity = 0usize;
+        let entity = Entity::new(0, 0);
         let position = Position { x: 0, z: 0, y: 0 };
         component_storage.insert(entity, position);
 
@@ -263,8 +500,8 @@ mod tests {
     #[tokio::test]
     async fn test_insert_and_get() {
         let storage = ComponentStorage::new();
-        storage.insert(0usize, Position { x: 0, y: 0, z: 0 });
-        let component = storage.get::<Position>(0usize).await;
+        storage.insert(Entity::new(0, 0), Position { x: 0, y: 0, z: 0 });
+        let component = storage.get::<Position>(Entity::new(0, 0)).await;
         assert!(component.is_ok());
         assert_eq!(component.unwrap().x, 0);
     }
@@ -272,9 +509,62 @@ mod tests {
     #[tokio::test]
     async fn test_insert_and_get_mut() {
         let storage = ComponentStorage::new();
-        storage.insert(0usize, Position { x: 0, y: 0, z: 0 });
-        let component = storage.get_mut::<Position>(0usize).await;
+        storage.insert(Entity::new(0, 0), Position { x: 0, y: 0, z: 0 });
+        let component = storage.get_mut::<Position>(Entity::new(0, 0)).await;
         assert!(component.is_ok());
         assert_eq!(component.unwrap().x, 0);
     }
+
+    #[tokio::test]
+    async fn test_change_ticks() {
+        let storage = ComponentStorage::new();
+        storage.insert(Entity::new(0, 0), Position { x: 0, y: 0, z: 0 });
+        let inserted = storage.last_change_stamp();
+        assert!(inserted > storage.change_tick());
+        assert_eq!(
+            storage.added_tick::<Position>(Entity::new(0, 0)),
+            Some(inserted)
+        );
+        assert_eq!(
+            storage.changed_tick::<Position>(Entity::new(0, 0)),
+            Some(inserted)
+        );
+
+        let tick = storage.increment_change_tick();
+        assert_eq!(storage.change_tick(), tick);
+        assert!(tick > inserted);
+        {
+            // Only reading through a mutable reference doesn't count as a change.
+            let position = storage
+                .get_mut::<Position>(Entity::new(0, 0))
+                .await
+                .unwrap();
+            assert_eq!(position.x, 0);
+        }
+        assert_eq!(
+            storage.changed_tick::<Position>(Entity::new(0, 0)),
+            Some(inserted)
+        );
+
+        storage
+            .get_mut::<Position>(Entity::new(0, 0))
+            .await
+            .unwrap()
+            .x = 5;
+        let changed = storage.changed_tick::<Position>(Entity::new(0, 0)).unwrap();
+        assert!(changed > tick);
+
+        storage.increment_change_tick();
+        storage.insert(Entity::new(0, 0), Position { x: 1, y: 1, z: 1 });
+        assert_eq!(
+            storage.added_tick::<Position>(Entity::new(0, 0)),
+            Some(inserted)
+        );
+        assert_eq!(
+            storage.changed_tick::<Position>(Entity::new(0, 0)),
+            Some(storage.last_change_stamp())
+        );
+        assert!(storage.contains::<Position>(Entity::new(0, 0)));
+        assert!(!storage.contains::<Position>(Entity::new(1, 0)));
+    }
 }
@@ -1,14 +1,31 @@
 use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
+
 use tokio::sync::RwLock;
 
 /// Represents an entity in the ECS.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+///
+/// The id is reused once the entity is deleted, with the generation incremented, so a handle to a
+/// deleted entity never refers to the entity that took its id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub struct Entity {
     pub id: u32,
     pub generation: u32,
 }
 
+impl Entity {
+    pub const fn new(id: u32, generation: u32) -> Self {
+        Self { id, generation }
+    }
+}
+
+impl std::fmt::Display for Entity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}v{}", self.id, self.generation)
+    }
+}
+
 impl From<Entity> for usize {
     fn from(val: Entity) -> Self {
         val.id as usize
@@ -57,7 +74,8 @@ impl EntityManager {
 
     /// Deletes an entity.
     ///
-    /// Returns `true` if the entity was successfully deleted, `false` otherwise.
+    /// Returns `true` if the entity was successfully deleted, `false` if it doesn't exist, including
+    /// when the handle is from an older generation.
     ///
     /// # Examples
     /// ```ignore
@@ -65,21 +83,16 @@ impl EntityManager {
     /// let entity = manager.create_entity();
     /// assert!(manager.delete_entity(entity));
     /// ```
-    pub async fn delete_entity(&self, entity: impl Into<usize>) -> bool {
-        let entity = entity.into();
+    pub async fn delete_entity(&self, entity: Entity) -> bool {
         let mut inner = self.inner.write().await;
 
-        if inner.free_ids.contains(&(entity as u32)) {
+        if !inner.is_alive(entity) {
             return false;
         }
 
-        if entity < inner.generations.len() {
-            inner.generations[entity] += 1;
-            inner.free_ids.push(entity as u32);
-            true
-        } else {
-            false
-        }
+        inner.generations[entity.id as usize] += 1;
+        inner.free_ids.push(entity.id);
+        true
     }
 
     /// Checks if an entity exists.
@@ -91,9 +104,63 @@ impl EntityManager {
     /// assert!(manager.entity_exists(entity));
     /// ```
     pub async fn entity_exists(&self, entity: Entity) -> bool {
+        self.inner.read().await.is_alive(entity)
+    }
+
+    /// Returns every living entity.
+    pub async fn entities(&self) -> Vec<Entity> {
         let inner = self.inner.read().await;
-        (entity.id as usize) < inner.generations.len()
-            && inner.generations[entity.id as usize] == entity.generation
+        let free = inner
+            .free_ids
+            .iter()
+            .collect::<std::collections::HashSet<_>>();
+        inner
+            .generations
+            .iter()
+            .enumerate()
+            .map(|(id, generation)| Entity::new(id as u32, *generation))
+            .filter(|entity| !free.contains(&entity.id))
+            .collect()
+    }
+
+    /// The handles the free ids will be reused with, i.e. with their next generation.
+    pub async fn free_entities(&self) -> Vec<Entity> {
+        let inner = self.inner.read().await;
+        let mut free = inner
+            .free_ids
+            .iter()
+            .map(|id| Entity::new(*id, inner.generations[*id as usize]))
+            .collect::<Vec<_>>();
+        free.sort_by_key(|entity| entity.id);
+        free
+    }
+
+    /// Replaces every entity with the given living ones, keeping their ids and generations. Every
+    /// other id is free, and reused with the generation in `free` if it's there.
+    pub async fn restore(&self, alive: &[Entity], free: &[Entity]) {
+        let mut inner = self.inner.write().await;
+        let len = alive
+            .iter()
+            .chain(free)
+            .map(|entity| entity.id as usize + 1)
+            .max()
+            .unwrap_or(0)
+            .max(inner.generations.len());
+
+        let mut is_alive = vec![false; len];
+        inner.generations.resize(len, 0);
+        for entity in free {
+            inner.generations[entity.id as usize] = entity.generation;
+        }
+        for entity in alive {
+            inner.generations[entity.id as usize] = entity.generation;
+            is_alive[entity.id as usize] = true;
+        }
+        // Reverse, so the lowest free id is reused first
+        inner.free_ids = (0..len as u32)
+            .rev()
+            .filter(|id| !is_alive[*id as usize])
+            .collect();
     }
 
     /// Returns the number of active entities.
@@ -144,6 +211,13 @@ impl EntityManager {
     }
 }
 
+impl EntityManagerInner {
+    /// Deleting an entity bumps its generation, so a free id never matches a handle that was alive.
+    fn is_alive(&self, entity: Entity) -> bool {
+        self.generations.get(entity.id as usize) == Some(&entity.generation)
+    }
+}
+
 impl Default for EntityManager {
     fn default() -> Self {
         Self::new()
@@ -1,11 +1,15 @@
+use crate::ecs::entity::Entity;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Entity {0} not found")]
-    EntityNotFound(usize),
+    EntityNotFound(Entity),
     #[error("Component not found")]
     ComponentNotFound,
     #[error("Couldn't remove component since it's locked")]
     ComponentLocked,
+    #[error("Resource {0} not found")]
+    ResourceNotFound(&'static str),
     #[error("Conversion error from usize to entity id")]
     ConversionError,
 }
@@ -1,8 +1,9 @@
 use crate::ecs::component::{Component, ComponentStorage};
+use crate::ecs::entity::Entity;
 
 /// A builder for creating and configuring entities in an Entity-Component-System architecture.
 pub struct EntityBuilder<'a> {
-    entity_id: usize,
+    entity: Entity,
     component_storage: &'a ComponentStorage,
 }
 impl<'a> EntityBuilder<'a> {
@@ -10,12 +11,11 @@ impl<'a> EntityBuilder<'a> {
     ///
     /// # Arguments
     ///
-    /// * `entity_id` - A unique identifier for the entity being built.
+    /// * `entity` - The entity being built.
     /// * `component_storage` - A reference to the `ComponentStorage` where components will be stored.
-    pub fn new(entity_id: impl Into<usize>, component_storage: &'a ComponentStorage) -> Self {
-        let entity_id = entity_id.into();
+    pub fn new(entity: Entity, component_storage: &'a ComponentStorage) -> Self {
         EntityBuilder {
-            entity_id,
+            entity,
             component_storage,
         }
     }
@@ -30,7 +30,7 @@ impl<'a> EntityBuilder<'a> {
     ///
     /// The `EntityBuilder` instance, allowing for method chaining.
     pub fn with<T: Component>(self, component: T) -> Self {
-        self.component_storage.insert(self.entity_id, component);
+        self.component_storage.insert(self.entity, component);
         self
     }
 
@@ -38,8 +38,8 @@ impl<'a> EntityBuilder<'a> {
     ///
     /// # Returns
     ///
-    /// The `entity_id` of the built entity.
-    pub fn build(self) -> usize {
-        self.entity_id
+    /// The built entity.
+    pub fn build(self) -> Entity {
+        self.entity
     }
 }
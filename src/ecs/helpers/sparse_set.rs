@@ -152,6 +152,31 @@ impl<T> SparseSet<T> {
         self.dense.iter_mut().map(|(key, value)| (key, value))
     }
 
+    /// Returns the indices in the set, in the order they're stored in.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use crateecs::dsa::sparse_set::SparseSet;
+    /// let mut set = SparseSet::new();
+    /// set.insert(5, 1);
+    /// set.insert(10, 2);
+    /// assert_eq!(set.indices().collect::<Vec<_>>(), vec![5, 10]);
+    /// ```
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.dense.iter().map(|(key, _)| *key)
+    }
+
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    /// Returns `true` if the set contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
     /// Removes all elements from the set.
     ///
     /// # Examples
@@ -0,0 +1,339 @@
+use std::any::TypeId;
+use std::marker::PhantomData;
+use std::sync::{Arc, LazyLock, OnceLock};
+
+use parking_lot::Mutex;
+
+use crate::ecs::component::Component;
+use crate::ecs::entity::Entity;
+use crate::events::creation::event::Event;
+use crate::events::creation::registry::HandlerTable;
+
+type Hook<T> = Box<dyn Fn(Entity, &T) + Send + Sync>;
+
+/// Callbacks fired by [ComponentStorage](crate::ecs::component::ComponentStorage) when components
+/// of type `T` are added, replaced or removed. Registered once per type with
+/// [World::register_component_hooks](crate::ecs::world::World::register_component_hooks).
+///
+/// Hooks run synchronously, right where the component is inserted or removed, without any of the
+/// storage's locks held. Anything async, like notifying other players, belongs in an
+/// `#[event_handler]` for [ComponentAdded] or [ComponentRemoved] instead.
+///
+/// # Examples
+/// ```ignore
+/// world.register_component_hooks(
+///     ComponentHooks::<Position>::new()
+///         .on_add(|entity, position| debug!("{} spawned at {:?}", entity, position))
+///         .on_despawn(|entity, _| debug!("{} despawned", entity)),
+/// );
+/// ```
+pub struct ComponentHooks<T: Component> {
+    on_add: Option<Hook<T>>,
+    /// Copies the added component for [ComponentHooks::on_add], see there.
+    copy_added: Option<fn(&T) -> T>,
+    on_replace: Option<Hook<T>>,
+    on_remove: Option<Hook<T>>,
+    on_despawn: Option<Hook<T>>,
+}
+
+impl<T: Component> ComponentHooks<T> {
+    pub fn new() -> Self {
+        Self {
+            on_add: None,
+            copy_added: None,
+            on_replace: None,
+            on_remove: None,
+            on_despawn: None,
+        }
+    }
+
+    /// Fired when the entity didn't have a `T` yet, right after it's stored. The stored component
+    /// can't be borrowed once the storage is unlocked, so the hook gets a copy of it, made while
+    /// it was stored.
+    pub fn on_add(mut self, hook: impl Fn(Entity, &T) + Send + Sync + 'static) -> Self
+    where
+        T: Clone,
+    {
+        self.on_add = Some(Box::new(hook));
+        self.copy_added = Some(T::clone);
+        self
+    }
+
+    /// Fired when inserting replaces the entity's `T`, with the old component.
+    pub fn on_replace(mut self, hook: impl Fn(Entity, &T) + Send + Sync + 'static) -> Self {
+        self.on_replace = Some(Box::new(hook));
+        self
+    }
+
+    /// Fired when the entity's `T` is removed, including when the entity is despawned.
+    pub fn on_remove(mut self, hook: impl Fn(Entity, &T) + Send + Sync + 'static) -> Self {
+        self.on_remove = Some(Box::new(hook));
+        self
+    }
+
+    /// Fired after [ComponentHooks::on_remove] when the `T` was removed because the entity was
+    /// despawned.
+    pub fn on_despawn(mut self, hook: impl Fn(Entity, &T) + Send + Sync + 'static) -> Self {
+        self.on_despawn = Some(Box::new(hook));
+        self
+    }
+}
+
+impl<T: Component> Default for ComponentHooks<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Event dispatched after a `T` is added to an entity that didn't have one.
+pub struct ComponentAdded<T: Component> {
+    pub entity: Entity,
+    _marker: PhantomData<T>,
+}
+
+/// Event dispatched after an entity's `T` is removed, with the removed component.
+pub struct ComponentRemoved<T: Component> {
+    pub entity: Entity,
+    pub component: T,
+    /// Whether the component was removed because the entity was despawned.
+    pub despawned: bool,
+}
+
+impl<T: Component> Event for ComponentAdded<T> {}
+
+impl<T: Component> Event for ComponentRemoved<T> {}
+
+/// An event recorded by the storage, waiting to be dispatched at the next sync point.
+pub struct LifecycleEvent {
+    /// E.g. a `ComponentRemoved<Player>`.
+    pub event: Box<dyn Event>,
+}
+
+/// The lifecycle events recorded since they were last taken.
+#[derive(Default)]
+pub(crate) struct LifecycleEvents {
+    queue: Mutex<Vec<LifecycleEvent>>,
+    handlers: OnceLock<Arc<HandlerTable>>,
+}
+
+/// The `#[event_handler]`s, for worlds that aren't attached to an `EventDispatcher`, e.g. in tests.
+static DEFAULT_HANDLERS: LazyLock<HandlerTable> = LazyLock::new(HandlerTable::from_registry);
+
+impl LifecycleEvents {
+    /// Only records events the given handlers listen for. Can only be set once.
+    pub(crate) fn set_handlers(&self, handlers: Arc<HandlerTable>) {
+        if self.handlers.set(handlers).is_err() {
+            tracing::warn!("The world's event handlers were already set");
+        }
+    }
+
+    /// Whether anything listens for events with the given type id.
+    pub(crate) fn is_listened(&self, type_id: TypeId) -> bool {
+        match self.handlers.get() {
+            Some(handlers) => handlers.has_handlers(type_id),
+            None => DEFAULT_HANDLERS.has_handlers(type_id),
+        }
+    }
+
+    /// Records the event if anything listens for it.
+    pub(crate) fn push<E: Event>(&self, event: impl FnOnce() -> E) {
+        if self.is_listened(TypeId::of::<E>()) {
+            self.queue.lock().push(LifecycleEvent {
+                event: Box::new(event()),
+            });
+        }
+    }
+
+    pub(crate) fn take(&self) -> Vec<LifecycleEvent> {
+        std::mem::take(&mut *self.queue.lock())
+    }
+}
+
+/// [ComponentHooks] with the component type erased, so the storage can fire them for removals
+/// where it only knows the type id, like despawning an entity.
+pub(crate) trait ErasedHooks: Send + Sync {
+    /// Whether [ErasedHooks::added] has anything to do, so inserts can skip checking whether the
+    /// component is new.
+    fn watches_add(&self, events: &LifecycleEvents) -> bool;
+    /// Copies a new component for [ErasedHooks::added], if there's an `on_add` hook to pass it to.
+    fn copy_added(&self, component: &dyn Component) -> Option<Box<dyn Component>>;
+    /// `copy` is what [ErasedHooks::copy_added] returned for the component.
+    fn added(&self, entity: Entity, copy: Option<Box<dyn Component>>, events: &LifecycleEvents);
+    fn replaced(&self, entity: Entity, previous: Box<dyn Component>);
+    fn removed(
+        &self,
+        entity: Entity,
+        component: Box<dyn Component>,
+        despawned: bool,
+        events: &LifecycleEvents,
+    );
+}
+
+/// # Safety
+/// The component has to be a `T`, which the storage guarantees by keying components by type id.
+unsafe fn downcast_ref<T: Component>(component: &dyn Component) -> &T {
+    &*(component as *const dyn Component as *const T)
+}
+
+/// # Safety
+/// See [downcast_ref].
+unsafe fn downcast<T: Component>(component: Box<dyn Component>) -> T {
+    *Box::from_raw(Box::into_raw(component) as *mut T)
+}
+
+impl<T: Component> ErasedHooks for ComponentHooks<T> {
+    fn watches_add(&self, events: &LifecycleEvents) -> bool {
+        self.on_add.is_some() || events.is_listened(TypeId::of::<ComponentAdded<T>>())
+    }
+
+    fn copy_added(&self, component: &dyn Component) -> Option<Box<dyn Component>> {
+        let copy = self.copy_added?;
+        Some(Box::new(copy(unsafe { downcast_ref::<T>(component) })))
+    }
+
+    fn added(&self, entity: Entity, copy: Option<Box<dyn Component>>, events: &LifecycleEvents) {
+        if let (Some(on_add), Some(copy)) = (&self.on_add, copy) {
+            on_add(entity, &unsafe { downcast::<T>(copy) });
+        }
+        events.push(|| ComponentAdded::<T> {
+            entity,
+            _marker: PhantomData,
+        });
+    }
+
+    fn replaced(&self, entity: Entity, previous: Box<dyn Component>) {
+        if let Some(on_replace) = &self.on_replace {
+            on_replace(entity, unsafe { downcast_ref::<T>(&*previous) });
+        }
+    }
+
+    fn removed(
+        &self,
+        entity: Entity,
+        component: Box<dyn Component>,
+        despawned: bool,
+        events: &LifecycleEvents,
+    ) {
+        let component = unsafe { downcast::<T>(component) };
+        if let Some(on_remove) = &self.on_remove {
+            on_remove(entity, &component);
+        }
+        if despawned {
+            if let Some(on_despawn) = &self.on_despawn {
+                on_despawn(entity, &component);
+            }
+        }
+        events.push(|| ComponentRemoved {
+            entity,
+            component,
+            despawned,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use ferrumc_macros::event_handler;
+
+    use crate::ecs::hooks::{ComponentHooks, ComponentRemoved};
+    use crate::ecs::world::World;
+    use crate::state::GlobalState;
+    use crate::utils::encoding::position::Position;
+
+    #[derive(Debug)]
+    struct Health(u8);
+
+    impl crate::ecs::component::Component for Health {}
+
+    #[event_handler]
+    async fn on_health_removed(_event: &mut ComponentRemoved<Health>, _state: GlobalState) {}
+
+    #[tokio::test]
+    async fn test_hooks() {
+        let world = World::new();
+        let added = Arc::new(AtomicUsize::new(0));
+        let replaced = Arc::new(AtomicUsize::new(0));
+        let removed = Arc::new(AtomicUsize::new(0));
+        let despawned = Arc::new(AtomicUsize::new(0));
+
+        let counter = |count: &Arc<AtomicUsize>| {
+            let count = count.clone();
+            move |_, position: &Position| {
+                count.fetch_add(position.x as usize, Ordering::Relaxed);
+            }
+        };
+        world.register_component_hooks(
+            ComponentHooks::<Position>::new()
+                .on_add(counter(&added))
+                .on_replace(counter(&replaced))
+                .on_remove(counter(&removed))
+                .on_despawn(counter(&despawned)),
+        );
+
+        let first = world
+            .create_entity()
+            .await
+            .with(Position::new(1, 0, 0))
+            .build();
+        let second = world
+            .create_entity()
+            .await
+            .with(Position::new(2, 0, 0))
+            .build();
+        assert_eq!(added.load(Ordering::Relaxed), 3);
+
+        // The hooks see the replaced and removed components
+        world
+            .get_component_storage()
+            .insert(first, Position::new(5, 0, 0));
+        assert_eq!(replaced.load(Ordering::Relaxed), 1);
+        assert_eq!(added.load(Ordering::Relaxed), 3);
+
+        world
+            .get_component_storage()
+            .remove::<Position>(first)
+            .unwrap();
+        world.delete_entity(second).await.unwrap();
+        assert_eq!(removed.load(Ordering::Relaxed), 7);
+        assert_eq!(despawned.load(Ordering::Relaxed), 2);
+
+        // Inserting through a deleted entity's handle doesn't store anything, so nothing is
+        // added, before or after its id is reused
+        let storage = world.get_component_storage();
+        storage.insert(second, Position::new(100, 0, 0));
+        let third = world
+            .create_entity()
+            .await
+            .with(Position::new(10, 0, 0))
+            .build();
+        assert_eq!(third.id, second.id);
+        storage.insert(second, Position::new(100, 0, 0));
+        assert_eq!(added.load(Ordering::Relaxed), 13);
+    }
+
+    #[tokio::test]
+    async fn test_removed_events() {
+        let world = World::new();
+        let entity = world
+            .create_entity()
+            .await
+            .with(Health(20))
+            .with(Position::new(0, 0, 0))
+            .build();
+        world.delete_entity(entity).await.unwrap();
+
+        // Only events with handlers are recorded
+        let mut events = world.take_lifecycle_events();
+        assert_eq!(events.len(), 1);
+        let event: Box<dyn Any> = events.remove(0).event;
+        let event = event.downcast::<ComponentRemoved<Health>>().unwrap();
+        assert_eq!(event.entity, entity);
+        assert_eq!(event.component.0, 20);
+        assert!(event.despawned);
+        assert!(world.take_lifecycle_events().is_empty());
+    }
+}
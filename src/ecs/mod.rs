@@ -1,11 +1,15 @@
 #[cfg(test)]
 use std::sync::OnceLock;
 
+pub mod commands;
 pub mod component;
 pub mod entity;
 pub mod error;
 pub mod helpers;
+pub mod hooks;
 pub mod query;
+pub mod resource;
+pub mod snapshot;
 #[cfg(test)]
 pub mod test;
 #[cfg(test)]
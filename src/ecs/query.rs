@@ -1,28 +1,36 @@
+use std::any::TypeId;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use futures::Stream;
 
 use crate::ecs::component::{Component, ComponentRef, ComponentRefMut, ComponentStorage};
-use crate::ecs::entity::EntityManager;
+use crate::ecs::entity::{Entity, EntityManager};
+use crate::ecs::resource::{Res, ResMut, Resource};
 use crate::utils::prelude::*;
 
 #[allow(async_fn_in_trait)]
 /// Trait for items that can be queried in the ECS.
 pub trait QueryItem {
     type Item<'a>;
-    async fn fetch<'a>(
-        entity_id: impl Into<usize>,
-        storage: &'a ComponentStorage,
-    ) -> Result<Self::Item<'a>>;
+    /// Whether every entity's item holds the same exclusive lock, like [ResMut], so only one of
+    /// them can be alive at a time. [Query::iter] rejects these at compile time.
+    const EXCLUSIVE: bool = false;
+    /// Adds the components an entity has to have for [QueryItem::fetch] to succeed.
+    fn required_components(required: &mut Vec<TypeId>);
+    async fn fetch<'a>(entity: Entity, storage: &'a ComponentStorage) -> Result<Self::Item<'a>>;
 }
 
 // Implement QueryItem for immutable references
 impl<T: Component> QueryItem for &T {
     type Item<'a> = ComponentRef<'a, T>;
 
-    async fn fetch<'a>(
-        entity_id: impl Into<usize>,
-        storage: &'a ComponentStorage,
-    ) -> Result<Self::Item<'a>> {
-        storage.get::<T>(entity_id).await
+    fn required_components(required: &mut Vec<TypeId>) {
+        required.push(TypeId::of::<T>());
+    }
+
+    async fn fetch<'a>(entity: Entity, storage: &'a ComponentStorage) -> Result<Self::Item<'a>> {
+        storage.get::<T>(entity).await
     }
 }
 
@@ -30,24 +38,147 @@ impl<T: Component> QueryItem for &T {
 impl<T: Component> QueryItem for &mut T {
     type Item<'a> = ComponentRefMut<'a, T>;
 
-    async fn fetch<'a>(
-        entity_id: impl Into<usize>,
-        storage: &'a ComponentStorage,
-    ) -> Result<Self::Item<'a>> {
-        storage.get_mut::<T>(entity_id).await
+    fn required_components(required: &mut Vec<TypeId>) {
+        required.push(TypeId::of::<T>());
+    }
+
+    async fn fetch<'a>(entity: Entity, storage: &'a ComponentStorage) -> Result<Self::Item<'a>> {
+        storage.get_mut::<T>(entity).await
+    }
+}
+
+// Resources aren't per entity, every matching entity gets the same one. Entities are skipped while
+// the resource is missing.
+impl<T: Resource> QueryItem for Res<T> {
+    type Item<'a> = Res<T>;
+
+    fn required_components(_required: &mut Vec<TypeId>) {}
+
+    async fn fetch<'a>(_entity: Entity, storage: &'a ComponentStorage) -> Result<Self::Item<'a>> {
+        storage.resources().get::<T>().await
+    }
+}
+
+// The write lock is held until the item is dropped, so each item has to be dropped before the next
+// one is fetched. [Query::iter] fetches all of them up front, so it doesn't accept `ResMut`.
+impl<T: Resource> QueryItem for ResMut<T> {
+    type Item<'a> = ResMut<T>;
+    const EXCLUSIVE: bool = true;
+
+    fn required_components(_required: &mut Vec<TypeId>) {}
+
+    async fn fetch<'a>(_entity: Entity, storage: &'a ComponentStorage) -> Result<Self::Item<'a>> {
+        storage.resources().get_mut::<T>().await
+    }
+}
+
+/// Trait for filters that decide which entities a query matches, without fetching anything.
+///
+/// Filters only look at which components an entity has and their change ticks, so they never wait
+/// on a component's lock.
+pub trait QueryFilter {
+    /// Whether the entity matches. `since` is the change tick `Added` and `Changed` compare against.
+    fn matches(entity: Entity, storage: &ComponentStorage, since: u64) -> bool;
+    /// Adds the components an entity has to have to match.
+    fn required_components(_required: &mut Vec<TypeId>) {}
+}
+
+impl QueryFilter for () {
+    fn matches(_entity: Entity, _storage: &ComponentStorage, _since: u64) -> bool {
+        true
+    }
+}
+
+/// Matches entities that have a `T`.
+pub struct With<T>(PhantomData<T>);
+
+/// Matches entities that don't have a `T`.
+pub struct Without<T>(PhantomData<T>);
+
+/// Matches entities whose `T` was added in or after the query's `since` change tick.
+pub struct Added<T>(PhantomData<T>);
+
+/// Matches entities whose `T` was added or mutated in or after the query's `since` change tick.
+pub struct Changed<T>(PhantomData<T>);
+
+impl<T: Component> QueryFilter for With<T> {
+    fn required_components(required: &mut Vec<TypeId>) {
+        required.push(TypeId::of::<T>());
+    }
+
+    fn matches(entity: Entity, storage: &ComponentStorage, _since: u64) -> bool {
+        storage.contains::<T>(entity)
+    }
+}
+
+impl<T: Component> QueryFilter for Without<T> {
+    fn matches(entity: Entity, storage: &ComponentStorage, _since: u64) -> bool {
+        !storage.contains::<T>(entity)
+    }
+}
+
+impl<T: Component> QueryFilter for Added<T> {
+    fn required_components(required: &mut Vec<TypeId>) {
+        required.push(TypeId::of::<T>());
+    }
+
+    fn matches(entity: Entity, storage: &ComponentStorage, since: u64) -> bool {
+        storage
+            .added_tick::<T>(entity)
+            .is_some_and(|tick| tick >= since)
+    }
+}
+
+impl<T: Component> QueryFilter for Changed<T> {
+    fn required_components(required: &mut Vec<TypeId>) {
+        required.push(TypeId::of::<T>());
+    }
+
+    fn matches(entity: Entity, storage: &ComponentStorage, since: u64) -> bool {
+        storage
+            .changed_tick::<T>(entity)
+            .is_some_and(|tick| tick >= since)
+    }
+}
+
+/// The change stamp a query last ran at, so its `Added` and `Changed` filters match every change
+/// exactly once, including the ones made after it ran in the same tick. Keep one per query, e.g. in
+/// a static next to the tick phase that runs it. See [Query::since_last_run].
+#[derive(Debug)]
+pub struct LastRun(AtomicU64);
+
+impl LastRun {
+    pub const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+}
+
+impl Default for LastRun {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 /// Struct for querying components in the ECS.
-#[derive(Clone, Copy)]
-pub struct Query<'a, Q: QueryItem> {
+///
+/// `F` is a [QueryFilter], e.g. `Query<&Position, (With<Player>, Changed<Position>)>`.
+///
+/// Queries only visit the entities in the smallest storage of the components they require, and
+/// check the rest of the required components without locking them. Only entities that have all of
+/// them get their components fetched. Queries that don't require any component, like
+/// `Query<Option<&Position>>`, visit every living entity instead.
+#[derive(Clone)]
+pub struct Query<'a, Q: QueryItem, F: QueryFilter = ()> {
     entity_manager: &'a EntityManager,
     component_storage: &'a ComponentStorage,
-    current_id: usize,
-    _marker: PhantomData<Q>,
+    required: Vec<TypeId>,
+    /// The entities [Query::next] has left to visit. Filled on the first call.
+    pending: Option<std::vec::IntoIter<Entity>>,
+    since: u64,
+    _marker: PhantomData<(Q, F)>,
 }
 
-impl<'a, Q: QueryItem> Query<'a, Q> {
+impl<'a, Q: QueryItem, F: QueryFilter> Query<'a, Q, F> {
     /// Creates a new Query.
     ///
     /// # Examples
@@ -56,43 +187,147 @@ impl<'a, Q: QueryItem> Query<'a, Q> {
     /// let query = Query::<&Position>::new(&entity_manager, &component_storage);
     /// ```
     pub fn new(entity_manager: &'a EntityManager, component_storage: &'a ComponentStorage) -> Self {
+        let mut required = Vec::new();
+        Q::required_components(&mut required);
+        F::required_components(&mut required);
+        required.sort();
+        required.dedup();
+
         Self {
             entity_manager,
             component_storage,
-            current_id: 0,
+            required,
+            pending: None,
+            since: component_storage.change_tick(),
             _marker: PhantomData,
         }
     }
 
+    /// Makes `Added` and `Changed` filters match changes made in or after the given change tick,
+    /// instead of only those made in the current one.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Everything that moved since the last autosave
+    /// let query = world
+    ///     .query_filtered::<&Position, Changed<Position>>()
+    ///     .since(last_save_tick);
+    /// ```
+    pub fn since(mut self, change_tick: u64) -> Self {
+        self.since = change_tick;
+        self
+    }
+
+    /// Makes `Added` and `Changed` filters match the changes made since the query last ran with
+    /// the same [LastRun], or every change the first time. Unlike the default, changes made after
+    /// the query ran in a tick are matched in its next run.
+    ///
+    /// Doesn't start a new change tick, so other queries aren't affected.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// static LAST_RUN: LastRun = LastRun::new();
+    ///
+    /// let query = world
+    ///     .query_filtered::<&Position, Changed<Position>>()
+    ///     .since_last_run(&LAST_RUN);
+    /// ```
+    pub fn since_last_run(mut self, last_run: &LastRun) -> Self {
+        let now = self.component_storage.last_change_stamp();
+        self.since = last_run.0.swap(now, Ordering::Relaxed) + 1;
+        self
+    }
+
+    /// The entities that might match, taken from the smallest storage of the required components.
+    async fn candidates(&self) -> Vec<Entity> {
+        let smallest = self
+            .required
+            .iter()
+            .min_by_key(|type_id| self.component_storage.count_type(**type_id));
+        match smallest {
+            Some(type_id) => self.component_storage.entities_with_type(*type_id),
+            None => self.entity_manager.entities().await,
+        }
+    }
+
+    /// Fetches the entity's components, if it has all the required ones and passes the filter.
+    async fn fetch_matching(&self, entity: Entity) -> Option<Q::Item<'a>> {
+        let has_required = self
+            .required
+            .iter()
+            .all(|type_id| self.component_storage.contains_type(*type_id, entity));
+        if !has_required || !F::matches(entity, self.component_storage, self.since) {
+            return None;
+        }
+        Q::fetch(entity, self.component_storage).await.ok()
+    }
+
+    /// Returns a stream of the query results, fetching each one as it's polled.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use futures::StreamExt;
+    ///
+    /// let query = Query::<(&mut Position, &Velocity)>::new(&entity_manager, &component_storage);
+    /// let mut results = std::pin::pin!(query.stream());
+    /// while let Some((entity, (mut position, velocity))) = results.next().await {
+    ///     position.x += velocity.x;
+    /// }
+    /// ```
+    pub fn stream(&'a self) -> impl Stream<Item = (Entity, Q::Item<'a>)> + 'a {
+        futures::stream::unfold(
+            None,
+            move |pending: Option<std::vec::IntoIter<Entity>>| async move {
+                let mut pending = match pending {
+                    Some(pending) => pending,
+                    None => self.candidates().await.into_iter(),
+                };
+                loop {
+                    let entity = pending.next()?;
+                    if let Some(item) = self.fetch_matching(entity).await {
+                        return Some(((entity, item), Some(pending)));
+                    }
+                }
+            },
+        )
+    }
+
     /// Returns an iterator over the query results.
     ///
+    /// All results are fetched up front, holding every matching component's lock until the
+    /// iterator is dropped. Use [Query::next] or [Query::stream] to fetch them one at a time, which
+    /// queries with a [ResMut] have to.
+    ///
     /// # Examples
     ///
     /// ```ignore
     /// // Query for positions
     /// let query = Query::<&Position>::new(&entity_manager, &component_storage);
-    /// for (entity_id, position) in query.iter().await {
-    ///     println!("Entity {} is at position {:?}", entity_id, position);
+    /// for (entity, position) in query.iter().await {
+    ///     println!("Entity {} is at position {:?}", entity, position);
     /// }
     ///
     /// // Query for mutable positions and velocities
     /// let query = Query::<(&mut Position, &Velocity)>::new(&entity_manager, &component_storage);
-    /// for (entity_id, (mut position, velocity)) in query.iter().await {
+    /// for (entity, (mut position, velocity)) in query.iter().await {
     ///     position.x += velocity.x;
     ///     position.y += velocity.y;
     /// }
     /// ```
-    pub async fn iter(&'a self) -> impl Iterator<Item = (usize, Q::Item<'a>)> + 'a {
-        let max_entity_id = self.entity_manager.len().await;
-        let mut results = vec![];
+    pub async fn iter(&'a self) -> impl Iterator<Item = (Entity, Q::Item<'a>)> + 'a {
+        use futures::StreamExt;
 
-        for entity_id in 0..=max_entity_id {
-            if let Ok(item) = Q::fetch(entity_id, self.component_storage).await {
-                results.push((entity_id, item));
-            }
-        }
+        const {
+            assert!(
+                !Q::EXCLUSIVE,
+                "Query::iter would hold a ResMut for every entity at once, use Query::next or Query::stream"
+            )
+        };
 
-        results.into_iter()
+        self.stream().collect::<Vec<_>>().await.into_iter()
     }
 
     /// Returns the next query result.
@@ -105,25 +340,26 @@ impl<'a, Q: QueryItem> Query<'a, Q> {
     /// # use ferrumc::utils::encoding::velocity::Velocity;
     /// # async fn doc() {
     /// let mut query = Query::<(&Position, &Velocity)>::new(&entity_manager, &component_storage);
-    /// while let Some((entity_id, (position, velocity))) = query.next().await {
-    ///     println!("Entity {} is at {:?} moving at {:?}", entity_id, position, velocity);
+    /// while let Some((entity, (position, velocity))) = query.next().await {
+    ///     println!("Entity {} is at {:?} moving at {:?}", entity, position, velocity);
     /// }
     /// # }
     /// ```
-    pub async fn next<'b>(&mut self) -> Option<(usize, Q::Item<'b>)>
+    pub async fn next<'b>(&mut self) -> Option<(Entity, Q::Item<'b>)>
     where
         'a: 'b, // 'a must outlive 'b
     {
-        let max_entity_id = self.entity_manager.len().await;
-        while self.current_id <= max_entity_id {
-            if let Ok(item) = Q::fetch(self.current_id, self.component_storage).await {
-                let result = Some((self.current_id, item));
-                self.current_id += 1;
-                return result;
+        let mut pending = match self.pending.take() {
+            Some(pending) => pending,
+            None => self.candidates().await.into_iter(),
+        };
+        while let Some(entity) = pending.next() {
+            if let Some(item) = self.fetch_matching(entity).await {
+                self.pending = Some(pending);
+                return Some((entity, item));
             }
-            self.current_id += 1;
         }
-        self.current_id = 0;
+        // Start over on the next call
         None
     }
 }
@@ -136,12 +372,16 @@ macro_rules! impl_query_item_tuple {
             $($T: QueryItem,)*
         {
             type Item<'a> = ($($T::Item<'a>,)*);
+            const EXCLUSIVE: bool = $($T::EXCLUSIVE)||*;
 
-            async fn fetch<'a>(entity_id: impl Into<usize>, storage: &'a ComponentStorage) -> Result<Self::Item<'a>> {
-                let entity_id = entity_id.into();
+            fn required_components(required: &mut Vec<TypeId>) {
+                $($T::required_components(required);)*
+            }
+
+            async fn fetch<'a>(entity: Entity, storage: &'a ComponentStorage) -> Result<Self::Item<'a>> {
                 Ok((
                     $(
-                        $T::fetch(entity_id, storage).await?,
+                        $T::fetch(entity, storage).await?,
                     )*
                 ))
             }
@@ -149,6 +389,29 @@ macro_rules! impl_query_item_tuple {
     };
 }
 
+// Filters in a tuple all have to match
+macro_rules! impl_query_filter_tuple {
+    ($($T: ident), *) => {
+        impl<$($T),*> QueryFilter for ($($T,)*)
+        where
+            $($T: QueryFilter,)*
+        {
+            fn matches(entity: Entity, storage: &ComponentStorage, since: u64) -> bool {
+                $($T::matches(entity, storage, since))&&*
+            }
+
+            fn required_components(required: &mut Vec<TypeId>) {
+                $($T::required_components(required);)*
+            }
+        }
+    };
+}
+
+impl_query_filter_tuple!(A);
+impl_query_filter_tuple!(A, B);
+impl_query_filter_tuple!(A, B, C);
+impl_query_filter_tuple!(A, B, C, D);
+
 impl_query_item_tuple!(A);
 impl_query_item_tuple!(A, B);
 impl_query_item_tuple!(A, B, C);
@@ -162,13 +425,16 @@ mod helpers {
     // optional query
     impl<T: QueryItem> QueryItem for Option<T> {
         type Item<'a> = Option<T::Item<'a>>;
+        const EXCLUSIVE: bool = T::EXCLUSIVE;
+
+        // An optional component isn't required
+        fn required_components(_required: &mut Vec<TypeId>) {}
 
         async fn fetch<'a>(
-            entity_id: impl Into<usize>,
+            entity: Entity,
             storage: &'a ComponentStorage,
         ) -> Result<Self::Item<'a>> {
-            let entity_id = entity_id.into();
-            let component = T::fetch(entity_id, storage).await;
+            let component = T::fetch(entity, storage).await;
             Ok(component.ok())
         }
     }
@@ -179,16 +445,19 @@ mod helpers {
 #[cfg(test)]
 mod tests {
     use crate::ecs::component::ComponentStorage;
-    use crate::ecs::entity::EntityManager;
-    use crate::ecs::query::{Query, QueryItem};
+    use crate::ecs::entity::{Entity, EntityManager};
+    use crate::ecs::query::{Added, Changed, LastRun, Query, QueryItem, With, Without};
+    use crate::ecs::resource::{Res, ResMut};
+    use crate::utils::components::rotation::Rotation;
     use crate::utils::encoding::position::Position;
     use crate::utils::encoding::velocity::Velocity;
+    use futures::StreamExt;
 
     #[tokio::test]
     async fn test_immut_query() {
         let storage = ComponentStorage::new();
-        storage.insert(0usize, Position { x: 0, y: 0, z: 0 });
-        let component = <&Position as QueryItem>::fetch(0usize, &storage).await;
+        storage.insert(Entity::new(0, 0), Position { x: 0, y: 0, z: 0 });
+        let component = <&Position as QueryItem>::fetch(Entity::new(0, 0), &storage).await;
         assert!(component.is_ok());
         assert_eq!(component.unwrap().x, 0);
     }
@@ -202,10 +471,10 @@ mod tests {
             entity_manager.create_entity().await;
         }
 
-        storage.insert(0usize, Position { x: 0, y: 0, z: 0 });
-        storage.insert(0usize, Velocity { x: 2, y: 0, z: 0 });
-        storage.insert(1usize, Position { x: 1, y: 1, z: 1 });
-        storage.insert(1usize, Velocity { x: 2, y: 2, z: 2 });
+        storage.insert(Entity::new(0, 0), Position { x: 0, y: 0, z: 0 });
+        storage.insert(Entity::new(0, 0), Velocity { x: 2, y: 0, z: 0 });
+        storage.insert(Entity::new(1, 0), Position { x: 1, y: 1, z: 1 });
+        storage.insert(Entity::new(1, 0), Velocity { x: 2, y: 2, z: 2 });
 
         let query = Query::<(&mut Position, &Velocity)>::new(&entity_manager, &storage);
 
@@ -221,4 +490,194 @@ mod tests {
             println!("Entity {}: {:?}", entity_id, *pos);
         }
     }
+
+    #[tokio::test]
+    async fn test_filters() {
+        let storage = ComponentStorage::new();
+        let entity_manager = EntityManager::new();
+
+        for _ in 0..=2 {
+            entity_manager.create_entity().await;
+        }
+
+        storage.insert(Entity::new(0, 0), Position { x: 0, y: 0, z: 0 });
+        storage.insert(Entity::new(0, 0), Velocity { x: 1, y: 0, z: 0 });
+        storage.insert(Entity::new(1, 0), Position { x: 1, y: 1, z: 1 });
+        storage.insert(Entity::new(2, 0), Position { x: 2, y: 2, z: 2 });
+        storage.insert(Entity::new(2, 0), Rotation::new(0f32, 0f32));
+
+        let with = Query::<&Position, With<Velocity>>::new(&entity_manager, &storage);
+        let ids = with.iter().await.map(|(e, _)| e.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![0]);
+
+        let without = Query::<&Position, (Without<Velocity>, Without<Rotation>)>::new(
+            &entity_manager,
+            &storage,
+        );
+        let ids = without.iter().await.map(|(e, _)| e.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![1]);
+
+        // Everything was added in change tick 0
+        let added = Query::<&Position, Added<Position>>::new(&entity_manager, &storage);
+        assert_eq!(added.iter().await.count(), 3);
+
+        // Only the current tick by default
+        storage.increment_change_tick();
+        let added = Query::<&Position, Added<Position>>::new(&entity_manager, &storage);
+        assert_eq!(added.iter().await.count(), 0);
+
+        storage.increment_change_tick();
+        storage
+            .get_mut::<Position>(Entity::new(2, 0))
+            .await
+            .unwrap()
+            .x += 1;
+        {
+            // Not mutated, so not changed
+            let _ = storage
+                .get_mut::<Position>(Entity::new(1, 0))
+                .await
+                .unwrap();
+        }
+
+        let added = Query::<&Position, Added<Position>>::new(&entity_manager, &storage);
+        assert_eq!(added.iter().await.count(), 0);
+
+        let mut changed = Query::<&Position, Changed<Position>>::new(&entity_manager, &storage);
+        let mut ids = vec![];
+        while let Some((entity, _)) = changed.next().await {
+            ids.push(entity.id);
+        }
+        assert_eq!(ids, vec![2]);
+
+        let changed =
+            Query::<&Position, Changed<Position>>::new(&entity_manager, &storage).since(0);
+        assert_eq!(changed.iter().await.count(), 3);
+    }
+
+    async fn changed_ids(query: Query<'_, &Position, Changed<Position>>) -> Vec<u32> {
+        query.iter().await.map(|(e, _)| e.id).collect()
+    }
+
+    #[tokio::test]
+    async fn test_changes_after_query_ran() {
+        let storage = ComponentStorage::new();
+        let entity_manager = EntityManager::new();
+        for id in 0..2 {
+            entity_manager.create_entity().await;
+            storage.insert(Entity::new(id, 0), Position { x: 0, y: 0, z: 0 });
+        }
+        storage.increment_change_tick();
+
+        let last_run = LastRun::new();
+        let tracked = || {
+            Query::<&Position, Changed<Position>>::new(&entity_manager, &storage)
+                .since_last_run(&last_run)
+        };
+        // Everything the first time
+        assert_eq!(changed_ids(tracked()).await, vec![0, 1]);
+
+        // Moved by a packet handler after the query ran in this tick
+        storage
+            .get_mut::<Position>(Entity::new(1, 0))
+            .await
+            .unwrap()
+            .x += 1;
+        storage.increment_change_tick();
+
+        // The tracked query sees it in the next tick, the default only sees the current tick
+        let changed = Query::<&Position, Changed<Position>>::new(&entity_manager, &storage);
+        assert!(changed_ids(changed).await.is_empty());
+        assert_eq!(changed_ids(tracked()).await, vec![1]);
+
+        // And only once
+        storage.increment_change_tick();
+        assert!(changed_ids(tracked()).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_independent_queries_in_one_tick() {
+        let storage = ComponentStorage::new();
+        let entity_manager = EntityManager::new();
+        for id in 0..2 {
+            entity_manager.create_entity().await;
+            storage.insert(Entity::new(id, 0), Position { x: 0, y: 0, z: 0 });
+        }
+        storage.increment_change_tick();
+        let tick = storage.change_tick();
+
+        let (first, second) = (LastRun::new(), LastRun::new());
+        let tracked = |last_run| {
+            Query::<&Position, Changed<Position>>::new(&entity_manager, &storage)
+                .since_last_run(last_run)
+        };
+        let default = || Query::<&Position, Changed<Position>>::new(&entity_manager, &storage);
+        assert_eq!(tracked(&first).iter().await.count(), 2);
+
+        storage
+            .get_mut::<Position>(Entity::new(0, 0))
+            .await
+            .unwrap()
+            .x += 1;
+
+        // Running tracked queries doesn't start a new tick or hide the change from anyone
+        assert_eq!(default().iter().await.count(), 1);
+        assert_eq!(tracked(&second).iter().await.count(), 2);
+        assert_eq!(tracked(&first).iter().await.count(), 1);
+        assert_eq!(default().iter().await.count(), 1);
+        assert_eq!(storage.change_tick(), tick);
+
+        // Each of them sees it once
+        assert_eq!(tracked(&first).iter().await.count(), 0);
+        assert_eq!(tracked(&second).iter().await.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_resource_query() {
+        #[derive(Debug)]
+        struct Gravity(i16);
+
+        let storage = ComponentStorage::new();
+        let entity_manager = EntityManager::new();
+        for id in 0..2 {
+            entity_manager.create_entity().await;
+            storage.insert(Entity::new(id, 0), Position { x: 0, y: 10, z: 0 });
+        }
+
+        // No resource, no results
+        let query = Query::<(&Position, Res<Gravity>)>::new(&entity_manager, &storage);
+        assert_eq!(query.iter().await.count(), 0);
+
+        storage.resources().insert(Gravity(-1));
+        let mut query = Query::<(&mut Position, ResMut<Gravity>)>::new(&entity_manager, &storage);
+        while let Some((_, (mut position, mut gravity))) = query.next().await {
+            position.y += gravity.0;
+            gravity.0 -= 1;
+        }
+
+        let query = Query::<&Position>::new(&entity_manager, &storage);
+        let heights = query.iter().await.map(|(_, p)| p.y).collect::<Vec<_>>();
+        assert_eq!(heights, vec![9, 8]);
+
+        // Read guards can be shared, so `Res` works with `iter`
+        let query = Query::<(&Position, Res<Gravity>)>::new(&entity_manager, &storage);
+        let gravities = query
+            .iter()
+            .await
+            .map(|(_, (_, g))| g.0)
+            .collect::<Vec<_>>();
+        assert_eq!(gravities, vec![-3, -3]);
+
+        // Each `ResMut` is dropped before the next one is fetched
+        let query = Query::<(&mut Position, ResMut<Gravity>)>::new(&entity_manager, &storage);
+        let mut results = std::pin::pin!(query.stream());
+        let mut visited = 0;
+        while let Some((_, (mut position, mut gravity))) = results.next().await {
+            position.y += gravity.0;
+            gravity.0 += 1;
+            visited += 1;
+        }
+        assert_eq!(visited, 2);
+        assert_eq!(storage.resources().get::<Gravity>().await.unwrap().0, -1);
+    }
 }
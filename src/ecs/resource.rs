@@ -0,0 +1,145 @@
+use std::any::{type_name, Any, TypeId};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use tokio::sync::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
+
+use crate::ecs::error::Error;
+use crate::utils::prelude::*;
+
+/// A trait for resources in the ECS, i.e. data there's one of in the world rather than one per
+/// entity. Implemented for everything that can be shared between threads.
+pub trait Resource: 'static + Send + Sync {}
+
+impl<T: 'static + Send + Sync> Resource for T {}
+
+type ResourceCell = Arc<RwLock<Box<dyn Any + Send + Sync>>>;
+
+/// An immutable reference to a resource.
+///
+/// # Examples
+/// ```ignore
+/// let tick_rate: Res<TickRate> = world.resource::<TickRate>().await?;
+/// println!("{}", tick_rate.0);
+/// ```
+pub struct Res<T: Resource> {
+    read_guard: OwnedRwLockReadGuard<Box<dyn Any + Send + Sync>>,
+    _phantom: PhantomData<T>,
+}
+
+/// A mutable reference to a resource.
+///
+/// # Examples
+/// ```ignore
+/// let mut tick_rate: ResMut<TickRate> = world.resource_mut::<TickRate>().await?;
+/// tick_rate.0 = 10;
+/// ```
+pub struct ResMut<T: Resource> {
+    write_guard: OwnedRwLockWriteGuard<Box<dyn Any + Send + Sync>>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Resource> std::ops::Deref for Res<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.read_guard
+            .downcast_ref()
+            .expect("Resources are keyed by their type id")
+    }
+}
+
+impl<T: Resource> std::ops::Deref for ResMut<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.write_guard
+            .downcast_ref()
+            .expect("Resources are keyed by their type id")
+    }
+}
+
+impl<T: Resource> std::ops::DerefMut for ResMut<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.write_guard
+            .downcast_mut()
+            .expect("Resources are keyed by their type id")
+    }
+}
+
+/// A storage structure for resources in the ECS, one of each type.
+#[derive(Default)]
+pub struct Resources {
+    resources: DashMap<TypeId, ResourceCell>,
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a resource, replacing the existing one of the same type.
+    pub fn insert<T: Resource>(&self, resource: T) {
+        self.resources
+            .insert(TypeId::of::<T>(), Arc::new(RwLock::new(Box::new(resource))));
+    }
+
+    pub fn contains<T: Resource>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
+
+    fn cell<T: Resource>(&self) -> Result<ResourceCell> {
+        let cell = self
+            .resources
+            .get(&TypeId::of::<T>())
+            .ok_or(Error::ResourceNotFound(type_name::<T>()))?;
+        Ok(cell.clone())
+    }
+
+    pub async fn get<T: Resource>(&self) -> Result<Res<T>> {
+        Ok(Res {
+            read_guard: self.cell::<T>()?.read_owned().await,
+            _phantom: PhantomData,
+        })
+    }
+
+    pub async fn get_mut<T: Resource>(&self) -> Result<ResMut<T>> {
+        Ok(ResMut {
+            write_guard: self.cell::<T>()?.write_owned().await,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Removes a resource, returning it unless a [Res] or [ResMut] to it is still alive, in which
+    /// case it's dropped along with the last one.
+    pub fn remove<T: Resource>(&self) -> Option<T> {
+        let (_, cell) = self.resources.remove(&TypeId::of::<T>())?;
+        let resource = Arc::into_inner(cell)?.into_inner();
+        resource.downcast().ok().map(|resource| *resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct TickRate(u32);
+
+    #[tokio::test]
+    async fn test_insert_and_get() {
+        let resources = Resources::new();
+        assert!(resources.get::<TickRate>().await.is_err());
+
+        resources.insert(TickRate(20));
+        assert!(resources.contains::<TickRate>());
+        assert_eq!(resources.get::<TickRate>().await.unwrap().0, 20);
+
+        resources.get_mut::<TickRate>().await.unwrap().0 = 10;
+        assert_eq!(resources.get::<TickRate>().await.unwrap().0, 10);
+
+        assert_eq!(resources.remove::<TickRate>(), Some(TickRate(10)));
+        assert!(!resources.contains::<TickRate>());
+    }
+}
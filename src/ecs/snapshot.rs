@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ecs::component::{Component, ComponentStorage};
+use crate::ecs::entity::Entity;
+use crate::utils::error::Error;
+use crate::utils::prelude::*;
+
+/// Inserts a deserialized component into an entity.
+pub type Insert = Box<dyn FnOnce(Entity, &ComponentStorage) + Send>;
+
+type SerializeFn = for<'a> fn(
+    &'a ComponentStorage,
+    Entity,
+) -> Pin<Box<dyn Future<Output = Result<Option<Value>>> + Send + 'a>>;
+type DeserializeFn = fn(Value) -> Result<Insert>;
+
+/// A component type registered with `#[component(serialize)]`, which requires it to implement
+/// serde's `Serialize` and `Deserialize`. Only registered components end up in a [WorldSnapshot].
+pub struct SerializableComponent {
+    /// The key of the component in snapshots, the name of the type.
+    pub name: &'static str,
+    serialize: SerializeFn,
+    deserialize: DeserializeFn,
+}
+
+impl SerializableComponent {
+    pub const fn new<T: Component + Serialize + DeserializeOwned>(name: &'static str) -> Self {
+        Self {
+            name,
+            serialize: serialize::<T>,
+            deserialize: deserialize::<T>,
+        }
+    }
+
+    /// Serializes the entity's component, `None` if it doesn't have one.
+    pub async fn serialize(
+        &self,
+        storage: &ComponentStorage,
+        entity: Entity,
+    ) -> Result<Option<Value>> {
+        (self.serialize)(storage, entity).await
+    }
+
+    pub fn deserialize(&self, value: Value) -> Result<Insert> {
+        (self.deserialize)(value)
+    }
+}
+
+fn serialize<T: Component + Serialize>(
+    storage: &ComponentStorage,
+    entity: Entity,
+) -> Pin<Box<dyn Future<Output = Result<Option<Value>>> + Send + '_>> {
+    Box::pin(async move {
+        let Ok(component) = storage.get::<T>(entity).await else {
+            return Ok(None);
+        };
+        serde_json::to_value(&*component)
+            .map(Some)
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    })
+}
+
+fn deserialize<T: Component + DeserializeOwned>(value: Value) -> Result<Insert> {
+    let component = serde_json::from_value::<T>(value)
+        .map_err(|e| Error::DeserializationError(e.to_string()))?;
+    Ok(Box::new(move |entity, storage| {
+        storage.insert(entity, component);
+    }))
+}
+
+inventory::collect!(SerializableComponent);
+
+/// Every component type registered with `#[component(serialize)]`, sorted by name.
+pub fn serializable_components() -> Vec<&'static SerializableComponent> {
+    let mut components = inventory::iter::<SerializableComponent>
+        .into_iter()
+        .collect::<Vec<_>>();
+    components.sort_by_key(|component| component.name);
+    components
+}
+
+/// The serializable components of every entity in a world, see
+/// [World::snapshot](crate::ecs::world::World::snapshot).
+///
+/// Entities are sorted by id and components by name, so two snapshots of the same state are
+/// equal, and their JSON is identical.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub entities: Vec<EntitySnapshot>,
+    /// The ids of deleted entities, with the generation they'll be reused with, so handles to
+    /// them stay stale after a restore.
+    #[serde(default)]
+    pub free_entities: Vec<Entity>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    pub entity: Entity,
+    /// The components, keyed by the name they were registered with.
+    pub components: BTreeMap<String, Value>,
+}
+
+impl WorldSnapshot {
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| Error::DeserializationError(e.to_string()))
+    }
+
+    /// The snapshot of the entity, if it was alive.
+    pub fn entity(&self, entity: Entity) -> Option<&EntitySnapshot> {
+        self.entities
+            .iter()
+            .find(|snapshot| snapshot.entity == entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ecs::snapshot::WorldSnapshot;
+    use crate::ecs::world::World;
+    use crate::utils::components::player::Player;
+    use crate::utils::components::rotation::Rotation;
+    use crate::utils::encoding::position::Position;
+
+    #[derive(Debug)]
+    struct NotSerializable;
+
+    impl crate::ecs::component::Component for NotSerializable {}
+
+    #[tokio::test]
+    async fn test_snapshot_and_restore() {
+        let world = World::new();
+        let player = world
+            .create_entity()
+            .await
+            .with(Player::new(u128::MAX, "Steve".to_string()))
+            .with(Position::new(1, 64, 2))
+            .with(NotSerializable)
+            .build();
+        let removed = world.create_entity().await.build();
+        let rotated = world
+            .create_entity()
+            .await
+            .with(Rotation::new(90.0, 0.0))
+            .build();
+        world.delete_entity(removed).await.unwrap();
+
+        let snapshot = world.snapshot().await.unwrap();
+        assert_eq!(snapshot.entities.len(), 2);
+        let components = &snapshot.entity(player).unwrap().components;
+        assert_eq!(
+            components.keys().collect::<Vec<_>>(),
+            vec!["Player", "Position"]
+        );
+        assert_eq!(
+            components["Player"]["uuid"],
+            "ffffffff-ffff-ffff-ffff-ffffffffffff"
+        );
+
+        let json = snapshot.to_json().unwrap();
+        let restored = World::new();
+        restored.create_entity().await.build();
+        restored
+            .restore(&WorldSnapshot::from_json(&json).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(restored.snapshot().await.unwrap(), snapshot);
+        assert_eq!(restored.entity_count().await, 2);
+        assert!(!restored.entity_exists(removed).await);
+        let rotation = restored.get_component::<Rotation>(rotated).await.unwrap();
+        assert_eq!(rotation.yaw, 90.0);
+        let player = restored.get_component::<Player>(player).await.unwrap();
+        assert_eq!(player.uuid, u128::MAX);
+
+        // The freed id is reused, with the next generation
+        let reused = restored.create_entity().await.build();
+        assert_eq!(reused.id, removed.id);
+        assert_eq!(reused.generation, removed.generation + 1);
+    }
+
+    #[tokio::test]
+    async fn test_restore_unknown_component() {
+        let world = World::new();
+        let entity = world
+            .create_entity()
+            .await
+            .with(Position::new(0, 0, 0))
+            .build();
+
+        let mut snapshot = world.snapshot().await.unwrap();
+        snapshot.entities[0]
+            .components
+            .insert("Unknown".to_string(), serde_json::Value::Null);
+        world.create_entity().await.build();
+
+        assert!(world.restore(&snapshot).await.is_err());
+        // Left untouched
+        assert_eq!(world.entity_count().await, 2);
+        assert!(world.get_component::<Position>(entity).await.is_ok());
+    }
+}
@@ -27,10 +27,8 @@ mod tests {
         let query = Query::<&Position>::new(&entity_manager, &storage);
         let results: Vec<_> = query.iter().await.collect();
 
-        let entity_id: usize = entity.into();
-
         assert_eq!(results.len(), 1);
-        assert_eq!(entity_id, results[0].0);
+        assert_eq!(entity, results[0].0);
         assert_eq!(results[0].1.x, 1);
         assert_eq!(results[0].1.y, 2);
     }
@@ -50,9 +48,8 @@ mod tests {
         let query = Query::<(&mut Position, &Velocity)>::new(&entity_manager, &storage);
         let results: Vec<_> = query.iter().await.collect();
 
-        let entity1_id: usize = entity1.into();
         assert_eq!(results.len(), 1);
-        assert_eq!(results[0].0, entity1_id);
+        assert_eq!(results[0].0, entity1);
         assert_eq!(results[0].1 .0.x, 1);
         assert_eq!(results[0].1 .1.x, 3);
     }
@@ -412,7 +409,7 @@ mod tests {
                 entity_id, pos.x, pos.y
             );
             assert!(
-                pos.x >= entity_id as i32,
+                pos.x >= entity_id.id as i32,
                 "Unexpected final position for entity {}",
                 entity_id
             );
@@ -1,5 +1,6 @@
 mod iter;
 mod multi_threaded_state;
 mod next;
+mod reuse;
 mod stress;
 mod with_rayon;
@@ -22,12 +22,12 @@ mod tests {
         let mut query = Query::<&Position>::new(&entity_manager, &storage);
 
         let (id1, pos1) = query.next().await.unwrap();
-        assert_eq!(id1, entity1.id as usize);
+        assert_eq!(id1, entity1);
         assert_eq!(pos1.x, 1);
         assert_eq!(pos1.y, 2);
 
         let (id2, pos2) = query.next().await.unwrap();
-        assert_eq!(id2, entity2.id as usize);
+        assert_eq!(id2, entity2);
         assert_eq!(pos2.x, 3);
         assert_eq!(pos2.y, 4);
 
@@ -49,7 +49,7 @@ mod tests {
         let mut query = Query::<(&Position, &Velocity)>::new(&entity_manager, &storage);
 
         let (id1, (pos1, vel1)) = query.next().await.unwrap();
-        assert_eq!(id1, entity1.id as usize);
+        assert_eq!(id1, entity1);
         assert_eq!(pos1.x, 1);
         assert_eq!(vel1.x, 3);
 
@@ -67,7 +67,7 @@ mod tests {
         let mut query = Query::<&mut Position>::new(&entity_manager, &storage);
 
         let (id, mut pos) = query.next().await.unwrap();
-        assert_eq!(id, entity.id as usize);
+        assert_eq!(id, entity);
         pos.x += 1;
 
         drop(pos); // Explicitly drop the RwLockWriteGuard.
@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use crate::ecs::entity::Entity;
+    use crate::ecs::error::Error as EcsError;
+    use crate::ecs::world::World;
+    use crate::utils::encoding::position::Position;
+    use crate::utils::encoding::velocity::Velocity;
+    use crate::utils::error::Error;
+
+    /// Deletes an entity and creates a new one, which reuses its id.
+    async fn delete_and_reuse(world: &World) -> (Entity, Entity) {
+        let old = world
+            .create_entity()
+            .await
+            .with(Position { x: 1, y: 1, z: 1 })
+            .build();
+        world.delete_entity(old).await.unwrap();
+
+        let new = world
+            .create_entity()
+            .await
+            .with(Position { x: 2, y: 2, z: 2 })
+            .build();
+        assert_eq!(old.id, new.id);
+        assert_ne!(old.generation, new.generation);
+        (old, new)
+    }
+
+    #[tokio::test]
+    async fn test_stale_handle_get() {
+        let world = World::new();
+        let (old, new) = delete_and_reuse(&world).await;
+
+        let stale = world.get_component::<Position>(old).await;
+        assert!(matches!(
+            stale,
+            Err(Error::ECSError(EcsError::EntityNotFound(entity))) if entity == old
+        ));
+        // Also for components the new entity doesn't have
+        let stale = world.get_component_mut::<Velocity>(old).await;
+        assert!(matches!(
+            stale,
+            Err(Error::ECSError(EcsError::EntityNotFound(_)))
+        ));
+
+        assert_eq!(world.get_component::<Position>(new).await.unwrap().x, 2);
+    }
+
+    #[tokio::test]
+    async fn test_stale_handle_insert_and_remove() {
+        let world = World::new();
+        let (old, new) = delete_and_reuse(&world).await;
+        let storage = world.get_component_storage();
+
+        storage.insert(old, Position { x: 3, y: 3, z: 3 });
+        assert_eq!(world.get_component::<Position>(new).await.unwrap().x, 2);
+
+        assert!(storage.remove::<Position>(old).is_err());
+        assert!(storage.contains::<Position>(new));
+        assert!(!storage.contains::<Position>(old));
+    }
+
+    #[tokio::test]
+    async fn test_insert_after_delete_before_reuse() {
+        let world = World::new();
+        let entity = world
+            .create_entity()
+            .await
+            .with(Position { x: 1, y: 1, z: 1 })
+            .build();
+        world.delete_entity(entity).await.unwrap();
+
+        // The id is free, so nothing is in its slot, but the entity is still gone
+        world
+            .get_component_storage()
+            .insert(entity, Position { x: 2, y: 2, z: 2 });
+        assert!(!world.get_component_storage().contains::<Position>(entity));
+        assert_eq!(world.query::<&Position>().iter().await.count(), 0);
+
+        let new = world.create_entity().await.build();
+        assert_eq!(new.id, entity.id);
+        world
+            .get_component_storage()
+            .insert(new, Position { x: 3, y: 3, z: 3 });
+        assert_eq!(world.get_component::<Position>(new).await.unwrap().x, 3);
+    }
+
+    #[tokio::test]
+    async fn test_stale_handle_delete() {
+        let world = World::new();
+        let (old, new) = delete_and_reuse(&world).await;
+
+        assert!(world.delete_entity(old).await.is_err());
+        assert!(world.entity_exists(new).await);
+        assert!(!world.entity_exists(old).await);
+    }
+
+    #[tokio::test]
+    async fn test_query_after_reuse() {
+        let world = World::new();
+        let (_, new) = delete_and_reuse(&world).await;
+
+        let query = world.query::<&Position>();
+        let results = query
+            .iter()
+            .await
+            .map(|(entity, position)| (entity, position.x))
+            .collect::<Vec<_>>();
+        assert_eq!(results, vec![(new, 2)]);
+
+        // Queries without a required component go through the living entities instead
+        let query = world.query::<Option<&Velocity>>();
+        let entities = query
+            .iter()
+            .await
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        assert_eq!(entities, vec![new]);
+    }
+}
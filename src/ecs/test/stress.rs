@@ -31,4 +31,41 @@ mod tests {
         let elapsed = start.elapsed();
         println!("Time taken to update 1000 entities: {:?}", elapsed);
     }
+
+    /// Runs a dense query (every entity matches) and a sparse one (1% match) at growing entity
+    /// counts, and checks they find every match.
+    #[tokio::test]
+    async fn stress_test_scaling() {
+        use crate::ecs::world::World;
+
+        for count in [1_000, 10_000, 100_000] {
+            let world = World::new();
+            for i in 0..count {
+                let entity = world
+                    .create_entity()
+                    .await
+                    .with(Position { x: 0, y: 0, z: 0 });
+                if i % 100 == 0 {
+                    entity.with(Velocity { x: 1, y: 1, z: 1 });
+                }
+            }
+
+            let mut query = world.query::<&mut Position>();
+            let mut dense = 0;
+            while let Some((_, mut pos)) = query.next().await {
+                pos.x += 1;
+                dense += 1;
+            }
+
+            let mut query = world.query::<(&mut Position, &Velocity)>();
+            let mut sparse = 0;
+            while let Some((_, (mut pos, vel))) = query.next().await {
+                pos.x += vel.x;
+                sparse += 1;
+            }
+
+            assert_eq!(dense, count);
+            assert_eq!(sparse, count / 100);
+        }
+    }
 }
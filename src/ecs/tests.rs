@@ -38,7 +38,7 @@ pub async fn test_component_storage_memory_leak() {
             let component = TestComponent {
                 _counter: DropCounter::new(Arc::clone(&drop_count)),
             };
-            storage.insert(i as usize, component);
+            storage.insert(Entity::new(i, 0), component);
         }
 
         // Access components
@@ -56,7 +56,7 @@ pub async fn test_component_storage_memory_leak() {
             let component = TestComponent {
                 _counter: DropCounter::new(Arc::clone(&drop_count)),
             };
-            storage.insert(i as usize, component);
+            storage.insert(Entity::new(i, 0), component);
         }
     } // ComponentStorage is dropped here
 
@@ -74,6 +74,7 @@ mod tests_pro_max {
     use tokio::sync::Semaphore;
 
     use crate::ecs::component::{Component, ComponentStorage};
+    use crate::ecs::entity::Entity;
 
     #[derive(Debug)]
     struct DropCounter {
@@ -122,11 +123,13 @@ mod tests_pro_max {
                 let component = TestComponent {
                     _counter: DropCounter::new(Arc::clone(&drop_count_clone)),
                 };
-                storage_clone.insert(i, component);
+                storage_clone.insert(Entity::new(i, 0), component);
 
                 // 50% chance of immediate removal
                 if i % 2 == 0 {
-                    storage_clone.remove::<TestComponent>(i).unwrap();
+                    storage_clone
+                        .remove::<TestComponent>(Entity::new(i, 0))
+                        .unwrap();
                 }
             });
             handles.push(handle);
@@ -141,17 +144,17 @@ mod tests_pro_max {
             let component = TestComponent {
                 _counter: DropCounter::new(Arc::clone(&drop_count)),
             };
-            storage.insert(i as usize, component);
+            storage.insert(Entity::new(i, 0), component);
         }
 
         // Scenario 3: Accessing non-existent components
         for i in concurrent_ops..(concurrent_ops + 100) {
-            let _ = storage.get::<TestComponent>(i).await;
+            let _ = storage.get::<TestComponent>(Entity::new(i, 0)).await;
         }
 
         // Scenario 4: Removing non-existent components
         for i in concurrent_ops..(concurrent_ops + 100) {
-            storage.remove::<TestComponent>(i).unwrap();
+            storage.remove::<TestComponent>(Entity::new(i, 0)).unwrap();
         }
 
         // Shouldn't exactly happen like ever the bottom thing 👇
@@ -174,7 +177,7 @@ mod tests_pro_max {
         // tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
 
         let final_drop_count = *drop_count.lock().unwrap();
-        let expected_drop_count = concurrent_ops + 100; // 1000 concurrent ops, 100 overwrites, 100 accesses, 100 removals
+        let expected_drop_count = concurrent_ops as usize + 100; // 1000 concurrent ops, 100 overwrites, 100 accesses, 100 removals
         assert_eq!(
             final_drop_count, expected_drop_count,
             "Expected {} drops, got {}",
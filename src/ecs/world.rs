@@ -1,8 +1,13 @@
+use crate::ecs::commands::Commands;
 use crate::ecs::component::{Component, ComponentRef, ComponentRefMut, ComponentStorage};
-use crate::ecs::entity::EntityManager;
+use crate::ecs::entity::{Entity, EntityManager};
 use crate::ecs::error::Error;
 use crate::ecs::helpers::entity_builder::EntityBuilder;
+use crate::ecs::hooks::{ComponentHooks, LifecycleEvent};
 use crate::ecs::query::Query;
+use crate::ecs::resource::{Res, ResMut, Resource};
+use crate::ecs::snapshot::{serializable_components, EntitySnapshot, WorldSnapshot};
+use crate::events::creation::registry::HandlerTable;
 
 use crate::utils::prelude::*;
 
@@ -58,6 +63,7 @@ use crate::utils::prelude::*;
 pub struct World {
     entity_manager: EntityManager,
     component_storage: ComponentStorage,
+    commands: Commands,
 }
 
 impl World {
@@ -73,6 +79,7 @@ impl World {
         Self {
             entity_manager: EntityManager::new(),
             component_storage: ComponentStorage::new(),
+            commands: Commands::new(),
         }
     }
 
@@ -100,14 +107,23 @@ impl World {
         EntityBuilder::new(entity, &self.component_storage)
     }
 
-    pub async fn delete_entity(&self, entity_id: impl TryInto<usize>) -> Result<()> {
-        let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
+    /// Returns the number of entities in the world
+    pub async fn entity_count(&self) -> usize {
+        self.entity_manager.entity_count().await
+    }
+
+    /// Whether the entity exists. `false` for handles to deleted entities, even once the id has
+    /// been reused.
+    pub async fn entity_exists(&self, entity: Entity) -> bool {
+        self.entity_manager.entity_exists(entity).await
+    }
 
-        if !self.entity_manager.delete_entity(entity_id).await {
-            return Err(Error::EntityNotFound(entity_id))?;
+    pub async fn delete_entity(&self, entity: Entity) -> Result<()> {
+        if !self.entity_manager.delete_entity(entity).await {
+            return Err(Error::EntityNotFound(entity).into());
         }
 
-        self.component_storage.remove_all(entity_id);
+        self.component_storage.remove_all(entity);
 
         Ok(())
     }
@@ -148,19 +164,189 @@ impl World {
         Query::<Q>::new(&self.entity_manager, &self.component_storage)
     }
 
+    /// Creates a new query for components, only matching entities that pass the filter `F`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// # use ferrumc::ecs::query::{Changed, With};
+    /// // Players that moved this tick
+    /// let query = world.query_filtered::<&Position, (With<Player>, Changed<Position>)>();
+    /// for (entity_id, position) in query.iter().await {
+    ///     println!("Player {} moved to {:?}", entity_id, position);
+    /// }
+    /// ```
+    pub fn query_filtered<Q, F>(&self) -> Query<'_, Q, F>
+    where
+        Q: crate::ecs::query::QueryItem,
+        F: crate::ecs::query::QueryFilter,
+    {
+        Query::<Q, F>::new(&self.entity_manager, &self.component_storage)
+    }
+
+    /// The current change tick, see [ComponentStorage::change_tick].
+    pub fn change_tick(&self) -> u64 {
+        self.component_storage.change_tick()
+    }
+
+    /// Starts a new change tick. Called by the tick loop after every tick.
+    pub fn increment_change_tick(&self) -> u64 {
+        self.component_storage.increment_change_tick()
+    }
+
+    /// Gets the entity's `T`. Fails with [Error::EntityNotFound] if the entity doesn't exist.
     pub async fn get_component<'a, T: Component>(
         &'a self,
-        entity_id: impl TryInto<usize>,
+        entity: Entity,
     ) -> Result<ComponentRef<'a, T>> {
-        let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
-        self.get_component_storage().get::<T>(entity_id).await
+        match self.get_component_storage().get::<T>(entity).await {
+            Err(e) => Err(self.component_error(entity, e).await),
+            component => component,
+        }
     }
     pub async fn get_component_mut<T: Component>(
         &self,
-        entity_id: impl TryInto<usize>,
+        entity: Entity,
     ) -> Result<ComponentRefMut<'_, T>> {
-        let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
-        self.get_component_storage().get_mut::<T>(entity_id).await
+        match self.get_component_storage().get_mut::<T>(entity).await {
+            Err(e) => Err(self.component_error(entity, e).await),
+            component => component,
+        }
+    }
+
+    /// A missing component might also mean a deleted entity, which only the entity manager knows.
+    async fn component_error(
+        &self,
+        entity: Entity,
+        error: crate::utils::error::Error,
+    ) -> crate::utils::error::Error {
+        if self.entity_exists(entity).await {
+            error
+        } else {
+            Error::EntityNotFound(entity).into()
+        }
+    }
+
+    /// Takes a snapshot of every living entity and its components registered with
+    /// `#[component(serialize)]`. Other components are left out.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let snapshot = world.snapshot().await?;
+    /// std::fs::write("world.json", snapshot.to_json()?)?;
+    /// ```
+    pub async fn snapshot(&self) -> Result<WorldSnapshot> {
+        let serializable = serializable_components();
+        let mut entities = Vec::new();
+        for entity in self.entity_manager.entities().await {
+            let mut components = std::collections::BTreeMap::new();
+            for component in &serializable {
+                if let Some(value) = component.serialize(&self.component_storage, entity).await? {
+                    components.insert(component.name.to_string(), value);
+                }
+            }
+            entities.push(EntitySnapshot { entity, components });
+        }
+        Ok(WorldSnapshot {
+            entities,
+            free_entities: self.entity_manager.free_entities().await,
+        })
+    }
+
+    /// Replaces every entity with the ones in the snapshot, keeping their ids and generations.
+    /// Handles to entities from before the restore shouldn't be used afterwards.
+    ///
+    /// The whole snapshot is deserialized first, so the world is left untouched if any of it is
+    /// invalid, e.g. a component that isn't registered with `#[component(serialize)]`.
+    pub async fn restore(&self, snapshot: &WorldSnapshot) -> Result<()> {
+        let serializable = serializable_components();
+        let mut inserts = Vec::new();
+        for entity in &snapshot.entities {
+            for (name, value) in &entity.components {
+                let component = serializable
+                    .iter()
+                    .find(|component| component.name == name)
+                    .ok_or_else(|| {
+                        crate::utils::error::Error::DeserializationError(format!(
+                            "Component {} isn't serializable",
+                            name
+                        ))
+                    })?;
+                inserts.push((entity.entity, component.deserialize(value.clone())?));
+            }
+        }
+
+        for entity in self.entity_manager.entities().await {
+            self.delete_entity(entity).await?;
+        }
+        self.component_storage.forget_deleted();
+        let entities = snapshot
+            .entities
+            .iter()
+            .map(|entity| entity.entity)
+            .collect::<Vec<_>>();
+        self.entity_manager
+            .restore(&entities, &snapshot.free_entities)
+            .await;
+        for (entity, insert) in inserts {
+            insert(entity, &self.component_storage);
+        }
+        Ok(())
+    }
+
+    /// Sets the hooks fired when components of type `T` are added, replaced or removed, see
+    /// [ComponentHooks].
+    pub fn register_component_hooks<T: Component>(&self, hooks: ComponentHooks<T>) {
+        self.component_storage.register_hooks(hooks);
+    }
+
+    /// Attaches the world to the event dispatcher's handlers, so it only records the lifecycle
+    /// events they listen for, including handlers registered at runtime.
+    pub fn set_event_handlers(&self, handlers: std::sync::Arc<HandlerTable>) {
+        self.component_storage.set_event_handlers(handlers);
+    }
+
+    /// Takes the `ComponentAdded` and `ComponentRemoved` events recorded since the last call. The
+    /// tick schedule dispatches them after every stage, see `EventDispatcherExt`.
+    pub fn take_lifecycle_events(&self) -> Vec<LifecycleEvent> {
+        self.component_storage.take_events()
+    }
+
+    /// Inserts a resource, replacing the existing one of the same type.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// world.insert_resource(TickRate(20));
+    /// let tick_rate = world.resource::<TickRate>().await?;
+    /// ```
+    pub fn insert_resource<T: Resource>(&self, resource: T) {
+        self.component_storage.resources().insert(resource);
+    }
+
+    /// Gets the `T` resource. Fails with [Error::ResourceNotFound] if there is none.
+    pub async fn resource<T: Resource>(&self) -> Result<Res<T>> {
+        self.component_storage.resources().get::<T>().await
+    }
+
+    pub async fn resource_mut<T: Resource>(&self) -> Result<ResMut<T>> {
+        self.component_storage.resources().get_mut::<T>().await
+    }
+
+    pub fn remove_resource<T: Resource>(&self) -> Option<T> {
+        self.component_storage.resources().remove::<T>()
+    }
+
+    /// The world's [Commands] buffer, applied by [World::apply_commands]. The tick schedule
+    /// applies it after every stage.
+    pub fn commands(&self) -> &Commands {
+        &self.commands
+    }
+
+    /// Applies the commands recorded in [World::commands].
+    pub async fn apply_commands(&self) {
+        self.commands.apply(self).await;
     }
 
     /// <p style="color:#9C27B0;">Returns a reference to the ComponentStorage</p>
@@ -195,22 +381,18 @@ mod multiple_components {
     use super::*;
 
     impl World {
-        pub async fn get_components<T>(
-            &self,
-            entity_id: impl TryInto<usize>,
-        ) -> Result<T::Output<'_>>
+        pub async fn get_components<T>(&self, entity: Entity) -> Result<T::Output<'_>>
         where
             T: GetComponents,
         {
-            let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
-            T::get_components(self, entity_id).await
+            T::get_components(self, entity).await
         }
     }
 
     pub trait GetComponents: Sized {
         type Output<'a>;
         #[allow(async_fn_in_trait)]
-        async fn get_components(world: &World, entity_id: usize) -> Result<Self::Output<'_>>;
+        async fn get_components(world: &World, entity: Entity) -> Result<Self::Output<'_>>;
     }
 
     macro_rules! impl_get_components_for_tuple {
@@ -218,8 +400,8 @@ mod multiple_components {
             impl<$($T: Component),*> GetComponents for ($($T,)*) {
                 type Output<'a> = ($(ComponentRef<'a, $T>,)*);
 
-                async fn get_components(world: &World, entity_id: usize) -> Result<Self::Output<'_>> {
-                    Ok(($(world.get_component::<$T>(entity_id).await?,)*))
+                async fn get_components(world: &World, entity: Entity) -> Result<Self::Output<'_>> {
+                    Ok(($(world.get_component::<$T>(entity).await?,)*))
                 }
             }
         };
@@ -1,28 +1,133 @@
 use std::any::Any;
 use std::sync::Arc;
-use crate::events::creation::registry::{dispatch_event};
+use std::time::{Duration, Instant};
+
+use tokio::task::AbortHandle;
+
+use crate::events::creation::event::Event;
+use crate::events::creation::queue::EventQueue;
+use crate::events::creation::registry::HandlerTable;
 use crate::state::GlobalState;
+use crate::utils::metrics::METRICS;
+use crate::utils::prelude::*;
 
-pub struct EventDispatcher;
+/// Runs event handlers. Holds the [HandlerTable], built from the `#[event_handler]`s once, when
+/// the dispatcher is created, which handlers can be added to and removed from at runtime.
+///
+/// Events are either dispatched inline with [EventDispatcher::dispatch_event], or queued with
+/// [EventDispatcher::queue_event] and dispatched later by the
+/// [crate::net::systems::event_queue::EventQueueSystem].
+pub struct EventDispatcher {
+    handlers: Arc<HandlerTable>,
+    queue: EventQueue,
+}
 
 
 impl EventDispatcher {
     pub fn new() -> Self {
-        Self
+        Self {
+            handlers: Arc::new(HandlerTable::from_registry()),
+            queue: EventQueue::default(),
+        }
+    }
+
+    /// The handler table, to register and unregister handlers at runtime.
+    pub fn handlers(&self) -> &Arc<HandlerTable> {
+        &self.handlers
+    }
+
+    /// The queue of events waiting to be dispatched.
+    pub fn queue(&self) -> &EventQueue {
+        &self.queue
+    }
+
+    /// Runs the event's handlers, and returns the event as they left it, e.g. cancelled.
+    pub async fn dispatch_event<T: Event>(&self, mut event: T, state: GlobalState) -> T {
+        self.dispatch_event_dyn(&mut event, state).await;
+        event
+    }
+
+    /// Runs the handlers of an event whose type is only known at runtime, in priority order. Once
+    /// a handler cancels the event, only the handlers marked with `ignore_cancelled` run.
+    pub async fn dispatch_event_dyn(&self, event: &mut dyn Event, state: GlobalState) {
+        let Some(handlers) = self.handlers.handlers_for((&*event as &dyn Any).type_id()) else {
+            return;
+        };
+
+        for handler in handlers.iter() {
+            if event.is_cancelled() && !handler.ignore_cancelled {
+                continue;
+            }
+            let start = Instant::now();
+            handler.handler().handle(event, state.clone()).await;
+            METRICS.event_handler(handler.handler().name(), start.elapsed());
+        }
+    }
+
+    /// Queues the event instead of running its handlers now, e.g. when nothing needs to wait for
+    /// them. Fails with [Error::EventQueueFull] if the queue is full.
+    pub fn queue_event<T: Event>(&self, event: T) -> Result<()> {
+        self.queue.push(Box::new(event))
+    }
+
+    /// Queues the event once `delay` has passed. Abort the returned handle to cancel it.
+    pub fn dispatch_after<T: Event>(&self, delay: Duration, event: T) -> AbortHandle {
+        self.queue.push_after(delay, Box::new(event))
     }
-    pub async fn dispatch_event<T: 'static + Any + Send + Sync>(&self, event: T, state: GlobalState) {
-        let event = Arc::new(event);
-        dispatch_event::<T>(event, state).await;
+
+    /// Dispatches queued events as they come in, one after the other. Only returns once the queue
+    /// is closed.
+    pub async fn run_queue(&self, state: GlobalState) {
+        while let Some(mut event) = self.queue.pop().await {
+            self.dispatch_event_dyn(&mut *event, state.clone()).await;
+        }
+    }
+
+    /// Dispatches the events queued so far, without waiting for more.
+    pub async fn dispatch_queued_events(&self, state: GlobalState) {
+        for mut event in self.queue.drain() {
+            self.dispatch_event_dyn(&mut *event, state.clone()).await;
+        }
+    }
+
+    /// Dispatches the component lifecycle events the world recorded since the last call, e.g.
+    /// `ComponentRemoved<Player>` once a player's entity is deleted.
+    pub async fn dispatch_lifecycle_events(&self, state: GlobalState) {
+        for mut event in state.world.take_lifecycle_events() {
+            self.dispatch_event_dyn(&mut *event.event, state.clone()).await;
+        }
+    }
+}
+
+impl Default for EventDispatcher {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 pub trait EventDispatcherExt {
     #[allow(async_fn_in_trait)]
-    async fn dispatch_event<T: 'static + Any + Send + Sync>(&self, event: T);
+    async fn dispatch_event<T: Event>(&self, event: T) -> T;
+    #[allow(async_fn_in_trait)]
+    async fn dispatch_lifecycle_events(&self);
+    fn queue_event<T: Event>(&self, event: T) -> Result<()>;
+    fn dispatch_after<T: Event>(&self, delay: Duration, event: T) -> AbortHandle;
 }
 
 impl EventDispatcherExt for GlobalState {
-    async fn dispatch_event<T: 'static + Any + Send + Sync>(&self, event: T) {
-        self.event_dispatcher.dispatch_event(event, self.clone()).await;
+    async fn dispatch_event<T: Event>(&self, event: T) -> T {
+        self.event_dispatcher.dispatch_event(event, self.clone()).await
+    }
+
+    async fn dispatch_lifecycle_events(&self) {
+        self.event_dispatcher.dispatch_lifecycle_events(self.clone()).await;
+    }
+
+    fn queue_event<T: Event>(&self, event: T) -> Result<()> {
+        self.event_dispatcher.queue_event(event)
+    }
+
+    fn dispatch_after<T: Event>(&self, delay: Duration, event: T) -> AbortHandle {
+        self.event_dispatcher.dispatch_after(delay, event)
     }
-}
\ No newline at end of file
+}
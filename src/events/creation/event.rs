@@ -0,0 +1,40 @@
+use std::any::Any;
+
+/// An event that can be dispatched to `#[event_handler]`s, which get mutable access to it in
+/// priority order. Implement it with `#[derive(Event)]`.
+///
+/// # Examples
+/// ```ignore
+/// #[derive(Event)]
+/// #[event(cancellable)]
+/// pub struct ChatEvent {
+///     pub message: String,
+///     cancelled: bool,
+/// }
+///
+/// let event = state.dispatch_event(ChatEvent { message, cancelled: false }).await;
+/// if !event.is_cancelled() {
+///     broadcast(event.message).await;
+/// }
+/// ```
+pub trait Event: Any + Send + Sync {
+    /// Whether a handler cancelled the event. Always `false` for events that aren't
+    /// [Cancellable].
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// An [Event] handlers can cancel, e.g. to stop a block from being broken. Once cancelled, the
+/// handlers after it only run if they're marked with `ignore_cancelled`, and the caller is
+/// expected to skip whatever the event announced.
+///
+/// `#[derive(Event)]` with `#[event(cancellable)]` implements it, storing the state in a
+/// `cancelled: bool` field.
+pub trait Cancellable: Event {
+    fn set_cancelled(&mut self, cancelled: bool);
+
+    fn cancel(&mut self) {
+        self.set_cancelled(true);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod event;
+pub mod queue;
 pub mod registry;
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::{mpsc, Notify};
+use tokio::task::AbortHandle;
+use tracing::warn;
+
+use crate::events::creation::event::Event;
+use crate::utils::metrics::METRICS;
+use crate::utils::prelude::*;
+
+/// How many events can wait in the [EventQueue] before [EventQueue::push] fails.
+pub const EVENT_QUEUE_CAPACITY: usize = 4096;
+
+/// Events whose handlers run later, outside of the code that raised them, so a slow handler
+/// doesn't stall it. The [crate::net::systems::event_queue::EventQueueSystem] dispatches them in
+/// the order they were queued.
+///
+/// The receiver is only locked to take events that are already there, never while waiting for
+/// one, so [EventQueue::drain] doesn't wait on a consumer parked in [EventQueue::pop].
+pub struct EventQueue {
+    sender: mpsc::Sender<Box<dyn Event>>,
+    receiver: Mutex<mpsc::Receiver<Box<dyn Event>>>,
+    /// Woken for every queued event.
+    queued: Arc<Notify>,
+}
+
+impl EventQueue {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        Self {
+            sender,
+            receiver: Mutex::new(receiver),
+            queued: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Queues an event, without waiting. Fails with [Error::EventQueueFull] if the queue is full,
+    /// in which case the event is dropped.
+    pub fn push(&self, event: Box<dyn Event>) -> Result<()> {
+        self.sender.try_send(event).map_err(|_| {
+            METRICS.events_dropped.inc();
+            Error::EventQueueFull
+        })?;
+        self.queued.notify_one();
+        Ok(())
+    }
+
+    /// Queues an event once `delay` has passed, e.g. for timers. If the queue is full by then, it
+    /// waits for room instead of dropping the event.
+    ///
+    /// Abort the returned handle to cancel the event before it's queued.
+    pub fn push_after(&self, delay: Duration, event: Box<dyn Event>) -> AbortHandle {
+        let sender = self.sender.clone();
+        let queued = Arc::clone(&self.queued);
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if sender.send(event).await.is_err() {
+                warn!("The event queue was closed before a scheduled event was queued");
+                return;
+            }
+            queued.notify_one();
+        })
+        .abort_handle()
+    }
+
+    /// The next queued event, waiting for one if there are none. Only returns `None` once the
+    /// queue is closed.
+    pub async fn pop(&self) -> Option<Box<dyn Event>> {
+        loop {
+            match self.receiver.lock().try_recv() {
+                Ok(event) => return Some(event),
+                Err(TryRecvError::Disconnected) => return None,
+                Err(TryRecvError::Empty) => {}
+            }
+            // Keeps the wakeup if an event was queued since `try_recv`. Another consumer may have
+            // taken it by the time we wake up, so check again.
+            self.queued.notified().await;
+        }
+    }
+
+    /// The events queued so far, without waiting for more.
+    pub fn drain(&self) -> Vec<Box<dyn Event>> {
+        let mut receiver = self.receiver.lock();
+        let mut events = Vec::with_capacity(receiver.len());
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    /// The number of events waiting to be dispatched.
+    pub fn depth(&self) -> usize {
+        self.sender.max_capacity() - self.sender.capacity()
+    }
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::new(EVENT_QUEUE_CAPACITY)
+    }
+}
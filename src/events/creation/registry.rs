@@ -1,16 +1,23 @@
-use std::any::Any;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
 use std::future::Future;
+use std::marker::PhantomData;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use parking_lot::RwLock;
+use crate::events::creation::event::Event;
 use crate::state::GlobalState;
 
 pub trait EventHandlerWrapper: Send + Sync + 'static {
-    fn handle(&self, event: Arc<dyn Any + Send + Sync>, state: GlobalState) -> Pin<Box<dyn Future<Output=()> + Send + '_>>;
+    fn handle<'a>(&'a self, event: &'a mut dyn Event, state: GlobalState) -> Pin<Box<dyn Future<Output=()> + Send + 'a>>;
     fn event_type_id(&self) -> std::any::TypeId;
+    /// The handler's name, to label its metrics.
+    fn name(&self) -> &'static str;
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct EventPriority(u8);
 
 impl EventPriority {
@@ -30,13 +37,16 @@ pub struct EventContainer {
     /// 0 <-----> 255
     /// Default is 128 (not too high, not too low)
     priority: EventPriority,
+    /// Whether the handler still runs once an earlier handler cancelled the event.
+    ignore_cancelled: bool,
     pub(crate) handler: &'static dyn EventHandlerWrapper,
 }
 
 impl EventContainer {
-    pub const fn new(priority: u8, handler: &'static dyn EventHandlerWrapper) -> Self {
+    pub const fn new(priority: u8, ignore_cancelled: bool, handler: &'static dyn EventHandlerWrapper) -> Self {
         Self {
             priority: EventPriority(priority),
+            ignore_cancelled,
             handler,
         }
     }
@@ -49,47 +59,215 @@ pub fn get_event_handlers() -> Vec<&'static EventContainer> {
         .collect()
 }
 
-pub fn get_event_handlers_for<T: 'static>() -> Vec<&'static EventContainer> {
-    let mut handlers = get_event_handlers()
-        .into_iter()
-        .filter(|h| h.handler.event_type_id() == std::any::TypeId::of::<T>())
-        .collect::<Vec<_>>();
+#[derive(Clone)]
+enum HandlerRef {
+    /// Registered with `#[event_handler]`.
+    Static(&'static dyn EventHandlerWrapper),
+    /// Registered at runtime with [HandlerTable::register].
+    Dynamic(Arc<dyn EventHandlerWrapper>),
+}
+
+/// A handler in a [HandlerTable].
+#[derive(Clone)]
+pub struct RegisteredHandler {
+    id: u64,
+    priority: EventPriority,
+    /// Whether the handler still runs once an earlier handler cancelled the event.
+    pub ignore_cancelled: bool,
+    handler: HandlerRef,
+}
 
-    handlers.sort_by_key(|h| h.priority.0);
+impl RegisteredHandler {
+    pub fn handler(&self) -> &dyn EventHandlerWrapper {
+        match &self.handler {
+            HandlerRef::Static(handler) => *handler,
+            HandlerRef::Dynamic(handler) => &**handler,
+        }
+    }
+}
+
+/// Returned by [HandlerTable::register], to unregister the handler again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventHandlerHandle {
+    type_id: TypeId,
+    id: u64,
+}
 
-    handlers
+/// The event handlers, by the type id of the event they handle, sorted by priority.
+///
+/// Dispatching only clones the list of the event's handlers. Registering and unregistering
+/// replace the list, so they never wait on a dispatch.
+pub struct HandlerTable {
+    handlers: RwLock<HashMap<TypeId, Arc<[RegisteredHandler]>>>,
+    next_id: AtomicU64,
 }
 
-pub async fn dispatch_event<T: 'static + Any + Send + Sync>(event: Arc<T>, state: GlobalState) {
-    let handlers = get_event_handlers_for::<T>();
+impl HandlerTable {
+    /// Builds the table from the handlers registered with `#[event_handler]`.
+    pub fn from_registry() -> Self {
+        let mut handlers = HashMap::<TypeId, Vec<RegisteredHandler>>::new();
+        for (id, container) in get_event_handlers().into_iter().enumerate() {
+            handlers
+                .entry(container.handler.event_type_id())
+                .or_default()
+                .push(RegisteredHandler {
+                    id: id as u64,
+                    priority: container.priority,
+                    ignore_cancelled: container.ignore_cancelled,
+                    handler: HandlerRef::Static(container.handler),
+                });
+        }
+
+        let next_id = AtomicU64::new(handlers.values().map(Vec::len).sum::<usize>() as u64);
+        let handlers = handlers
+            .into_iter()
+            .map(|(type_id, mut handlers)| {
+                handlers.sort_by_key(|h| h.priority.0);
+                (type_id, handlers.into())
+            })
+            .collect();
+        Self {
+            handlers: RwLock::new(handlers),
+            next_id,
+        }
+    }
+
+    /// The handlers of the event with the given type id, in the order they run in.
+    pub fn handlers_for(&self, type_id: TypeId) -> Option<Arc<[RegisteredHandler]>> {
+        self.handlers.read().get(&type_id).cloned()
+    }
+
+    /// Whether any handler listens for events with the given type id. Lets callers skip building
+    /// events nobody listens for.
+    pub fn has_handlers(&self, type_id: TypeId) -> bool {
+        self.handlers.read().contains_key(&type_id)
+    }
+
+    /// Adds a handler for `E` events. It runs after the handlers with the same priority that were
+    /// registered before it.
+    ///
+    /// `name` labels the handler's metrics, so it should say what the handler does.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let handle = state.event_dispatcher.handlers().register::<ChatEvent, _>(
+    ///     "lowercase_chat",
+    ///     128,
+    ///     false,
+    ///     |event, _state| Box::pin(async move { event.message.make_ascii_lowercase() }),
+    /// );
+    /// ```
+    pub fn register<E, F>(
+        &self,
+        name: &'static str,
+        priority: u8,
+        ignore_cancelled: bool,
+        handler: F,
+    ) -> EventHandlerHandle
+    where
+        E: Event,
+        F: for<'a> Fn(&'a mut E, GlobalState) -> Pin<Box<dyn Future<Output=()> + Send + 'a>> + Send + Sync + 'static,
+    {
+        let type_id = TypeId::of::<E>();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let handler = RegisteredHandler {
+            id,
+            priority: EventPriority(priority),
+            ignore_cancelled,
+            handler: HandlerRef::Dynamic(Arc::new(ClosureEventHandler {
+                name,
+                handler,
+                _marker: PhantomData::<fn(&mut E)>,
+            })),
+        };
 
-    let event = event as Arc<dyn Any + Send + Sync>;
+        let mut handlers = self.handlers.write();
+        let existing = handlers.remove(&type_id);
+        let mut list = existing
+            .iter()
+            .flat_map(|handlers| handlers.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        let index = list.partition_point(|h| h.priority.0 <= priority);
+        list.insert(index, handler);
+        handlers.insert(type_id, list.into());
 
+        EventHandlerHandle { type_id, id }
+    }
+
+    /// Removes a handler added with [HandlerTable::register]. Returns whether it was still
+    /// registered.
+    pub fn unregister(&self, handle: EventHandlerHandle) -> bool {
+        let mut handlers = self.handlers.write();
+        let Some(existing) = handlers.get(&handle.type_id) else {
+            return false;
+        };
+        if !existing.iter().any(|h| h.id == handle.id) {
+            return false;
+        }
 
-    for handler in handlers.iter() {
-        handler.handler.handle(Arc::clone(&event), state.clone()).await;
+        let list = existing
+            .iter()
+            .filter(|h| h.id != handle.id)
+            .cloned()
+            .collect::<Vec<_>>();
+        if list.is_empty() {
+            handlers.remove(&handle.type_id);
+        } else {
+            handlers.insert(handle.type_id, list.into());
+        }
+        true
     }
 }
 
-pub struct FunctionEventHandler<E: Send + Sync> {
-    // pub handler: fn(Arc<E>) -> Pin<Box<dyn Future<Output=()> + Send + '_>>,
-    // pub handler: Arc<dyn Fn(Arc<E>) -> Pin<Box<dyn Future<Output=()> + Send + 'static>>>,
-//     fn(parking_lot::lock_api::RwLock<parking_lot::RawRwLock, TestEvent>) -> impl futures::Future<Output = ()> {handler}
-    pub handler: fn(Arc<E>, GlobalState) -> Pin<Box<dyn Future<Output=()> + Send + 'static>>,
+/// The function an `#[event_handler]` compiles to, returning the handler's future.
+pub type HandlerFn<E> = for<'a> fn(&'a mut E, GlobalState) -> Pin<Box<dyn Future<Output=()> + Send + 'a>>;
+
+pub struct FunctionEventHandler<E: Event> {
+    pub name: &'static str,
+    pub handler: HandlerFn<E>,
 }
 
-impl<E: 'static + Any + Send + Sync> EventHandlerWrapper for FunctionEventHandler<E> {
-    fn handle(&self, event: Arc<dyn Any + Send + Sync>, state: GlobalState) -> Pin<Box<dyn Future<Output=()> + Send + '_>> {
-        Box::pin(async move {
-            let event = Arc::downcast::<E>(event).expect("wrong type for event");
-            (self.handler)(event, state).await;
-        })
+impl<E: Event> EventHandlerWrapper for FunctionEventHandler<E> {
+    fn handle<'a>(&'a self, event: &'a mut dyn Event, state: GlobalState) -> Pin<Box<dyn Future<Output=()> + Send + 'a>> {
+        let event = (event as &mut dyn Any).downcast_mut::<E>().expect("wrong type for event");
+        (self.handler)(event, state)
     }
 
     fn event_type_id(&self) -> std::any::TypeId {
         std::any::TypeId::of::<E>()
     }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+
+struct ClosureEventHandler<E, F> {
+    name: &'static str,
+    handler: F,
+    _marker: PhantomData<fn(&mut E)>,
+}
+
+impl<E, F> EventHandlerWrapper for ClosureEventHandler<E, F>
+where
+    E: Event,
+    F: for<'a> Fn(&'a mut E, GlobalState) -> Pin<Box<dyn Future<Output=()> + Send + 'a>> + Send + Sync + 'static,
+{
+    fn handle<'a>(&'a self, event: &'a mut dyn Event, state: GlobalState) -> Pin<Box<dyn Future<Output=()> + Send + 'a>> {
+        let event = (event as &mut dyn Any).downcast_mut::<E>().expect("wrong type for event");
+        (self.handler)(event, state)
+    }
+
+    fn event_type_id(&self) -> TypeId {
+        TypeId::of::<E>()
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
 }
 
 
-inventory::collect!(EventContainer);
\ No newline at end of file
+inventory::collect!(EventContainer);
@@ -1,79 +1,272 @@
-/*use crate::events::registry::{call_event, EventHandler};
-use ferrumc_macros::{event_handler, EventHandler};
-
-
-#[derive(EventHandler)]
-struct Handler1;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
-impl EventHandler for Handler1 {
-    type EventType = TestEvent;
+use crate::create_state;
+use crate::ecs::hooks::ComponentAdded;
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::{Cancellable, Event};
+use crate::events::creation::queue::EventQueue;
+use crate::state::GlobalState;
+use crate::utils::prelude::Error;
+use ferrumc_macros::{event_handler, Event};
+use tokio::net::TcpListener;
 
-    fn handle(&self, event: &mut Self::EventType) {
-        println!("Handler 1 called with value: {}", event.value);
-        event.value += 1;
-    }
+#[derive(Event)]
+struct TestEvent {
+    value: i32,
+    handled_by: Vec<&'static str>,
 }
 
-#[derive(EventHandler)]
 #[event_handler(priority = "fastest")]
-struct Handler2;
+async fn handler(event: &mut TestEvent, _state: GlobalState) {
+    event.value += 2;
+    event.handled_by.push("handler");
+}
 
-impl EventHandler for Handler2 {
-    type EventType = TestEvent;
+#[event_handler]
+async fn handler2(event: &mut TestEvent, _state: GlobalState) {
+    event.value *= 10;
+    event.handled_by.push("handler2");
+}
+
+#[derive(Event)]
+#[event(cancellable)]
+struct CancellableEvent {
+    cancel: bool,
+    handled_by: Vec<&'static str>,
+    cancelled: bool,
+}
 
-    fn handle(&self, event: &mut Self::EventType) {
-        println!("Handler 2 called with value: {}", event.value);
-        event.value += 1;
+#[event_handler(priority = "fast")]
+async fn cancelling_handler(event: &mut CancellableEvent, _state: GlobalState) {
+    event.handled_by.push("cancelling_handler");
+    if event.cancel {
+        event.cancel();
     }
 }
 
-*/
-use crate::events::creation::registry::dispatch_event;
-use ferrumc_macros::event_handler;
-use std::sync::Arc;
-use tokio::net::TcpListener;
-use crate::create_state;
-use crate::state::GlobalState;
+#[event_handler(priority = "slow")]
+async fn skipped_handler(event: &mut CancellableEvent, _state: GlobalState) {
+    event.handled_by.push("skipped_handler");
+}
 
-struct TestEvent {
-    value: i32,
+#[event_handler(priority = "slowest", ignore_cancelled)]
+async fn monitor_handler(event: &mut CancellableEvent, _state: GlobalState) {
+    event.handled_by.push("monitor_handler");
 }
 
-#[event_handler(priority = "fastest")]
-async fn handler(event: Arc<parking_lot::RwLock<TestEvent>>, _state: GlobalState) {
-    let mut event = event.write();
-    println!("Handler called with value: {}", event.value);
-    event.value += i32::MAX;
+#[tokio::test]
+async fn test_mutable_events() -> anyhow::Result<()> {
+    let state = create_state(TcpListener::bind("127.0.0.1:0").await?).await?;
+
+    let event = state
+        .dispatch_event(TestEvent {
+            value: 0,
+            handled_by: Vec::new(),
+        })
+        .await;
+
+    // Handlers run in priority order, and see each other's changes
+    assert_eq!(event.handled_by, vec!["handler", "handler2"]);
+    assert_eq!(event.value, 20);
+    assert!(!event.is_cancelled());
+
+    Ok(())
 }
 
-#[event_handler]
-async fn handler2(event: Arc<parking_lot::RwLock<TestEvent>>, _state: GlobalState) {
-    let mut event = event.write();
-    println!("Handler 2 called with value: {}", event.value);
-    event.value -= i32::MAX;
+#[tokio::test]
+async fn test_cancellable_events() -> anyhow::Result<()> {
+    let state = create_state(TcpListener::bind("127.0.0.1:0").await?).await?;
+
+    let event = state
+        .dispatch_event(CancellableEvent {
+            cancel: false,
+            handled_by: Vec::new(),
+            cancelled: false,
+        })
+        .await;
+    assert!(!event.is_cancelled());
+    assert_eq!(
+        event.handled_by,
+        vec!["cancelling_handler", "skipped_handler", "monitor_handler"]
+    );
+
+    let event = state
+        .dispatch_event(CancellableEvent {
+            cancel: true,
+            handled_by: Vec::new(),
+            cancelled: false,
+        })
+        .await;
+    assert!(event.is_cancelled());
+    assert_eq!(
+        event.handled_by,
+        vec!["cancelling_handler", "monitor_handler"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_runtime_handlers() -> anyhow::Result<()> {
+    let state = create_state(TcpListener::bind("127.0.0.1:0").await?).await?;
+    let handlers = state.event_dispatcher.handlers();
+
+    let late = handlers.register::<TestEvent, _>("late", 200, false, |event, _state| {
+        Box::pin(async move {
+            event.value += 1;
+            event.handled_by.push("late");
+        })
+    });
+    let early = handlers.register::<TestEvent, _>("early", 0, false, |event, _state| {
+        Box::pin(async move { event.handled_by.push("early") })
+    });
+
+    let event = state
+        .dispatch_event(TestEvent {
+            value: 0,
+            handled_by: Vec::new(),
+        })
+        .await;
+    // Same priority as `handler` ("fastest"), but registered after it
+    assert_eq!(
+        event.handled_by,
+        vec!["handler", "early", "handler2", "late"]
+    );
+    assert_eq!(event.value, 21);
+
+    assert!(handlers.unregister(late));
+    assert!(!handlers.unregister(late));
+    assert!(handlers.unregister(early));
+
+    let event = state
+        .dispatch_event(TestEvent {
+            value: 0,
+            handled_by: Vec::new(),
+        })
+        .await;
+    assert_eq!(event.handled_by, vec!["handler", "handler2"]);
+
+    Ok(())
 }
 
 #[tokio::test]
-async fn test_if_this_even_compiles() -> anyhow::Result<()> {
-    let state = create_state(TcpListener::bind("0.0.0.0:9009").await?).await?;
-    let some_event = TestEvent {
-        value: 0,
-    };
+async fn test_runtime_lifecycle_handlers() -> anyhow::Result<()> {
+    #[derive(Debug)]
+    struct Marker;
 
-    let some_event = Arc::new(parking_lot::RwLock::new(some_event));
-    dispatch_event(Arc::clone(&some_event), state).await;
+    impl crate::ecs::component::Component for Marker {}
 
-    println!("Final value: {}", some_event.read().value);
+    let state = create_state(TcpListener::bind("127.0.0.1:0").await?).await?;
+    let added = Arc::new(AtomicUsize::new(0));
 
+    // Nothing listens yet, so nothing is recorded
+    state.world.create_entity().await.with(Marker).build();
+    assert!(state.world.take_lifecycle_events().is_empty());
+
+    let count = Arc::clone(&added);
+    let handle = state
+        .event_dispatcher
+        .handlers()
+        .register::<ComponentAdded<Marker>, _>(
+            "count_added_markers",
+            128,
+            false,
+            move |_event, _state| {
+                let count = Arc::clone(&count);
+                Box::pin(async move {
+                    count.fetch_add(1, Ordering::Relaxed);
+                })
+            },
+        );
+    state.world.create_entity().await.with(Marker).build();
+    state.dispatch_lifecycle_events().await;
+    assert_eq!(added.load(Ordering::Relaxed), 1);
+
+    state.event_dispatcher.handlers().unregister(handle);
+    state.world.create_entity().await.with(Marker).build();
+    assert!(state.world.take_lifecycle_events().is_empty());
 
     Ok(())
 }
 
+#[derive(Event)]
+struct QueuedEvent {
+    value: usize,
+}
+
+#[tokio::test]
+async fn test_queued_events() -> anyhow::Result<()> {
+    let state = create_state(TcpListener::bind("127.0.0.1:0").await?).await?;
+    let total = Arc::new(AtomicUsize::new(0));
+
+    let sum = Arc::clone(&total);
+    state
+        .event_dispatcher
+        .handlers()
+        .register::<QueuedEvent, _>("sum_queued", 128, false, move |event, _state| {
+            let sum = Arc::clone(&sum);
+            Box::pin(async move {
+                sum.fetch_add(event.value, Ordering::Relaxed);
+            })
+        });
+
+    state.queue_event(QueuedEvent { value: 1 })?;
+    state.queue_event(QueuedEvent { value: 2 })?;
+    // Nothing runs until the queue is processed
+    assert_eq!(total.load(Ordering::Relaxed), 0);
+    assert_eq!(state.event_dispatcher.queue().depth(), 2);
 
+    state
+        .event_dispatcher
+        .dispatch_queued_events(state.clone())
+        .await;
+    assert_eq!(total.load(Ordering::Relaxed), 3);
+    assert_eq!(state.event_dispatcher.queue().depth(), 0);
 
+    // Due before the other one, so it'd be popped first if aborting it didn't work
+    let cancelled = state.dispatch_after(Duration::from_millis(1), QueuedEvent { value: 100 });
+    cancelled.abort();
+    state.dispatch_after(Duration::from_millis(20), QueuedEvent { value: 10 });
+    assert_eq!(state.event_dispatcher.queue().depth(), 0);
 
+    // Waits for the scheduled event to be queued
+    let mut event = state.event_dispatcher.queue().pop().await.unwrap();
+    state
+        .event_dispatcher
+        .dispatch_event_dyn(&mut *event, state.clone())
+        .await;
+    assert_eq!(total.load(Ordering::Relaxed), 13);
+    assert_eq!(state.event_dispatcher.queue().depth(), 0);
 
+    Ok(())
+}
 
+#[tokio::test]
+async fn test_full_event_queue() {
+    let queue = EventQueue::new(1);
 
+    assert!(queue.push(Box::new(QueuedEvent { value: 1 })).is_ok());
+    assert!(matches!(
+        queue.push(Box::new(QueuedEvent { value: 2 })),
+        Err(Error::EventQueueFull)
+    ));
+    assert_eq!(queue.drain().len(), 1);
+}
 
+#[tokio::test]
+async fn test_drain_while_popping() {
+    let queue = Arc::new(EventQueue::new(8));
+
+    // A consumer parked in `pop` doesn't block `drain`
+    let consumer = tokio::spawn({
+        let queue = Arc::clone(&queue);
+        async move { queue.pop().await.is_some() }
+    });
+    tokio::task::yield_now().await;
+    assert!(queue.drain().is_empty());
 
+    queue.push(Box::new(QueuedEvent { value: 1 })).unwrap();
+    assert!(consumer.await.unwrap());
+}
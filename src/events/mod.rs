@@ -1,2 +1,3 @@
 pub mod creation;
+pub mod server_events;
 pub mod world_events;
\ No newline at end of file
@@ -0,0 +1,105 @@
+use std::net::SocketAddr;
+
+use ferrumc_macros::Event;
+
+use crate::net::packets::incoming::status::StatusResponse;
+use crate::net::packets::ConnectionId;
+use crate::net::State;
+
+/// Dispatched once the server is listening for connections.
+#[derive(Event)]
+pub struct ServerStartedEvent {
+    pub address: SocketAddr,
+}
+
+/// Dispatched when the server is shutting down, before the systems are stopped.
+#[derive(Event)]
+pub struct ServerStoppingEvent;
+
+/// Dispatched when a client connects, after the connection throttle accepted it, before anything
+/// is read from it. Cancelling it closes the connection.
+#[derive(Event)]
+#[event(cancellable)]
+pub struct ConnectionOpenedEvent {
+    /// The address of the client, the one from the PROXY header if `proxy_protocol` is enabled.
+    pub address: SocketAddr,
+    cancelled: bool,
+}
+
+impl ConnectionOpenedEvent {
+    pub fn new(address: SocketAddr) -> Self {
+        Self {
+            address,
+            cancelled: false,
+        }
+    }
+}
+
+/// Dispatched when a client's handshake is received, before the connection switches to the next
+/// state. Cancelling it drops the connection.
+#[derive(Event)]
+#[event(cancellable)]
+pub struct HandshakeEvent {
+    pub conn_id: ConnectionId,
+    pub protocol_version: i32,
+    /// The address the client connected to. With BungeeCord forwarding, it also contains the
+    /// forwarded player info.
+    pub server_address: String,
+    pub server_port: u16,
+    /// [State::Status] or [State::Login]. Handlers can change it, but setting it to any other
+    /// state drops the connection.
+    pub next_state: State,
+    cancelled: bool,
+}
+
+impl HandshakeEvent {
+    pub fn new(
+        conn_id: ConnectionId,
+        protocol_version: i32,
+        server_address: String,
+        server_port: u16,
+        next_state: State,
+    ) -> Self {
+        Self {
+            conn_id,
+            protocol_version,
+            server_address,
+            server_port,
+            next_state,
+            cancelled: false,
+        }
+    }
+}
+
+/// Dispatched for every status request, before the response is sent.
+///
+/// Handlers can change the response (e.g. the MOTD or the player sample), or cancel the event to
+/// not answer at all, so the server shows up as offline.
+#[derive(Event)]
+#[event(cancellable)]
+pub struct ServerListPingEvent {
+    pub conn_id: ConnectionId,
+    /// The address of the client, if known.
+    pub address: Option<SocketAddr>,
+    /// The protocol version the client sent in the handshake.
+    pub protocol_version: i32,
+    pub response: StatusResponse,
+    cancelled: bool,
+}
+
+impl ServerListPingEvent {
+    pub fn new(
+        conn_id: ConnectionId,
+        address: Option<SocketAddr>,
+        protocol_version: i32,
+        response: StatusResponse,
+    ) -> Self {
+        Self {
+            conn_id,
+            address,
+            protocol_version,
+            response,
+            cancelled: false,
+        }
+    }
+}
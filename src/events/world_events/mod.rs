@@ -1,25 +1,112 @@
+use crate::ecs::entity::Entity;
+use crate::ecs::hooks::ComponentRemoved;
+use crate::net::packets::incoming::client_info::ClientInfo;
 use crate::state::GlobalState;
 use crate::utils::components::player::{Player};
-use ferrumc_macros::{event_handler, Constructor};
-use std::sync::Arc;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::position::Position;
+use ferrumc_macros::{event_handler, Constructor, Event};
 use tracing::{error, info};
 
-#[derive(Constructor)]
+/// Queued once a player finished logging in, so its handlers don't hold up the login.
+#[derive(Constructor, Event)]
 pub struct PlayerJoinWorldEvent {
-    entity_id: usize,
+    entity: Entity,
+}
+
+/// Dispatched when a player's connection is dropped, before their entity is deleted, so handlers
+/// can still read its components.
+#[derive(Constructor, Event)]
+pub struct PlayerLeaveEvent {
+    pub entity: Entity,
+}
+
+/// Dispatched when a player moves, before the new position is stored.
+///
+/// Handlers can change `to`, or cancel the event to keep the player at `from`. Either way the
+/// player is teleported to wherever they end up, e.g. to keep them inside a region.
+#[derive(Event)]
+#[event(cancellable)]
+pub struct PlayerMoveEvent {
+    pub entity: Entity,
+    pub from: Position,
+    pub to: Position,
+    pub on_ground: bool,
+    cancelled: bool,
+}
+
+impl PlayerMoveEvent {
+    pub fn new(entity: Entity, from: Position, to: Position, on_ground: bool) -> Self {
+        Self {
+            entity,
+            from,
+            to,
+            on_ground,
+            cancelled: false,
+        }
+    }
+}
+
+/// Dispatched when a player turns their head, before the new rotation is stored. Works like
+/// [PlayerMoveEvent].
+#[derive(Event)]
+#[event(cancellable)]
+pub struct PlayerRotateEvent {
+    pub entity: Entity,
+    pub from: Rotation,
+    pub to: Rotation,
+    cancelled: bool,
+}
+
+impl PlayerRotateEvent {
+    pub fn new(entity: Entity, from: Rotation, to: Rotation) -> Self {
+        Self {
+            entity,
+            from,
+            to,
+            cancelled: false,
+        }
+    }
+}
+
+/// Dispatched when a client sends its settings, on join and whenever they change. Handlers can
+/// change the settings before they're stored, e.g. to cap the view distance.
+#[derive(Constructor, Event)]
+pub struct ClientSettingsChangedEvent {
+    pub entity: Entity,
+    /// `None` the first time the client sends its settings.
+    pub previous: Option<ClientInfo>,
+    pub settings: ClientInfo,
+}
+
+/// Dispatched after chunks were sent to a player.
+#[derive(Constructor, Event)]
+pub struct ChunkSentEvent {
+    pub entity: Entity,
+    /// The chunk the player is in, in chunk coordinates.
+    pub center: (i32, i32),
+    /// The number of chunks sent.
+    pub count: usize,
 }
 
 #[event_handler(priority = "slow")]
-async fn on_player_join_world(event: Arc<PlayerJoinWorldEvent>, state: GlobalState) {
-    if let Err(e) = send_join_message(event.entity_id, state).await {
+async fn on_player_join_world(event: &mut PlayerJoinWorldEvent, state: GlobalState) {
+    if let Err(e) = send_join_message(event.entity, state).await {
         error!("Failed to send join message: {:?}", e);
     }
 }
 
-async fn send_join_message(entity_id: usize, state: GlobalState) -> crate::Result<()> {
-    let player = state.world.get_component::<Player>(entity_id).await?;
+async fn send_join_message(entity: Entity, state: GlobalState) -> crate::Result<()> {
+    let player = state.world.get_component::<Player>(entity).await?;
     
     info!("{} joined the world!", player.get_username());
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[event_handler(priority = "slow")]
+async fn on_player_leave_world(event: &mut ComponentRemoved<Player>, _state: GlobalState) {
+    if event.despawned {
+        info!("{} left the world!", event.component.get_username());
+    }
+}
@@ -4,16 +4,22 @@ use std::sync::{atomic::AtomicU32, Arc};
 
 use dashmap::DashMap;
 use ecs::world::World;
+use net::systems::tick_system::TickStats;
+use net::throttle::ConnectionThrottle;
 use net::ConnectionList;
 use state::{GlobalState, ServerState};
 use tokio::net::TcpListener;
 use utils::prelude::*;
+use crate::access::AccessLists;
 use crate::events::creation::dispatcher::EventDispatcher;
 
 extern crate core;
 #[macro_use]
 extern crate macro_rules_attribute;
 
+pub mod access;
+pub mod cli;
+pub mod commands;
 pub mod ecs;
 pub mod net;
 pub mod setup;
@@ -27,14 +33,22 @@ pub mod world;
 pub mod events;
 
 pub async fn create_state(tcp_listener: TcpListener) -> Result<GlobalState> {
+    let event_dispatcher = Arc::new(EventDispatcher::new());
+    let world = Arc::new(World::new());
+    world.set_event_handlers(Arc::clone(event_dispatcher.handlers()));
+
+    world.insert_resource(ConnectionList {
+        connections: DashMap::new(),
+        connection_count: AtomicU32::new(0),
+    });
+    world.insert_resource(database::start_database().await?);
+
     Ok(Arc::new(ServerState {
-        world: Arc::new(World::new()),
-        connections: ConnectionList {
-            connections: DashMap::new(),
-            connection_count: AtomicU32::new(0),
-        },
-        database: database::start_database().await?,
+        world,
         server_stream: tcp_listener,
-        event_dispatcher: Arc::new(EventDispatcher::new()),
+        event_dispatcher,
+        access: AccessLists::load(utils::get_root_path()?)?,
+        throttle: Arc::new(ConnectionThrottle::new()),
+        tick_stats: Arc::new(TickStats::default()),
     }))
 }
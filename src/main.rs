@@ -1,17 +1,23 @@
-use std::env;
-use std::process::exit;
-
-use ferrumc::{create_state, setup, utils, world};
+use std::path::Path;
+
+use clap::Parser;
+use ferrumc::cli::{Cli, Command, DbCommand, RunArgs};
+use ferrumc::events::creation::dispatcher::EventDispatcherExt;
+use ferrumc::events::server_events::{ServerStartedEvent, ServerStoppingEvent};
+use ferrumc::state::GlobalState;
+use ferrumc::{create_state, database, setup, utils, world};
 use tokio::net::TcpListener;
 use tokio::select;
 use tokio::task::JoinHandle;
-use tracing::{error, info, trace};
+use tracing::{error, info, trace, warn};
 
 use ferrumc::{
     net::systems::{kill_all_systems, start_all_systems},
     utils::{config::get_global_config, prelude::*},
 };
-use ferrumc::utils::config::ServerConfig;
+use ferrumc::utils::config::{
+    get_config_path, set_config_overrides, set_config_path, set_global_config, ServerConfig,
+};
 
 #[tokio::main]
 async fn main() {
@@ -23,20 +29,69 @@ async fn main() {
 }
 
 async fn entry() -> Result<()> {
-    utils::setup_logger()?;
+    let cli = Cli::parse();
+
+    if let Some(root) = cli.root {
+        utils::set_root_path(root);
+    }
+    if let Some(config) = cli.config {
+        set_config_path(config);
+    }
+    let command = cli.command.unwrap_or(Command::Run(RunArgs::default()));
+    if let Command::Run(args) = &command {
+        set_config_overrides(args.clone().into());
+    }
+
+    // The logger is configured in the config file, so errors and warnings in it can only be
+    // reported once the logger is set up with the defaults.
+    let config_path = get_config_path()?;
+    let config = if config_path.exists() {
+        ServerConfig::load_with_warnings(&config_path).map(Some)
+    } else {
+        Ok(None)
+    };
+    let logging = match &config {
+        Ok(Some((config, _))) => config.logging.clone(),
+        _ => Default::default(),
+    };
+    utils::setup_logger(cli.log.as_deref(), &logging)?;
+    if !matches!(command, Command::Setup) {
+        if let Some((config, warnings)) = config? {
+            for warning in warnings {
+                warn!("{}", warning);
+            }
+            set_global_config(config);
+        }
+    }
 
+    match command {
+        Command::Run(_) => run().await,
+        Command::Setup => setup::setup().await,
+        Command::Import(args) => import(&args.region_dir, args.batch_size).await,
+        Command::Export(args) => {
+            let dir = match args.output_dir {
+                Some(dir) => dir,
+                None => utils::get_root_path()?.join("export"),
+            };
+            let database = database::start_database().await?;
+            world::exporting::export_regions(&database, &dir).await
+        }
+        Command::Db {
+            command: DbCommand::Stats,
+        } => db_stats().await,
+    }
+}
+
+async fn run() -> Result<()> {
     if setup::handle_setup().await? {
         return Ok(());
     }
 
     info!("Initializing server...");
 
-    {
-        // silently check for configuration errors.
-        let _ = ServerConfig::new()?;
-    }
+    utils::favicon::init_favicon();
 
-    let server_handle = start_server().await?;
+    let (server_handle, state) = start_server().await?;
 
     let need_to_kill = select! {
         server_result = server_handle => {
@@ -58,6 +113,7 @@ async fn entry() -> Result<()> {
     };
 
     if need_to_kill {
+        state.dispatch_event(ServerStoppingEvent).await;
         kill_all_systems().await?;
     }
 
@@ -69,7 +125,7 @@ async fn entry() -> Result<()> {
 /// Starts the server. Sets up the sockets and listens for incoming connections
 ///
 /// The actual management of connections tx/rx is handled by [net::systems::connection_handler]
-async fn start_server() -> Result<JoinHandle<Result<()>>> {
+async fn start_server() -> Result<(JoinHandle<Result<()>>, GlobalState)> {
     let config = get_global_config();
     trace!("Starting server on {}:{}", config.host, config.port);
 
@@ -86,21 +142,15 @@ async fn start_server() -> Result<JoinHandle<Result<()>>> {
 
     let state = create_state(listener).await?;
 
-    if env::args().any(|arg| arg == "--import") {
-        // world::importing::import_regions(state.clone()).await?;
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(num_cpus::get())
-            .build_global()
-            .expect("Failed to build rayon thread pool");
-        world::importing::import_regions(state.clone()).await?;
-        exit(0);
-    }
-
     info!("Server started on {}", addr);
+    state
+        .dispatch_event(ServerStartedEvent { address: addr })
+        .await;
 
     // Start all systems (separate task)
+    let systems_state = state.clone();
     let handle = tokio::task::spawn(async {
-        let all_systems = tokio::task::spawn(start_all_systems(state));
+        let all_systems = tokio::task::spawn(start_all_systems(systems_state));
 
         // Wait for all systems to finish
         all_systems.await??;
@@ -110,5 +160,23 @@ async fn start_server() -> Result<JoinHandle<Result<()>>> {
         Ok(())
     });
 
-    Ok(handle)
+    Ok((handle, state))
+}
+
+async fn import(region_dir: &Path, batch_size: usize) -> Result<()> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_cpus::get())
+        .build_global()
+        .expect("Failed to build rayon thread pool");
+    let database = database::start_database().await?;
+    world::importing::import_regions(&database, region_dir, batch_size).await
+}
+
+async fn db_stats() -> Result<()> {
+    let database = database::start_database().await?;
+    let stats = database.stats().await?;
+    info!("Chunks: {}", stats.chunks);
+    info!("Size on disk: {:.2} MiB", stats.disk_size as f64 / 1024f64.powi(2));
+    info!("Used: {:.2} MiB", stats.used_size as f64 / 1024f64.powi(2));
+    Ok(())
 }
@@ -0,0 +1,248 @@
+//! Player info forwarding from BungeeCord and Velocity proxies.
+//!
+//! A proxy authenticates players itself and connects to the server in offline mode, so the server
+//! needs to be told the player's real UUID, IP and skin (the `textures` property).
+//!
+//! - BungeeCord ("legacy") forwarding appends the info to the server address in the
+//!   [crate::net::packets::incoming::handshake::Handshake], separated by null characters.
+//!   If a secret is configured, it's checked against the `bungeeguard-token` property, like
+//!   BungeeGuard does.
+//! - Velocity ("modern") forwarding sends the info as a response to a login plugin request on the
+//!   `velocity:player_info` channel, signed with the secret shared with the proxy.
+
+use std::io::Cursor;
+use std::net::IpAddr;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::utils::impls::packet_impls::NetDecode;
+use crate::utils::prelude::*;
+
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+/// The forwarding version we ask Velocity for. 1 is the version without chat signing keys.
+pub const VELOCITY_MODERN_DEFAULT: u8 = 1;
+const VELOCITY_SIGNATURE_LENGTH: usize = 32;
+const BUNGEEGUARD_TOKEN_PROPERTY: &str = "bungeeguard-token";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// The player info forwarded by a proxy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForwardedPlayer {
+    pub address: IpAddr,
+    pub uuid: Uuid,
+    /// Only sent by Velocity, BungeeCord leaves the username in the login start packet.
+    pub username: Option<String>,
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// Parses a BungeeCord forwarded server address, `host\0ip\0uuid[\0properties]`.
+///
+/// Returns the actual server address and the forwarded info.
+pub fn parse_bungeecord(server_address: &str, secret: &str) -> Result<(String, ForwardedPlayer)> {
+    let parts = server_address.split('\0').collect::<Vec<_>>();
+    let [host, address, uuid, rest @ ..] = parts.as_slice() else {
+        return Err(Error::InvalidForwarding(
+            "The handshake doesn't contain forwarded player info".to_string(),
+        ));
+    };
+
+    let address = address
+        .parse::<IpAddr>()
+        .map_err(|_| Error::InvalidForwarding(format!("Invalid address: {}", address)))?;
+    let uuid = Uuid::parse_str(uuid)
+        .map_err(|_| Error::InvalidForwarding(format!("Invalid UUID: {}", uuid)))?;
+    let properties: Vec<ProfileProperty> = match rest.first() {
+        Some(properties) => serde_json::from_str(properties)
+            .map_err(|e| Error::InvalidForwarding(format!("Invalid properties: {}", e)))?,
+        None => Vec::new(),
+    };
+
+    if !secret.is_empty() {
+        let token = properties
+            .iter()
+            .find(|property| property.name == BUNGEEGUARD_TOKEN_PROPERTY);
+        if token.is_none_or(|token| token.value != secret) {
+            return Err(Error::InvalidForwarding(
+                "Missing or invalid BungeeGuard token".to_string(),
+            ));
+        }
+    }
+
+    let properties = properties
+        .into_iter()
+        .filter(|property| property.name != BUNGEEGUARD_TOKEN_PROPERTY)
+        .collect();
+
+    Ok((
+        host.to_string(),
+        ForwardedPlayer {
+            address,
+            uuid,
+            username: None,
+            properties,
+        },
+    ))
+}
+
+/// Verifies and parses the data of a Velocity `velocity:player_info` response.
+pub async fn parse_velocity(data: &[u8], secret: &str) -> Result<ForwardedPlayer> {
+    if data.len() < VELOCITY_SIGNATURE_LENGTH {
+        return Err(Error::InvalidForwarding(
+            "Velocity response is too short".to_string(),
+        ));
+    }
+    let (signature, payload) = data.split_at(VELOCITY_SIGNATURE_LENGTH);
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| Error::InvalidForwarding(e.to_string()))?;
+    mac.update(payload);
+    mac.verify_slice(signature).map_err(|_| {
+        Error::InvalidForwarding("Velocity response has an invalid signature".to_string())
+    })?;
+
+    let mut cursor = Cursor::new(payload);
+    let version = VarInt::net_decode(&mut cursor).await?.get_val();
+    if version < VELOCITY_MODERN_DEFAULT as i32 {
+        return Err(Error::InvalidForwarding(format!(
+            "Unsupported Velocity forwarding version: {}",
+            version
+        )));
+    }
+
+    let address = String::net_decode(&mut cursor).await?;
+    let address = address
+        .parse::<IpAddr>()
+        .map_err(|_| Error::InvalidForwarding(format!("Invalid address: {}", address)))?;
+    let uuid = Uuid::from_u128(*u128::net_decode(&mut cursor).await?);
+    let username = *String::net_decode(&mut cursor).await?;
+
+    let property_count = VarInt::net_decode(&mut cursor).await?.get_val();
+    let mut properties = Vec::new();
+    for _ in 0..property_count {
+        let name = *String::net_decode(&mut cursor).await?;
+        let value = *String::net_decode(&mut cursor).await?;
+        let signature = if *bool::net_decode(&mut cursor).await? {
+            Some(*String::net_decode(&mut cursor).await?)
+        } else {
+            None
+        };
+        properties.push(ProfileProperty {
+            name,
+            value,
+            signature,
+        });
+    }
+
+    // Newer versions append the player's chat signing key, which we don't use.
+    Ok(ForwardedPlayer {
+        address,
+        uuid,
+        username: Some(username),
+        properties,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    const SECRET: &str = "hunter2";
+
+    #[test]
+    fn test_bungeecord() {
+        let address = "play.example.com\u{0}10.0.0.7\u{0}069a79f444e94726a5befca90e38aaf5\u{0}\
+                       [{\"name\":\"textures\",\"value\":\"abc\",\"signature\":\"def\"}]";
+        let (host, player) = parse_bungeecord(address, "").unwrap();
+
+        assert_eq!(host, "play.example.com");
+        assert_eq!(player.address, "10.0.0.7".parse::<IpAddr>().unwrap());
+        assert_eq!(
+            player.uuid,
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+        );
+        assert_eq!(player.properties.len(), 1);
+        assert_eq!(player.properties[0].signature.as_deref(), Some("def"));
+    }
+
+    #[test]
+    fn test_bungeecord_without_forwarding() {
+        assert!(parse_bungeecord("play.example.com", "").is_err());
+    }
+
+    #[test]
+    fn test_bungeeguard_token() {
+        let address = format!(
+            "localhost\u{0}::1\u{0}069a79f444e94726a5befca90e38aaf5\u{0}\
+             [{{\"name\":\"{}\",\"value\":\"{}\"}}]",
+            BUNGEEGUARD_TOKEN_PROPERTY, SECRET
+        );
+        let (_, player) = parse_bungeecord(&address, SECRET).unwrap();
+        // The token isn't passed on to the client.
+        assert!(player.properties.is_empty());
+
+        assert!(parse_bungeecord(&address, "something else").is_err());
+    }
+
+    async fn velocity_response(secret: &str) -> Vec<u8> {
+        let uuid = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let mut payload = Vec::new();
+        VarInt::from(VELOCITY_MODERN_DEFAULT as i32)
+            .net_encode(&mut payload)
+            .await
+            .unwrap();
+        "192.168.1.20".net_encode(&mut payload).await.unwrap();
+        payload.extend_from_slice(&uuid.as_u128().to_be_bytes());
+        "Notch".net_encode(&mut payload).await.unwrap();
+        VarInt::from(1).net_encode(&mut payload).await.unwrap();
+        "textures".net_encode(&mut payload).await.unwrap();
+        "abc".net_encode(&mut payload).await.unwrap();
+        false.net_encode(&mut payload).await.unwrap();
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(&payload);
+        let mut data = mac.finalize().into_bytes().to_vec();
+        data.extend_from_slice(&payload);
+        data
+    }
+
+    #[tokio::test]
+    async fn test_velocity() {
+        let data = velocity_response(SECRET).await;
+        let player = parse_velocity(&data, SECRET).await.unwrap();
+
+        assert_eq!(player.address, "192.168.1.20".parse::<IpAddr>().unwrap());
+        assert_eq!(player.username.as_deref(), Some("Notch"));
+        assert_eq!(
+            player.uuid,
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+        );
+        assert_eq!(
+            player.properties,
+            vec![ProfileProperty {
+                name: "textures".to_string(),
+                value: "abc".to_string(),
+                signature: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_velocity_wrong_secret() {
+        let data = velocity_response("not the secret").await;
+        assert!(parse_velocity(&data, SECRET).await.is_err());
+        assert!(parse_velocity(&data[..10], SECRET).await.is_err());
+    }
+}
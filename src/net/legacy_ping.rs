@@ -0,0 +1,104 @@
+//! Responses to the server list ping from before 1.7, still used by some monitoring tools.
+//!
+//! See <https://wiki.vg/Server_List_Ping#1.6>
+//!
+//! 1.4 to 1.5 clients send `0xFE 0x01`, and 1.6 clients follow that with a `MC|PingHost` plugin
+//! message (`0xFA ...`), which we don't need. The response is a kick packet (`0xFF`) with a
+//! UTF-16BE string containing the server info, after which the connection is closed.
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+use crate::net::packets::incoming::status::{random_motd, sample_players};
+use crate::net::protocol;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+use crate::utils::text;
+
+const PING_START: [u8; 2] = [0xFE, 0x01];
+const PLUGIN_MESSAGE_ID: u8 = 0xFA;
+const KICK_PACKET_ID: u8 = 0xFF;
+/// Makes old clients show the server as incompatible, with our version name.
+const LEGACY_PROTOCOL_VERSION: i32 = 127;
+
+/// Whether the first bytes of a connection are a legacy ping.
+///
+/// A modern handshake can also start with `0xFE 0x01` (a 254 byte packet), but it's followed by
+/// the handshake packet id (0x00) rather than nothing or a plugin message.
+pub fn is_legacy_ping(start: &[u8]) -> bool {
+    match start {
+        [0xFE, 0x01] => true,
+        [first, second, third, ..] => {
+            [*first, *second] == PING_START && *third == PLUGIN_MESSAGE_ID
+        }
+        _ => false,
+    }
+}
+
+/// Encodes the kick packet with the server info.
+pub fn encode_response(version: &str, motd: &str, online: usize, max_players: i32) -> Vec<u8> {
+    // The fields are separated by null characters, so they can't contain any.
+    let motd = motd.replace('\0', "");
+    let info = format!(
+        "§1\0{}\0{}\0{}\0{}\0{}",
+        LEGACY_PROTOCOL_VERSION, version, motd, online, max_players
+    );
+    let info = info.encode_utf16().collect::<Vec<_>>();
+
+    let mut response = Vec::with_capacity(3 + info.len() * 2);
+    response.push(KICK_PACKET_ID);
+    response.extend_from_slice(&(info.len() as u16).to_be_bytes());
+    for unit in info {
+        response.extend_from_slice(&unit.to_be_bytes());
+    }
+    response
+}
+
+/// Answers a legacy ping with the same MOTD and player count as the status response, and closes
+/// the connection.
+pub async fn respond(mut socket: TcpStream, state: &GlobalState) -> Result<()> {
+    let online = sample_players(state).await.len();
+    let response = encode_response(
+        protocol::VERSION_NAME,
+        &text::to_legacy_text(&random_motd()),
+        online,
+        get_global_config().max_players,
+    );
+    socket.write_all(&response).await?;
+    socket.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_legacy_ping() {
+        assert!(is_legacy_ping(&[0xFE, 0x01]));
+        assert!(is_legacy_ping(&[0xFE, 0x01, 0xFA, 0x00, 0x0B]));
+        // A 254 byte handshake.
+        assert!(!is_legacy_ping(&[0xFE, 0x01, 0x00, 0xFB, 0x05]));
+        assert!(!is_legacy_ping(&[0x10, 0x00, 0xFB, 0x05]));
+        assert!(!is_legacy_ping(&[0xFE]));
+    }
+
+    #[test]
+    fn test_encode_response() {
+        let response = encode_response("1.20.1", "A Server", 2, 20);
+        let expected = "§1\x00127\x001.20.1\x00A Server\x002\x0020";
+        let length = expected.encode_utf16().count();
+
+        assert_eq!(response[0], KICK_PACKET_ID);
+        assert_eq!(
+            u16::from_be_bytes([response[1], response[2]]) as usize,
+            length
+        );
+        let decoded = response[3..]
+            .chunks(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(String::from_utf16(&decoded).unwrap(), expected);
+    }
+}
@@ -1,6 +1,7 @@
 use std::cmp::PartialEq;
 use std::fmt::{Debug, Display};
 use std::io::Cursor;
+use std::net::SocketAddr;
 use std::sync::atomic::AtomicU32;
 use std::sync::{atomic, Arc};
 use std::time::Duration;
@@ -10,14 +11,20 @@ use ferrumc_codec::enc::NetEncode;
 use ferrumc_codec::network_types::varint::VarInt;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard};
-use tracing::{debug, error, trace};
+use tracing::{debug, error, info_span, trace, warn, Instrument};
 
 use ferrumc_macros::Component;
 
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::Event;
+use crate::events::server_events::ConnectionOpenedEvent;
+use crate::events::world_events::PlayerLeaveEvent;
+use crate::net::forwarding::ForwardedPlayer;
 use crate::net::packets::{handle_packet, ConnectionId};
 use crate::state::GlobalState;
 
 use super::utils::config::get_global_config;
+use super::utils::metrics::METRICS;
 use super::utils::prelude::*;
 pub mod utils;
 // To allow implementing the `Component` trait for `Connection`. Since we can't implement a trait for a type defined in another crate.
@@ -35,10 +42,15 @@ impl Debug for ConnectionWrapper {
 unsafe impl Send for ConnectionWrapper {}
 unsafe impl Sync for ConnectionWrapper {}
 
+pub mod forwarding;
+pub mod legacy_ping;
 pub mod packets;
+pub mod protocol;
+pub mod proxy_protocol;
 pub mod systems;
 mod test_ecs;
 pub mod the_dimension_codec;
+pub mod throttle;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum State {
@@ -67,7 +79,8 @@ impl State {
     }
 }
 
-/// A list of connections, with a counter for the number of connections.
+/// A list of connections, with a counter for the number of connections. A resource of the world,
+/// see [World::resource](crate::ecs::world::World::resource).
 ///
 /// In desperate need of reworking.
 pub struct ConnectionList {
@@ -78,8 +91,7 @@ pub struct ConnectionList {
 }
 
 impl ConnectionList {
-    pub fn get_connection(&self, conn_id: impl TryInto<usize>) -> Result<Arc<RwLock<Connection>>> {
-        let conn_id = conn_id.try_into().map_err(|_| Error::ConversionError)?;
+    pub fn get_connection(&self, conn_id: ConnectionId) -> Result<Arc<RwLock<Connection>>> {
         let conn = self
             .connections
             .get(&conn_id)
@@ -98,7 +110,7 @@ impl ConnectionList {
 /// - `metadata`: Metadata for the connection ([ConnectionMetadata]).
 /// - `drop`: Whether to drop and clean up the connection after this network tick.
 pub struct Connection {
-    pub id: usize,
+    pub id: ConnectionId,
     // pub socket: tokio::net::TcpStream,
     pub stream: NetStream,
     pub player_uuid: Option<uuid::Uuid>,
@@ -115,9 +127,22 @@ pub struct NetStream {
 #[derive(Debug, Default)]
 pub struct ConnectionMetadata {
     pub protocol_version: i32,
-    pub entity: usize,
+    pub entity: ConnectionId,
+    /// The address of the client. If `proxy_protocol` is enabled, this is the address from the
+    /// PROXY header rather than the load balancer's address, and with player info forwarding
+    /// it's the address forwarded by the proxy (keeping the proxy's port).
+    pub address: Option<SocketAddr>,
+    /// The player info forwarded by a BungeeCord or Velocity proxy, if forwarding is enabled.
+    pub forwarded: Option<ForwardedPlayer>,
+    /// The message id of the Velocity forwarding request we're waiting for a response to.
+    pub velocity_message_id: Option<i32>,
 }
 
+/// How long to wait for the PROXY header before dropping the connection.
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long to wait for the first bytes, to tell legacy pings apart from modern handshakes.
+const LEGACY_PING_SNIFF_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub fn setup_tracer() {
     console_subscriber::init();
 }
@@ -126,8 +151,74 @@ pub fn setup_tracer() {
 ///
 /// - `socket`: The TCP socket for the connection ([tokio::net::TcpStream]).
 ///
+/// Reads the PROXY protocol header if `proxy_protocol` is enabled, and checks the client's address
+/// against the [throttle::ConnectionThrottle]. Legacy (pre-1.7) pings are answered by
+/// [legacy_ping], everything else is passed to [register_conn].
+pub async fn init_connection(mut socket: tokio::net::TcpStream, state: GlobalState) -> Result<()> {
+    let peer_addr = socket.peer_addr()?;
+
+    let address = if get_global_config().proxy_protocol {
+        let header = tokio::time::timeout(
+            PROXY_HEADER_TIMEOUT,
+            proxy_protocol::read_proxy_header(&mut socket),
+        )
+        .await;
+        match header {
+            Ok(Ok(address)) => address.unwrap_or(peer_addr),
+            Ok(Err(e)) => {
+                warn!("Invalid PROXY header from {}: {}", peer_addr, e);
+                return Ok(());
+            }
+            Err(_) => {
+                warn!("Timed out waiting for the PROXY header from {}", peer_addr);
+                return Ok(());
+            }
+        }
+    } else {
+        peer_addr
+    };
+
+    let limits = &get_global_config().connection_limits;
+    // Held until the connection is closed, so it counts towards the IP's limit.
+    let _permit = match state.throttle.try_connect(address.ip(), limits) {
+        Ok(permit) => permit,
+        Err(rejection) => {
+            // Dropping the socket closes it, nothing has been allocated for it yet.
+            debug!("Refused connection from {}: {:?}", address, rejection);
+            return Ok(());
+        }
+    };
+
+    let event = state.dispatch_event(ConnectionOpenedEvent::new(address)).await;
+    if event.is_cancelled() {
+        debug!("Connection from {} was cancelled by an event handler", address);
+        return Ok(());
+    }
+
+    // Peek, so the handshake is still there for `manage_conn` if it isn't a legacy ping.
+    let mut start = [0u8; 3];
+    let peeked = tokio::time::timeout(LEGACY_PING_SNIFF_TIMEOUT, socket.peek(&mut start)).await;
+    if let Ok(Ok(length)) = peeked {
+        if legacy_ping::is_legacy_ping(&start[..length]) {
+            debug!("Answering legacy ping from {}", address);
+            return legacy_ping::respond(socket, &state).await;
+        }
+    }
+
+    debug!("Accepted connection from {}", address);
+    register_conn(socket, address, state)
+        .instrument(info_span!("conn", %address).or_current())
+        .await
+}
+
 /// Creates a new [Connection] and adds it to the [ConnectionList]. Passes the connection to [manage_conn].
-pub async fn init_connection(socket: tokio::net::TcpStream, state: GlobalState) -> Result<()> {
+///
+/// - `address`: The client's address. Differs from the socket's peer address when behind a proxy.
+async fn register_conn(
+    socket: tokio::net::TcpStream,
+    address: SocketAddr,
+    state: GlobalState,
+) -> Result<()> {
     let entity_id = state.world.create_entity().await.build();
 
     let (in_stream, out_stream) = socket.into_split();
@@ -140,7 +231,10 @@ pub async fn init_connection(socket: tokio::net::TcpStream, state: GlobalState)
         },
         player_uuid: None,
         state: State::Handshake,
-        metadata: ConnectionMetadata::default(),
+        metadata: ConnectionMetadata {
+            address: Some(address),
+            ..Default::default()
+        },
         drop: false,
     };
 
@@ -151,27 +245,29 @@ pub async fn init_connection(socket: tokio::net::TcpStream, state: GlobalState)
         .get_component_storage()
         .insert(entity_id, ConnectionWrapper(conn.clone()));
 
-    // Doesn't matter if we clone, since actual value is not cloned
-    state
-        .connections
-        .connections
-        .insert(entity_id, conn.clone());
-    state
-        .connections
-        .connection_count
-        .fetch_add(1, atomic::Ordering::Relaxed);
-
-    let current_amount = state
-        .connections
-        .connection_count
-        .load(atomic::Ordering::Relaxed);
+    let current_amount = {
+        let connections = state.world.resource::<ConnectionList>().await?;
+        // Doesn't matter if we clone, since actual value is not cloned
+        connections.connections.insert(entity_id, conn.clone());
+        connections
+            .connection_count
+            .fetch_add(1, atomic::Ordering::Relaxed)
+            + 1
+    };
 
     debug!(
         "Connection established with id: {}. Current connection count: {}",
         entity_id, current_amount
     );
 
-    let res = manage_conn(conn.clone(), state.clone()).await;
+    let login_timeout = get_global_config().connection_limits.login_timeout_secs;
+    let res = tokio::select! {
+        res = manage_conn(conn.clone(), state.clone()) => res,
+        _ = wait_for_login_timeout(conn.clone(), login_timeout) => {
+            state.throttle.record_login_timeout();
+            Err(Error::LoginTimedOut(login_timeout))
+        }
+    };
 
     if let Err(e) = res {
         error!(
@@ -184,6 +280,18 @@ pub async fn init_connection(socket: tokio::net::TcpStream, state: GlobalState)
     Ok(())
 }
 
+/// Completes once `timeout_secs` have passed, if the connection hasn't reached the play state by
+/// then. Never completes otherwise, or if `timeout_secs` is 0.
+async fn wait_for_login_timeout(conn: Arc<RwLock<Connection>>, timeout_secs: u64) {
+    if timeout_secs > 0 {
+        tokio::time::sleep(Duration::from_secs(timeout_secs)).await;
+        if conn.read().await.state != State::Play {
+            return;
+        }
+    }
+    std::future::pending().await
+}
+
 /// Manages a connection. This is the main loop for a connection.
 ///
 /// - `conn`: The connection to manage ([Arc<RwLock<Connection>>]).
@@ -191,17 +299,10 @@ pub async fn init_connection(socket: tokio::net::TcpStream, state: GlobalState)
 /// Reads packets from the connection and passes them to [handle_packet]. The handle_packet function
 /// is generated at compile time by [ferrumc_macros::bake_packet_registry].
 pub async fn manage_conn(conn: Arc<RwLock<Connection>>, state: GlobalState) -> Result<()> {
-    {
-        let local_addr = conn
-            .read()
-            .await
-            .stream
-            .in_stream
-            .lock()
-            .await
-            .peer_addr()?;
-        debug!("Starting receiver for the addr: {:?}", local_addr);
-    }
+    debug!(
+        "Starting receiver for the addr: {:?}",
+        conn.read().await.metadata.address
+    );
 
     loop {
         // Get the length of the packet
@@ -224,6 +325,7 @@ pub async fn manage_conn(conn: Arc<RwLock<Connection>>, state: GlobalState) -> R
         trace!("Packet ID: {}", packet_id);
 
         let packet_id = packet_id.get_val() as u8;
+        METRICS.packet_received(packet_id);
 
         let state_clone = state.clone();
         tokio::spawn(async move {
@@ -244,6 +346,34 @@ pub async fn manage_conn(conn: Arc<RwLock<Connection>>, state: GlobalState) -> R
     #[allow(unreachable_code)]
     Ok(())
 }
+/// Counts the packets in an encoded buffer, which can hold several frames (a packet queue).
+fn record_sent_packets(buffer: &[u8]) {
+    let mut position = 0;
+    while position < buffer.len() {
+        let Some((frame_len, len_size)) = read_varint(&buffer[position..]) else {
+            return;
+        };
+        position += len_size;
+        let Some((packet_id, _)) = read_varint(&buffer[position..]) else {
+            return;
+        };
+        METRICS.packet_sent(packet_id);
+        position += frame_len.max(0) as usize;
+    }
+}
+
+/// Reads a VarInt from the start of `bytes`, returning it and its length.
+fn read_varint(bytes: &[u8]) -> Option<(i32, usize)> {
+    let mut value = 0;
+    for (i, &byte) in bytes.iter().take(5).enumerate() {
+        value |= (i32::from(byte) & 0b01111111) << (i * 7);
+        if byte & 0b10000000 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
 async fn get_packet_length_and_buffer(
     conn: &RwLockReadGuard<'_, Connection>,
 ) -> Result<(VarInt, Vec<u8>)> {
@@ -265,22 +395,30 @@ async fn drop_conn_if_flagged(conn: Arc<RwLock<Connection>>, state: GlobalState)
 
     Ok(())
 }
-pub async fn drop_conn(connection_id: usize, state: GlobalState) -> Result<()> {
+pub async fn drop_conn(connection_id: ConnectionId, state: GlobalState) -> Result<()> {
     debug!("Dropping connection with id: {}", connection_id);
-    let connection = state.connections.connections.remove(&connection_id);
-    let Some((_, conn_arc)) = connection else {
-        return Err(Error::ConnectionNotFound(connection_id));
+    let conn_arc = {
+        let connections = state.world.resource::<ConnectionList>().await?;
+        let Some((_, conn_arc)) = connections.connections.remove(&connection_id) else {
+            return Err(Error::ConnectionNotFound(connection_id));
+        };
+        connections
+            .connection_count
+            .fetch_sub(1, atomic::Ordering::Relaxed);
+        conn_arc
     };
-    state
-        .connections
-        .connection_count
-        .fetch_sub(1, atomic::Ordering::Relaxed);
 
     {
         let read_lock = conn_arc.read().await;
         let entity_id = read_lock.id;
+        let in_play = read_lock.state == State::Play;
+        drop(read_lock);
+        if in_play {
+            state.dispatch_event(PlayerLeaveEvent::new(entity_id)).await;
+        }
         state.world.delete_entity(entity_id).await?;
     }
+    state.dispatch_lifecycle_events().await;
 
     // drop the connection in the end, just in case it errors out
     let conn = conn_arc.read().await;
@@ -291,8 +429,12 @@ pub async fn drop_conn(connection_id: usize, state: GlobalState) -> Result<()> {
 
 impl Connection {
     pub async fn send_packet(&self, packet: impl NetEncode) -> Result<()> {
-        let mut out_stream = self.get_out_stream().await;
-        packet.net_encode(&mut *out_stream).await?;
+        let mut buffer = Vec::new();
+        packet.net_encode(&mut buffer).await?;
+        record_sent_packets(&buffer);
+
+        METRICS.bytes_sent.add(buffer.len() as u64);
+        self.get_out_stream().await.write_all(&buffer).await?;
         Ok(())
     }
 
@@ -0,0 +1,54 @@
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::commands::{dispatch_command, CommandSender};
+use crate::net::packets::outgoing::system_chat_message::SystemChatMessage;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::ConnectionList;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// Sent by the client when the player runs a command (a chat message starting with `/`).
+///
+/// The command is sent without the leading slash. The message signatures following the salt are
+/// ignored, since the server runs in offline mode.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x04, state = "play")]
+pub struct ChatCommand {
+    pub command: String,
+    pub timestamp: i64,
+    pub salt: i64,
+}
+
+impl IncomingPacket for ChatCommand {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        debug!("Received command: /{}", self.command);
+
+        let sender = CommandSender::Player(conn_id);
+        let feedback = match dispatch_command(&self.command, &sender, state.clone()).await {
+            Ok(feedback) => feedback,
+            Err(Error::InvalidCommand(message)) => message,
+            Err(e) => {
+                debug!("Command /{} failed: {:?}", self.command, e);
+                "An error occurred while running the command".to_string()
+            }
+        };
+
+        // The sender may have been kicked by their own command.
+        let Ok(conn) = state
+            .world
+            .resource::<ConnectionList>()
+            .await?
+            .get_connection(conn_id)
+        else {
+            return Ok(());
+        };
+        conn.read()
+            .await
+            .send_packet(SystemChatMessage::from_text(feedback))
+            .await?;
+
+        Ok(())
+    }
+}
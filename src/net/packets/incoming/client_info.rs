@@ -2,6 +2,8 @@ use tracing::trace;
 
 use ferrumc_macros::{packet, Component, NetDecode};
 
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::world_events::ClientSettingsChangedEvent;
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::net::systems::chunk_sender::ChunkSender;
 use crate::state::GlobalState;
@@ -31,8 +33,21 @@ impl IncomingPacket for ClientInfo {
         trace!("Displayed Skin Parts: {}", self.displayed_skin_parts);
         trace!("Main Hand: {}", self.main_hand);
 
+        let previous = state
+            .world
+            .get_component::<ClientInfo>(entity_id)
+            .await
+            .ok()
+            .map(|info| info.clone());
+        let event = state
+            .dispatch_event(ClientSettingsChangedEvent::new(entity_id, previous, self))
+            .await;
+
         // ClientInfo is a packet & also a component.
-        state.world.get_component_storage().insert(entity_id, self);
+        state
+            .world
+            .get_component_storage()
+            .insert(entity_id, event.settings);
 
         // Send chunks again
         ChunkSender::send_chunks_to_player(state.clone(), entity_id).await?;
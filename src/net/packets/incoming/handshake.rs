@@ -1,15 +1,26 @@
 use ferrumc_codec::network_types::varint::VarInt;
+use std::net::SocketAddr;
+
+use tracing::{debug, warn};
 
 use ferrumc_macros::{packet, NetDecode};
 
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::Event;
+use crate::events::server_events::HandshakeEvent;
+use crate::net::packets::outgoing::login_disconnect::LoginDisconnect;
 use crate::net::packets::{ConnectionId, IncomingPacket};
-use crate::net::State;
+use crate::net::{forwarding, protocol, ConnectionList, State};
 use crate::state::GlobalState;
+use crate::utils::config::{get_global_config, ForwardingMode};
 use crate::utils::prelude::*;
 
 /// The first packet sent by the client to the server.
 ///
 /// This packet is used to negotiate the protocol version, server address, server port, and the next state.
+///
+/// With BungeeCord forwarding, the server address also contains the forwarded player info. See
+/// [crate::net::forwarding].
 #[derive(NetDecode)]
 #[packet(packet_id = 0x00, state = "handshake")]
 pub struct Handshake {
@@ -21,19 +32,82 @@ pub struct Handshake {
 
 impl IncomingPacket for Handshake {
     async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
-        let Some(conn) = state.connections.connections.get(&conn_id) else {
-            return Err(Error::ConnectionNotFound(conn_id));
-        };
+        // Not holding on to the connection list while the handlers run.
+        let conn = state
+            .world
+            .resource::<ConnectionList>()
+            .await?
+            .get_connection(conn_id)?;
 
-        let mut conn = conn.write().await;
-
-        conn.metadata.protocol_version = self.protocol_version.get_val();
-        conn.state = match self.next_state.get_val() {
+        let next_state = match self.next_state.get_val() {
             1 => State::Status,
             2 => State::Login,
             s => return Err(Error::InvalidState(s)),
         };
 
+        let event = HandshakeEvent::new(
+            conn_id,
+            self.protocol_version.get_val(),
+            self.server_address.clone(),
+            self.server_port,
+            next_state,
+        );
+        let event = state.dispatch_event(event).await;
+        if event.is_cancelled() {
+            debug!("Handshake was cancelled by an event handler");
+            conn.write().await.drop = true;
+            return Ok(());
+        }
+        // Handlers can only pick between the two states a handshake can lead to, anything else
+        // would skip the login.
+        if !matches!(event.next_state, State::Status | State::Login) {
+            warn!(
+                "An event handler set the handshake's next state to {:?}, dropping the connection",
+                event.next_state
+            );
+            conn.write().await.drop = true;
+            return Ok(());
+        }
+
+        let mut conn = conn.write().await;
+
+        conn.metadata.protocol_version = self.protocol_version.get_val();
+        conn.state = event.next_state;
+
+        // Status requests are fine for any version, the client will show the server as incompatible.
+        if conn.state == State::Login && !protocol::is_supported(conn.metadata.protocol_version) {
+            warn!(
+                "Refusing login from unsupported protocol version {}",
+                conn.metadata.protocol_version
+            );
+            let reason = format!(
+                "Unsupported client version! This server supports: {}",
+                protocol::VERSION_NAME
+            );
+            conn.send_packet(LoginDisconnect::from_text(reason)).await?;
+            conn.drop = true;
+            return Ok(());
+        }
+
+        let forwarding = &get_global_config().forwarding;
+        if conn.state == State::Login && forwarding.mode == ForwardingMode::BungeeCord {
+            match forwarding::parse_bungeecord(&self.server_address, &forwarding.secret) {
+                Ok((_, forwarded)) => {
+                    debug!("Forwarded player info from BungeeCord: {:?}", forwarded);
+                    let port = conn.metadata.address.map_or(0, |address| address.port());
+                    conn.metadata.address = Some(SocketAddr::new(forwarded.address, port));
+                    conn.metadata.forwarded = Some(forwarded);
+                }
+                Err(e) => {
+                    warn!("Refusing login without valid forwarded player info: {}", e);
+                    let reason = "If you wish to use IP forwarding, please enable it in your \
+                                  BungeeCord config as well!";
+                    conn.send_packet(LoginDisconnect::from_text(reason)).await?;
+                    conn.drop = true;
+                }
+            }
+        }
+
         Ok(())
     }
 }
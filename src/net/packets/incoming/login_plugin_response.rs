@@ -0,0 +1,81 @@
+use std::net::SocketAddr;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::{debug, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::forwarding;
+use crate::net::packets::incoming::login_start::LoginStart;
+use crate::net::packets::outgoing::login_disconnect::LoginDisconnect;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::ConnectionList;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::encoding::remaining_bytes::RemainingBytes;
+use crate::utils::prelude::*;
+
+/// The response to a [crate::net::packets::outgoing::login_plugin_request::LoginPluginQuery].
+///
+/// The only query we send is the Velocity forwarding request, so this finishes logging in the
+/// player with the forwarded info. See [crate::net::forwarding].
+#[derive(NetDecode)]
+#[packet(packet_id = 0x02, state = "login")]
+pub struct LoginPluginResponse {
+    pub message_id: VarInt,
+    pub successful: bool,
+    /// Only present if `successful` is true.
+    pub data: RemainingBytes,
+}
+
+impl IncomingPacket for LoginPluginResponse {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let conn = state
+            .world
+            .resource::<ConnectionList>()
+            .await?
+            .get_connection(conn_id)?;
+        let mut conn = conn.write().await;
+
+        if conn.metadata.velocity_message_id != Some(self.message_id.get_val()) {
+            debug!(
+                "Ignoring unexpected login plugin response {}",
+                self.message_id.get_val()
+            );
+            return Ok(());
+        }
+        conn.metadata.velocity_message_id = None;
+
+        let forwarded = if self.successful {
+            forwarding::parse_velocity(&self.data.0, &get_global_config().forwarding.secret).await
+        } else {
+            Err(Error::InvalidForwarding(
+                "The client didn't understand the forwarding request".to_string(),
+            ))
+        };
+        let forwarded = match forwarded {
+            Ok(forwarded) => forwarded,
+            Err(e) => {
+                warn!("Refusing login without valid forwarded player info: {}", e);
+                conn.send_packet(LoginDisconnect::from_text(
+                    "This server requires you to connect with Velocity.",
+                ))
+                .await?;
+                conn.drop = true;
+                return Ok(());
+            }
+        };
+
+        debug!("Forwarded player info from Velocity: {:?}", forwarded);
+        let port = conn.metadata.address.map_or(0, |address| address.port());
+        conn.metadata.address = Some(SocketAddr::new(forwarded.address, port));
+        let login_start = LoginStart {
+            username: forwarded.username.clone().unwrap_or_default(),
+            uuid: forwarded.uuid.as_u128(),
+        };
+        conn.metadata.forwarded = Some(forwarded);
+        drop(conn);
+
+        login_start.complete_login(conn_id, state).await
+    }
+}
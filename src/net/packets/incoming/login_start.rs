@@ -2,29 +2,32 @@ use std::time::Instant;
 
 use ferrumc_codec::network_types::varint::VarInt;
 use rand::random;
-use tracing::{debug};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use ferrumc_macros::{packet, NetDecode};
 use crate::events::creation::dispatcher::EventDispatcherExt;
 use crate::events::world_events::PlayerJoinWorldEvent;
+use crate::net::forwarding::{self, ProfileProperty};
 use crate::net::packets::outgoing::default_spawn_position::DefaultSpawnPosition;
 use crate::net::packets::outgoing::keep_alive::KeepAlivePacketOut;
-use crate::net::packets::outgoing::login_plugin_request::LoginPluginRequest;
-use crate::net::packets::outgoing::login_success::LoginSuccess;
+use crate::net::packets::outgoing::login_disconnect::LoginDisconnect;
+use crate::net::packets::outgoing::login_plugin_request::{LoginPluginQuery, LoginPluginRequest};
+use crate::net::packets::outgoing::login_success::{LoginSuccess, Property};
 use crate::net::packets::outgoing::synchronize_player_position::SynchronizePlayerPosition;
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::net::systems::chunk_sender::ChunkSender;
 use crate::net::utils::packet_queue::PacketQueue;
-use crate::net::Connection;
 use crate::net::State::Play;
+use crate::net::{Connection, ConnectionList};
 use crate::state::GlobalState;
 use crate::utils::components::keep_alive::KeepAlive;
 use crate::utils::components::player::Player;
 use crate::utils::components::rotation::Rotation;
+use crate::utils::config::{get_global_config, ForwardingMode};
 use crate::utils::constants::init;
 use crate::utils::encoding::position::Position;
 use crate::utils::prelude::*;
+use ferrumc_macros::{packet, NetDecode};
 
 /// The login start packet is sent by the client to the server to start the login process.
 ///
@@ -34,6 +37,10 @@ use crate::utils::prelude::*;
 /// No response is required from the client while these are being sent.
 ///
 /// This is the final stage in the login process. The client is now in the play state.
+///
+/// With Velocity forwarding, the server first asks the proxy for the player info, and the login is
+/// finished once the [crate::net::packets::incoming::login_plugin_response::LoginPluginResponse]
+/// arrives.
 #[derive(NetDecode)]
 #[packet(packet_id = 0x00, state = "login")]
 pub struct LoginStart {
@@ -54,12 +61,69 @@ impl IncomingPacket for LoginStart {
     async fn handle(mut self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
         self.username = self.username.trim().to_string();
 
-        let conn = state.connections.get_connection(conn_id)?;
+        if get_global_config().forwarding.mode == ForwardingMode::Velocity {
+            let conn = state
+                .world
+                .resource::<ConnectionList>()
+                .await?
+                .get_connection(conn_id)?;
+            let mut conn = conn.write().await;
+            let message_id = random::<u16>() as i32;
+            conn.metadata.velocity_message_id = Some(message_id);
+            let query = LoginPluginQuery::new(
+                message_id,
+                forwarding::VELOCITY_CHANNEL,
+                vec![forwarding::VELOCITY_MODERN_DEFAULT],
+            );
+            conn.send_packet(query).await?;
+            return Ok(());
+        }
+
+        self.complete_login(conn_id, state).await
+    }
+}
+
+impl LoginStart {
+    /// Checks whether the player can join, and if so, logs them in and sends them the world.
+    ///
+    /// If the player info was forwarded by a proxy, its UUID and properties are used instead of
+    /// the offline ones.
+    pub async fn complete_login(mut self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let conn = state
+            .world
+            .resource::<ConnectionList>()
+            .await?
+            .get_connection(conn_id)?;
         // let conn = conn.read().await;
 
+        let forwarded = conn.read().await.metadata.forwarded.clone();
+        let properties = match forwarded {
+            Some(forwarded) => {
+                self.uuid = forwarded.uuid.as_u128();
+                forwarded.properties
+            }
+            None => {
+                self.uuid = Player::offline_uuid(&self.username).as_u128();
+                Vec::new()
+            }
+        };
+
+        // The read guard has to be dropped before taking the write lock below.
+        let refusal = {
+            let conn = conn.read().await;
+            self.check_access(&conn, &state).await
+        };
+        if let Some(reason) = refusal {
+            let mut conn = conn.write().await;
+            conn.send_packet(LoginDisconnect::new_auto(reason)).await?;
+            conn.drop = true;
+            return Ok(());
+        }
+
         let mut packet_queue = PacketQueue::new();
 
-        self.send_login_success(&mut packet_queue).await?;
+        self.send_login_success(&mut packet_queue, properties)
+            .await?;
         self.send_login_play(&mut packet_queue).await?;
         self.send_spawn_position(&mut packet_queue).await?;
 
@@ -77,8 +141,10 @@ impl IncomingPacket for LoginStart {
         // conn.send_packet(packet).await?;
         packet_queue.queue(packet).await?;
 
-        let event = PlayerJoinWorldEvent::new(conn_id);
-        state.dispatch_event(event).await;
+        // Nothing here needs to wait for the handlers.
+        if let Err(e) = state.queue_event(PlayerJoinWorldEvent::new(conn_id)) {
+            warn!("Failed to queue the join event: {}", e);
+        }
 
         let mut conn = conn.write().await;
         // Send all the queued packets
@@ -95,23 +161,79 @@ impl IncomingPacket for LoginStart {
 
         Ok(())
     }
-}
 
-impl LoginStart {
-    async fn send_login_success(&self, packet_queue: &mut PacketQueue) -> Result<()> {
+    /// Checks the login rate limit, the ban lists, the whitelist and the player count, in that order.
+    ///
+    /// Returns the disconnect reason (as JSON) if the player isn't allowed to join.
+    async fn check_access(&self, conn: &Connection, state: &GlobalState) -> Option<String> {
+        let config = get_global_config();
+        let messages = &config.disconnect_messages;
+        let uuid = Uuid::from_u128(self.uuid).to_string();
+        let ip = conn.metadata.address.map(|address| address.ip());
+
+        let rate_limited =
+            ip.is_some_and(|ip| !state.throttle.try_login(ip, &config.connection_limits));
+        if rate_limited {
+            info!(
+                "Refusing login from {}, too many login attempts",
+                self.username
+            );
+            return Some(messages.rate_limited.clone());
+        }
+
+        if let Some(ban) = state.access.get_player_ban(&uuid, &self.username) {
+            info!("Refusing login from banned player {}", self.username);
+            return Some(messages.banned_message(&ban.reason));
+        }
+
+        let ip_ban = ip.and_then(|ip| state.access.get_ip_ban(&ip));
+        if let Some(ban) = ip_ban {
+            info!(
+                "Refusing login from banned IP {} ({})",
+                ban.ip, self.username
+            );
+            return Some(messages.banned_message(&ban.reason));
+        }
+
+        if config.whitelist && !state.access.is_whitelisted(&uuid, &self.username) {
+            info!("Refusing login from {}, not whitelisted", self.username);
+            return Some(messages.not_whitelisted.clone());
+        }
+
+        let online = state.world.query::<&Player>().iter().await.count();
+        if online >= config.max_players.max(0) as usize {
+            info!("Refusing login from {}, the server is full", self.username);
+            return Some(messages.server_full.clone());
+        }
+
+        None
+    }
+
+    async fn send_login_success(
+        &self,
+        packet_queue: &mut PacketQueue,
+        properties: Vec<ProfileProperty>,
+    ) -> Result<()> {
         debug!("LoginStart packet received");
         debug!("Username: {}", self.username);
         let uuid = Uuid::from_u128(self.uuid);
         debug!("UUID: {uuid}");
 
-        let namespace_uuid = Uuid::new_v5(&Uuid::NAMESPACE_URL, "OfflinePlayer".as_bytes());
-        let uuid = Uuid::new_v3(&namespace_uuid, self.username.as_bytes());
+        let properties = properties
+            .into_iter()
+            .map(|property| Property {
+                name: property.name,
+                value: property.value,
+                is_signed: property.signature.is_some(),
+                signature: property.signature,
+            })
+            .collect::<Vec<_>>();
 
         let response = LoginSuccess::new_auto(
             uuid.as_bytes().into(),
-            "OfflinePlayer".to_string(),
-            VarInt::new(0),
-            vec![],
+            self.username.clone(),
+            VarInt::new(properties.len() as i32),
+            properties,
         );
 
         packet_queue.queue(response).await?;
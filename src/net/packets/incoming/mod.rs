@@ -1,7 +1,9 @@
+pub mod chat_command;
 pub mod chat_message;
 pub mod client_info;
 pub mod handshake;
 pub mod keep_alive;
+pub mod login_plugin_response;
 pub mod login_start;
 pub mod ping;
 pub mod player_abilities;
@@ -5,6 +5,7 @@ use ferrumc_macros::{packet, NetDecode};
 
 use crate::net::packets::outgoing::ping::OutgoingPing;
 use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::ConnectionList;
 use crate::state::GlobalState;
 use crate::utils::prelude::*;
 
@@ -32,7 +33,11 @@ impl IncomingPacket for Ping {
                 response.net_encode(&mut cursor).await?;
                 let response = cursor.into_inner();
         */
-        let conn = state.connections.get_connection(conn_id)?;
+        let conn = state
+            .world
+            .resource::<ConnectionList>()
+            .await?
+            .get_connection(conn_id)?;
         let mut conn = conn.write().await;
 
         conn.drop = true;
@@ -1,3 +1,4 @@
+use crate::net::packets::incoming::set_player_position::move_player;
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::net::systems::chunk_sender::ChunkSender;
 use crate::state::GlobalState;
@@ -22,28 +23,28 @@ impl IncomingPacket for SetPlayerPosAndRotate {
     async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
         let my_entity_id = conn_id;
 
-        let component_storage = state.world.get_component_storage();
-
-        let mut position = component_storage.get_mut::<Position>(my_entity_id).await?;
-        let mut rotation = component_storage.get_mut::<Rotation>(my_entity_id).await?;
+        let chunk_pos = {
+            let position = state.world.get_component::<Position>(my_entity_id).await?;
+            (position.x >> 4, position.z >> 4)
+        };
 
-        ChunkSender::send_chunks_to_player_if_needed(
-            state.clone(),
-            my_entity_id,
-            (position.x >> 4, position.z >> 4),
-        )
-        .await?;
+        ChunkSender::send_chunks_to_player_if_needed(state.clone(), my_entity_id, chunk_pos)
+            .await?;
 
-        *position = Position {
+        let position = Position {
             x: self.x as i32,
             y: self.y as i16,
             z: self.z as i32,
         };
+        let rotation = Rotation::new(self.yaw, self.pitch);
 
-        *rotation = Rotation {
-            yaw: self.yaw,
-            pitch: self.pitch,
-        };
+        move_player(
+            &state,
+            my_entity_id,
+            Some((position, self.on_ground)),
+            Some(rotation),
+        )
+        .await?;
 
         trace!("SetPlayerPosAndRotate packet received: {:?}", self);
 
@@ -2,10 +2,18 @@ use tracing::trace;
 
 use ferrumc_macros::{packet, NetDecode};
 
+use crate::ecs::entity::Entity;
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::Event;
+use crate::events::world_events::{PlayerMoveEvent, PlayerRotateEvent};
+use crate::net::packets::outgoing::synchronize_player_position::SynchronizePlayerPosition;
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::net::systems::chunk_sender::ChunkSender;
+use crate::net::ConnectionWrapper;
 use crate::state::GlobalState;
+use crate::utils::components::rotation::Rotation;
 use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
 
 /// The set player position packet is sent by the client to the server to update the player's position.
 #[derive(NetDecode)]
@@ -30,16 +38,13 @@ impl IncomingPacket for SetPlayerPosition {
 
         let my_entity_id = conn_id;
 
-        let component_storage = state.world.get_component_storage();
-
-        let mut position = component_storage.get_mut::<Position>(my_entity_id).await?;
+        let chunk_pos = {
+            let position = state.world.get_component::<Position>(my_entity_id).await?;
+            (position.x >> 4, position.z >> 4)
+        };
 
-        ChunkSender::send_chunks_to_player_if_needed(
-            state.clone(),
-            my_entity_id,
-            (position.x >> 4, position.z >> 4),
-        )
-        .await?;
+        ChunkSender::send_chunks_to_player_if_needed(state.clone(), my_entity_id, chunk_pos)
+            .await?;
 
         /*let old_chunk_pos = (position.x >> 4, position.z >> 4);
         let new_chunk_pos = (self.x as i32 >> 4, self.z as i32 >> 4);
@@ -55,12 +60,68 @@ impl IncomingPacket for SetPlayerPosition {
             );
         }*/
 
-        *position = Position {
+        let position = Position {
             x: self.x as i32,
             y: self.y as i16,
             z: self.z as i32,
         };
 
-        Ok(())
+        move_player(&state, my_entity_id, Some((position, self.on_ground)), None).await
+    }
+}
+
+/// Dispatches [PlayerMoveEvent] and [PlayerRotateEvent] for the position and rotation a player
+/// sent, and stores them as the handlers left them.
+///
+/// If a handler cancelled or changed either of them, the player is teleported to where they
+/// ended up, since the client already moved.
+pub(crate) async fn move_player(
+    state: &GlobalState,
+    entity: Entity,
+    position: Option<(Position, bool)>,
+    rotation: Option<Rotation>,
+) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    let mut corrected = false;
+
+    // The components aren't borrowed while the handlers run, so they can read them.
+    if let Some((to, on_ground)) = position {
+        let from = component_storage.get::<Position>(entity).await?.clone();
+        let event = PlayerMoveEvent::new(entity, from.clone(), to.clone(), on_ground);
+        let event = state.dispatch_event(event).await;
+        let cancelled = event.is_cancelled();
+        let position = if cancelled { from } else { event.to };
+        corrected |= cancelled || position != to;
+
+        *component_storage.get_mut::<Position>(entity).await? = position;
     }
+
+    if let Some(to) = rotation {
+        let from = component_storage
+            .get_mut_or_insert_with(entity, || Rotation::new(0.0, 0.0))
+            .await
+            .clone();
+        let event = state
+            .dispatch_event(PlayerRotateEvent::new(entity, from.clone(), to.clone()))
+            .await;
+        let cancelled = event.is_cancelled();
+        let rotation = if cancelled { from } else { event.to };
+        corrected |= cancelled || rotation != to;
+
+        *component_storage.get_mut::<Rotation>(entity).await? = rotation;
+    }
+
+    if corrected {
+        let (position, rotation, conn) = state
+            .world
+            .get_components::<(Position, Rotation, ConnectionWrapper)>(entity)
+            .await?;
+        let packet = SynchronizePlayerPosition::new(&position, &rotation);
+        let conn = conn.0.clone();
+        drop((position, rotation));
+
+        conn.read().await.send_packet(packet).await?;
+    }
+
+    Ok(())
 }
@@ -1,5 +1,6 @@
 use ferrumc_macros::{packet, NetDecode};
 
+use crate::net::packets::incoming::set_player_position::move_player;
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::state::GlobalState;
 use crate::utils::components::rotation::Rotation;
@@ -20,15 +21,8 @@ impl IncomingPacket for SetPlayerRotation {
     ) -> crate::utils::prelude::Result<()> {
         let my_entity_id = conn_id;
 
-        let component_storage = state.world.get_component_storage();
+        let rotation = Rotation::new(self.yaw, self.pitch);
 
-        let mut rotation = component_storage
-            .get_mut_or_insert_with(my_entity_id, || Rotation::new(0.0, 0.0))
-            .await;
-
-        rotation.yaw = self.yaw;
-        rotation.pitch = self.pitch;
-
-        Ok(())
+        move_player(&state, my_entity_id, None, Some(rotation)).await
     }
 }
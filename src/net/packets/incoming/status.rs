@@ -1,20 +1,23 @@
-use base64::Engine;
 use ferrumc_codec::network_types::varint::VarInt;
 use rand::prelude::IndexedRandom;
+use rand::seq::SliceRandom;
 use serde::Serialize;
-use tokio::io::AsyncReadExt;
-use tokio::sync::OnceCell;
 use tracing::debug;
 
 use ferrumc_macros::{packet, NetDecode};
 use uuid::Uuid;
 
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::Event;
+use crate::events::server_events::ServerListPingEvent;
 use crate::net::packets::outgoing::status::OutgoingStatusResponse;
 use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::protocol;
+use crate::net::ConnectionList;
 use crate::state::GlobalState;
 use crate::utils::components::player::Player;
-use crate::utils::config;
 use crate::utils::prelude::*;
+use crate::utils::{config, favicon, text};
 
 /// The status packet is sent by the client to the server to request the server's status.
 ///
@@ -25,36 +28,35 @@ pub struct Status;
 
 /// The response to the status packet.
 /// Sent as json.
-#[derive(Serialize)]
-struct JsonResponse {
-    version: Version,
-    players: Players,
-    description: Description,
-    favicon: &'static String,
-}
-
-#[derive(Serialize)]
-struct Version {
-    name: String,
-    protocol: u32,
+///
+/// Can be changed by handlers of the [ServerListPingEvent].
+#[derive(Serialize, Debug, Clone)]
+pub struct StatusResponse {
+    pub version: Version,
+    pub players: Players,
+    /// The MOTD, as a JSON text component.
+    pub description: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
 }
 
-#[derive(Serialize)]
-struct Players {
-    max: i32,
-    online: i32,
-    sample: Vec<Sample>,
+#[derive(Serialize, Debug, Clone)]
+pub struct Version {
+    pub name: String,
+    pub protocol: u32,
 }
 
-#[derive(Serialize)]
-struct Sample {
-    name: String,
-    id: String,
+#[derive(Serialize, Debug, Clone)]
+pub struct Players {
+    pub max: i32,
+    pub online: i32,
+    pub sample: Vec<Sample>,
 }
 
-#[derive(Serialize)]
-struct Description {
-    text: String,
+#[derive(Serialize, Debug, Clone)]
+pub struct Sample {
+    pub name: String,
+    pub id: String,
 }
 
 impl IncomingPacket for Status {
@@ -62,36 +64,53 @@ impl IncomingPacket for Status {
         debug!("Handling status request packet");
         let config = config::get_global_config();
 
-        let conn = state.connections.get_connection(conn_id)?;
+        let conn = state
+            .world
+            .resource::<ConnectionList>()
+            .await?
+            .get_connection(conn_id)?;
         let conn = conn.read().await;
 
-        let random_motd = config.motd.choose(&mut rand::thread_rng()).unwrap().clone();
+        let mut player_samples = sample_players(&state).await;
+        let online = player_samples.len() as i32;
+        if config.server_list.hide_players {
+            player_samples.clear();
+        } else {
+            player_samples.shuffle(&mut rand::thread_rng());
+            player_samples.truncate(config.server_list.sample_size);
+        }
+
+        let version = Version {
+            name: protocol::VERSION_NAME.to_string(),
+            protocol: protocol::PROTOCOL_VERSION as u32,
+        };
+
+        let response = StatusResponse {
+            version,
+            players: Players {
+                max: config.max_players,
+                online,
+                sample: player_samples,
+            },
+            description: random_motd(),
+            favicon: favicon::get_favicon().map(str::to_string),
+        };
 
-        //Queries all players and makes a Sample struct from them
-        let player_query = state.world.query::<&Player>();
-        let players = player_query.iter().await.collect::<Vec<_>>();
-        let player_samples: Vec<Sample> = players.iter().map(|(_, player)| Sample{
-            name: player.username.to_string(),
-            id: Uuid::from_u128(player.uuid).to_string(),
-        }).collect();
+        let event = ServerListPingEvent::new(
+            conn_id,
+            conn.metadata.address,
+            conn.metadata.protocol_version,
+            response,
+        );
+        let event = state.dispatch_event(event).await;
+        if event.is_cancelled() {
+            return Ok(());
+        }
 
         let response = OutgoingStatusResponse {
             packet_id: VarInt::new(0x00),
-            json_response: serde_json::ser::to_string(&JsonResponse {
-                version: Version {
-                    name: "1.20.6".to_string(),
-                    // Allow any protocol version for now. To check the ping and stuff
-                    protocol: conn.metadata.protocol_version as u32,
-                },
-                players: Players {
-                    max: config.max_players,
-                    online: player_samples.len() as i32,
-                    sample: player_samples,
-                },
-                description: Description { text: random_motd },
-                favicon: get_encoded_favicon().await,
-            })
-            .unwrap(),
+            json_response: serde_json::ser::to_string(&event.response)
+                .map_err(|e| Error::SerializationError(e.to_string()))?,
         };
 
         conn.send_packet(response).await?;
@@ -107,20 +126,31 @@ impl IncomingPacket for Status {
     }
 }
 
-/// Get the favicon as a base64 encoded string.
+/// Picks one of the configured MOTDs at random, as a JSON text component.
 ///
-/// This is cached in a `OnceCell` to avoid reading the file every time.
-async fn get_encoded_favicon() -> &'static String {
-    static FAVICON: OnceCell<String> = OnceCell::const_new();
-    FAVICON
-        .get_or_init(|| async {
-            let mut data = Vec::new();
-            let Ok(mut image) = tokio::fs::File::open("icon-64.png").await else {
-                return String::new();
-            };
-            image.read_to_end(&mut data).await.unwrap_or_default();
-            let data = base64::engine::general_purpose::STANDARD.encode(&data);
-            format!("data:image/png;base64,{}", data)
-        })
+/// Also used by the query protocol ([crate::net::systems::query]) and legacy pings
+/// ([crate::net::legacy_ping]), which flatten it with [text::to_legacy_text].
+pub(crate) fn random_motd() -> serde_json::Value {
+    let config = config::get_global_config();
+    let motd = config
+        .motd
+        .choose(&mut rand::thread_rng())
+        .map(String::as_str)
+        .unwrap_or_default();
+    text::parse_text_component(motd)
+}
+
+/// Queries all players and makes a [Sample] from each of them.
+///
+/// Also used by the query protocol ([crate::net::systems::query]).
+pub(crate) async fn sample_players(state: &GlobalState) -> Vec<Sample> {
+    let player_query = state.world.query::<&Player>();
+    player_query
+        .iter()
         .await
+        .map(|(_, player)| Sample {
+            name: player.username.to_string(),
+            id: Uuid::from_u128(player.uuid).to_string(),
+        })
+        .collect()
 }
@@ -1,12 +1,14 @@
 use ferrumc_macros::bake_packet_registry;
 
+use crate::ecs::entity::Entity;
 use crate::state::GlobalState;
 use crate::utils::prelude::*;
 
 pub mod incoming;
 pub mod outgoing;
 
-pub type ConnectionId = usize;
+/// Connections are entities, so a connection is identified by its [Entity] handle.
+pub type ConnectionId = Entity;
 
 pub trait IncomingPacket {
     #[allow(async_fn_in_trait)]
@@ -1,3 +1,4 @@
+use crate::database::Database;
 use crate::state::GlobalState;
 use crate::utils::encoding::bitset::BitSet;
 use crate::utils::error::Error;
@@ -16,7 +17,7 @@ const _SECTION_HEIGHT: usize = 16;
 // Seperated light data from chunk data since clippy was complaining about the size of the struct
 #[derive(NetEncode)]
 pub struct ChunkDataAndUpdateLight {
-/*    #[encode(default=VarInt::from(0x24))]
+    /*    #[encode(default=VarInt::from(0x24))]
     pub packet_id: VarInt,
     pub chunk_x: i32,
     pub chunk_z: i32,
@@ -66,7 +67,9 @@ pub struct LightArray {
 impl ChunkDataAndUpdateLight {
     pub async fn new(state: GlobalState, chunk_x: i32, chunk_z: i32) -> Result<Self> {
         let chunk = state
-            .database
+            .world
+            .resource::<Database>()
+            .await?
             .get_chunk(chunk_x, chunk_z, "overworld".to_string())
             .await?
             .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
@@ -0,0 +1,22 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// The disconnect packet is sent by the server to kick a client that's in the play state.
+///
+/// For clients that are still logging in, use [super::login_disconnect::LoginDisconnect].
+#[derive(NetEncode)]
+pub struct Disconnect {
+    #[encode(default = VarInt::from(0x1A))]
+    pub packet_id: VarInt,
+    /// The reason, as a JSON text component.
+    pub reason: String,
+}
+
+impl Disconnect {
+    /// Creates a disconnect packet with a plain text reason.
+    pub fn from_text(reason: impl Into<String>) -> Self {
+        let reason = serde_json::json!({ "text": reason.into() });
+        Self::new_auto(reason.to_string())
+    }
+}
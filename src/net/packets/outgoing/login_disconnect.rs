@@ -8,5 +8,14 @@ use ferrumc_macros::NetEncode;
 pub struct LoginDisconnect {
     #[encode(default = VarInt::from(0x00))]
     pub packet_id: VarInt,
+    /// The reason, as a JSON text component.
     pub reason: String,
 }
+
+impl LoginDisconnect {
+    /// Creates a disconnect packet with a plain text reason.
+    pub fn from_text(reason: impl Into<String>) -> Self {
+        let reason = serde_json::json!({ "text": reason.into() });
+        Self::new_auto(reason.to_string())
+    }
+}
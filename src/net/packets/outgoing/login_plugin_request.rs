@@ -21,3 +21,22 @@ impl LoginPluginRequest {
         Self::new("minecraft:brand", str_buffer)
     }
 }
+
+/// A plugin request sent during login, which the client (or the proxy in front of it) has to
+/// answer with a [crate::net::packets::incoming::login_plugin_response::LoginPluginResponse].
+///
+/// Not to be confused with [LoginPluginRequest], which is the plugin message sent in play state.
+#[derive(NetEncode)]
+pub struct LoginPluginQuery {
+    #[encode(default = VarInt::from(0x04))]
+    pub packet_id: VarInt,
+    pub message_id: VarInt,
+    pub channel: String,
+    pub data: Vec<u8>,
+}
+
+impl LoginPluginQuery {
+    pub fn new(message_id: i32, channel: impl Into<String>, data: Vec<u8>) -> Self {
+        Self::new_auto(VarInt::from(message_id), channel.into(), data)
+    }
+}
@@ -9,9 +9,9 @@ pub struct LoginSuccess {
     pub packet_id: VarInt,
     pub uuid: Vec<u8>,
     pub username: String,
-    // Just set this to 0
     pub property_count: VarInt,
-    // TODO: Figure out how what in the everloving fuck this is
+    /// The player's profile properties, e.g. the skin (`textures`). Only set when forwarded by a
+    /// proxy, since we don't authenticate with Mojang.
     pub properties: Vec<Property>,
 }
 
@@ -21,5 +21,5 @@ pub struct Property {
     pub value: String,
     pub is_signed: bool,
     // Only if is_signed is true
-    pub signature: String,
+    pub signature: Option<String>,
 }
@@ -1,5 +1,6 @@
 pub mod chunk_and_light_data;
 pub mod default_spawn_position;
+pub mod disconnect;
 pub mod keep_alive;
 pub mod login_disconnect;
 pub mod login_play;
@@ -9,4 +10,5 @@ pub mod ping;
 pub mod set_center_chunk;
 pub mod status;
 pub mod synchronize_player_position;
+pub mod system_chat_message;
 pub mod player_info_update;
@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Shows a message from the server in the client's chat (or above the hotbar if `overlay` is set).
+#[derive(NetEncode)]
+pub struct SystemChatMessage {
+    #[encode(default = VarInt::from(0x64))]
+    pub packet_id: VarInt,
+    /// The message, as a JSON text component.
+    pub content: String,
+    pub overlay: bool,
+}
+
+impl SystemChatMessage {
+    /// Creates a chat message with plain text content.
+    pub fn from_text(content: impl Into<String>) -> Self {
+        let content = serde_json::json!({ "text": content.into() });
+        Self::new_auto(content.to_string(), false)
+    }
+}
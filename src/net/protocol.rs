@@ -0,0 +1,11 @@
+//! The protocol version the server speaks. The packets in [crate::net::packets] are written for
+//! [PROTOCOL_VERSION] only, so clients on other versions are refused when they try to log in.
+
+/// The protocol version 1.20 and 1.20.1 clients send in the handshake.
+pub const PROTOCOL_VERSION: i32 = 763;
+/// The game version using [PROTOCOL_VERSION], as shown to clients.
+pub const VERSION_NAME: &str = "1.20.1";
+
+pub fn is_supported(protocol_version: i32) -> bool {
+    protocol_version == PROTOCOL_VERSION
+}
@@ -0,0 +1,243 @@
+//! Parsing for the HAProxy PROXY protocol header, which load balancers put in front of the
+//! connection to tell the server the client's real address.
+//!
+//! See <https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt>
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::utils::prelude::*;
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+/// The longest a v1 header can be, including the CRLF.
+const V1_MAX_LENGTH: usize = 107;
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+const V2_COMMAND_LOCAL: u8 = 0x0;
+const V2_COMMAND_PROXY: u8 = 0x1;
+const V2_FAMILY_INET: u8 = 0x1;
+const V2_FAMILY_INET6: u8 = 0x2;
+
+/// Reads a v1 or v2 PROXY header from the start of the stream, and nothing after it.
+///
+/// Returns the client's address, or `None` if the header doesn't carry one (v1 `UNKNOWN`, v2
+/// `LOCAL` or a non-IP address family), in which case the peer address should be used.
+pub async fn read_proxy_header<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<SocketAddr>> {
+    // Both versions are at least 12 bytes long, so this never reads past the header.
+    let mut start = [0u8; 12];
+    reader.read_exact(&mut start).await?;
+
+    if start == V2_SIGNATURE {
+        read_v2(reader).await
+    } else if start.starts_with(V1_PREFIX) {
+        read_v1(reader, &start).await
+    } else {
+        Err(Error::InvalidProxyHeader(
+            "Connection didn't start with a PROXY header".to_string(),
+        ))
+    }
+}
+
+async fn read_v1<R: AsyncRead + Unpin>(reader: &mut R, start: &[u8]) -> Result<Option<SocketAddr>> {
+    let mut line = start.to_vec();
+    // Read byte by byte, so nothing after the header is consumed.
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LENGTH {
+            return Err(Error::InvalidProxyHeader(
+                "v1 header is too long".to_string(),
+            ));
+        }
+        line.push(reader.read_u8().await?);
+    }
+
+    let line = std::str::from_utf8(&line[V1_PREFIX.len()..line.len() - 2])
+        .map_err(|_| Error::InvalidProxyHeader("v1 header isn't valid ASCII".to_string()))?;
+    let parts = line.split(' ').collect::<Vec<_>>();
+
+    match parts.as_slice() {
+        ["UNKNOWN", ..] => Ok(None),
+        [protocol @ ("TCP4" | "TCP6"), source, _destination, source_port, _destination_port] => {
+            let ip = source
+                .parse::<IpAddr>()
+                .map_err(|_| invalid_v1_field("source address", source))?;
+            if ip.is_ipv4() != (*protocol == "TCP4") {
+                return Err(invalid_v1_field("source address", source));
+            }
+            let port = source_port
+                .parse::<u16>()
+                .map_err(|_| invalid_v1_field("source port", source_port))?;
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        _ => Err(Error::InvalidProxyHeader(format!(
+            "Malformed v1 header: {}",
+            line
+        ))),
+    }
+}
+
+fn invalid_v1_field(field: &str, value: &str) -> Error {
+    Error::InvalidProxyHeader(format!("Invalid {} in v1 header: {}", field, value))
+}
+
+async fn read_v2<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<SocketAddr>> {
+    let version_command = reader.read_u8().await?;
+    let family_protocol = reader.read_u8().await?;
+    let length = reader.read_u16().await? as usize;
+
+    // Always read the whole header (including any TLVs), so the handshake comes next.
+    let mut payload = vec![0u8; length];
+    reader.read_exact(&mut payload).await?;
+
+    if version_command >> 4 != 2 {
+        return Err(Error::InvalidProxyHeader(format!(
+            "Unsupported version: {}",
+            version_command >> 4
+        )));
+    }
+
+    match version_command & 0x0F {
+        V2_COMMAND_LOCAL => return Ok(None),
+        V2_COMMAND_PROXY => {}
+        command => {
+            return Err(Error::InvalidProxyHeader(format!(
+                "Unknown v2 command: {}",
+                command
+            )))
+        }
+    }
+
+    let address = match family_protocol >> 4 {
+        V2_FAMILY_INET if payload.len() >= 12 => {
+            let ip = Ipv4Addr::new(payload[0], payload[1], payload[2], payload[3]);
+            let port = u16::from_be_bytes([payload[8], payload[9]]);
+            Some(SocketAddr::new(IpAddr::V4(ip), port))
+        }
+        V2_FAMILY_INET6 if payload.len() >= 36 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&payload[..16]);
+            let port = u16::from_be_bytes([payload[32], payload[33]]);
+            Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port))
+        }
+        V2_FAMILY_INET | V2_FAMILY_INET6 => {
+            return Err(Error::InvalidProxyHeader(
+                "v2 address block is too short".to_string(),
+            ))
+        }
+        // UNSPEC or UNIX sockets, there's no address we can use.
+        _ => None,
+    };
+
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    /// Writes `bytes` to one end of a local socket pair, and returns the other end.
+    async fn socket_with(bytes: &[u8]) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        client.write_all(bytes).await.unwrap();
+        // Closing the client makes reading past the data fail instead of hanging.
+        client.shutdown().await.unwrap();
+        server
+    }
+
+    async fn remaining(stream: &mut TcpStream) -> Vec<u8> {
+        let mut rest = Vec::new();
+        stream.read_to_end(&mut rest).await.unwrap();
+        rest
+    }
+
+    #[tokio::test]
+    async fn test_v1_tcp4() {
+        let mut stream =
+            socket_with(b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 25565\r\n\x10\x00").await;
+        let address = read_proxy_header(&mut stream).await.unwrap();
+        assert_eq!(address, Some("192.168.0.1:56324".parse().unwrap()));
+        assert_eq!(remaining(&mut stream).await, [0x10, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn test_v1_tcp6_and_unknown() {
+        let mut stream = socket_with(b"PROXY TCP6 2001:db8::1 2001:db8::2 4000 25565\r\n").await;
+        let address = read_proxy_header(&mut stream).await.unwrap();
+        assert_eq!(address, Some("[2001:db8::1]:4000".parse().unwrap()));
+
+        let mut stream = socket_with(b"PROXY UNKNOWN\r\n\x10").await;
+        assert_eq!(read_proxy_header(&mut stream).await.unwrap(), None);
+        assert_eq!(remaining(&mut stream).await, [0x10]);
+    }
+
+    #[tokio::test]
+    async fn test_v1_malformed() {
+        let mut stream = socket_with(b"PROXY TCP4 192.168.0.1 25565\r\n").await;
+        assert!(read_proxy_header(&mut stream).await.is_err());
+
+        let mut stream = socket_with(b"PROXY TCP4 2001:db8::1 192.168.0.11 1 2\r\n").await;
+        assert!(read_proxy_header(&mut stream).await.is_err());
+
+        let mut stream = socket_with(&[b'P'; 200]).await;
+        assert!(read_proxy_header(&mut stream).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_v2_inet() {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[0x21, 0x11, 0x00, 12 + 3]);
+        header.extend_from_slice(&[10, 0, 0, 7, 10, 0, 0, 1]);
+        header.extend_from_slice(&4321u16.to_be_bytes());
+        header.extend_from_slice(&25565u16.to_be_bytes());
+        // A TLV, which is skipped.
+        header.extend_from_slice(&[0x04, 0x00, 0x00]);
+        header.push(0x10);
+
+        let mut stream = socket_with(&header).await;
+        let address = read_proxy_header(&mut stream).await.unwrap();
+        assert_eq!(address, Some("10.0.0.7:4321".parse().unwrap()));
+        assert_eq!(remaining(&mut stream).await, [0x10]);
+    }
+
+    #[tokio::test]
+    async fn test_v2_inet6() {
+        let source: Ipv6Addr = "2001:db8::7".parse().unwrap();
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[0x21, 0x21, 0x00, 36]);
+        header.extend_from_slice(&source.octets());
+        header.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        header.extend_from_slice(&4321u16.to_be_bytes());
+        header.extend_from_slice(&25565u16.to_be_bytes());
+
+        let mut stream = socket_with(&header).await;
+        let address = read_proxy_header(&mut stream).await.unwrap();
+        assert_eq!(address, Some(SocketAddr::new(IpAddr::V6(source), 4321)));
+    }
+
+    #[tokio::test]
+    async fn test_v2_local() {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[0x20, 0x00, 0x00, 0x00, 0x10]);
+
+        let mut stream = socket_with(&header).await;
+        assert_eq!(read_proxy_header(&mut stream).await.unwrap(), None);
+        assert_eq!(remaining(&mut stream).await, [0x10]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_header() {
+        // A handshake packet, which is what a client connecting directly would send.
+        let mut stream = socket_with(&[
+            0x10, 0x00, 0xFB, 0x05, 0x09, b'l', b'o', b'c', b'a', b'l', b'h', b'o',
+        ])
+        .await;
+        assert!(read_proxy_header(&mut stream).await.is_err());
+    }
+}
@@ -5,62 +5,77 @@ use ferrumc_codec::enc::NetEncode;
 use tokio::sync::RwLock;
 use tracing::{debug, error, warn};
 
+use crate::ecs::entity::Entity;
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::world_events::ChunkSentEvent;
 use crate::net::packets::incoming::client_info::ClientInfo;
 use crate::net::packets::outgoing::chunk_and_light_data::ChunkDataAndUpdateLight;
 use crate::net::packets::outgoing::set_center_chunk::SetCenterChunk;
-use crate::net::systems::System;
+use crate::net::systems::tick_system::{TickPhase, TICKS_PER_SECOND};
 use crate::net::{Connection, ConnectionWrapper};
 use crate::state::GlobalState;
 use crate::utils::components::last_chunk_tx_pos::LastChunkTxPos;
 use crate::utils::components::player::Player;
 use crate::utils::encoding::position::Position;
+use crate::utils::metrics::METRICS;
 use crate::utils::prelude::*;
-use ferrumc_macros::AutoGenName;
+use ferrumc_macros::tick_phase;
 
 pub const DEFAULT_CHUNK_RADIUS: i8 = 16;
-const CHUNK_TX_INTERVAL_MS: u64 = 50000;
 
-#[derive(AutoGenName)]
+/// Sends every player the chunks around them again, every [Self::INTERVAL] ticks.
+#[tick_phase(reads(Player, Position, ConnectionWrapper, ClientInfo))]
 pub struct ChunkSender;
 
+impl ChunkSender {
+    const INTERVAL: u64 = 50 * TICKS_PER_SECOND as u64;
+}
+
 #[async_trait]
-impl System for ChunkSender {
-    async fn run(&self, state: GlobalState) {
-        let mut interval =
-            tokio::time::interval(std::time::Duration::from_millis(CHUNK_TX_INTERVAL_MS));
-        loop {
-            interval.tick().await;
-
-            // Get all the Players, instead of all the *entities*. The player is just a filter.
-            let query = state.world.query::<&Player>();
-            let send_to = query.iter().await.collect::<Vec<_>>();
-
-            send_to.into_iter().for_each(|(entity_id, player)| {
-                debug!("Sending chunk to player: {}", player.get_username());
-                drop(player);
-                let state = state.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = ChunkSender::send_chunks_to_player(state, entity_id).await {
-                        error!("Failed to send chunk to player: {}", e);
-                    }
-                });
-            });
-        }
+impl TickPhase for ChunkSender {
+    fn name(&self) -> &'static str {
+        "ChunkSender"
     }
 
-    fn name(&self) -> &'static str {
-        Self::type_name()
+    async fn tick(&self, state: &GlobalState, tick: u64) -> Result<()> {
+        if !tick.is_multiple_of(Self::INTERVAL) {
+            return Ok(());
+        }
+
+        let mut query = state
+            .world
+            .query::<(&Player, &Position, &ConnectionWrapper)>();
+        while let Some((entity_id, (player, pos, conn))) = query.next().await {
+            debug!("Sending chunk to player: {}", player.get_username());
+            let view_distance = state
+                .world
+                .get_component::<ClientInfo>(entity_id)
+                .await
+                .map_or(DEFAULT_CHUNK_RADIUS, |c| c.view_distance);
+
+            // The chunks are sent in the background, so a big view distance doesn't hold up the
+            // tick.
+            let state = state.clone();
+            let pos = pos.clone();
+            let conn = conn.0.clone();
+            tokio::spawn(async move {
+                if let Err(e) =
+                    ChunkSender::send_chunks(state, entity_id, pos, view_distance, conn).await
+                {
+                    error!("Failed to send chunk to player: {}", e);
+                }
+            });
+        }
+        Ok(())
     }
 }
 
 impl ChunkSender {
     pub async fn send_chunks_to_player_if_needed(
         state: GlobalState,
-        entity_id: impl TryInto<usize>,
+        entity_id: Entity,
         current_pos: (i32, i32),
     ) -> Result<()> {
-        let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
-
         let mut last_chunk_tx_pos = state
             .world
             .get_component_storage()
@@ -86,27 +101,19 @@ impl ChunkSender {
 
         Ok(())
     }
-    pub async fn send_chunks_to_player(
-        state: GlobalState,
-        entity_id: impl TryInto<usize>,
-    ) -> Result<()> {
-        let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
-
+    pub async fn send_chunks_to_player(state: GlobalState, entity_id: Entity) -> Result<()> {
         let (player, c_pos, c_conn) = state
             .world
             .get_components::<(Player, Position, ConnectionWrapper)>(entity_id)
             .await?;
 
-        let c_info = state
+        let view_distance = state
             .world
             .get_component::<ClientInfo>(entity_id)
             .await
-            .ok();
+            .map_or(DEFAULT_CHUNK_RADIUS, |c| c.view_distance);
 
         let pos = c_pos.clone();
-        let view_distance: i8 = c_info
-            .as_ref()
-            .map_or(DEFAULT_CHUNK_RADIUS, |c| c.view_distance);
         let conn = c_conn.0.clone();
 
         drop(c_pos);
@@ -120,9 +127,27 @@ impl ChunkSender {
 
         drop(player);
 
+        ChunkSender::send_chunks(state, entity_id, pos, view_distance, conn).await
+    }
+
+    async fn send_chunks(
+        state: GlobalState,
+        entity_id: Entity,
+        pos: Position,
+        view_distance: i8,
+        conn: Arc<RwLock<Connection>>,
+    ) -> Result<()> {
         ChunkSender::send_set_center_chunk(&pos, conn.clone()).await?;
-        ChunkSender::send_chunk_data_to_player(state.clone(), &pos, view_distance, conn.clone())
-            .await?;
+        let count = ChunkSender::send_chunk_data_to_player(
+            state.clone(),
+            &pos,
+            view_distance,
+            conn.clone(),
+        )
+        .await?;
+
+        let event = ChunkSentEvent::new(entity_id, (pos.x >> 4, pos.z >> 4), count);
+        state.dispatch_event(event).await;
 
         Ok(())
     }
@@ -132,8 +157,9 @@ impl ChunkSender {
         pos: &Position,
         player_view_distance: i8,
         conn: Arc<RwLock<Connection>>,
-    ) -> Result<()> {
+    ) -> Result<usize> {
         let start = std::time::Instant::now();
+        let mut sent = 0;
 
         let pos_x = pos.x;
         let pos_z = pos.z;
@@ -153,16 +179,21 @@ impl ChunkSender {
                     warn!("Failed to send chunk to player: {} ; Cancelling.", e);
                     break 'x;
                 }
+                sent += 1;
             }
         }
 
-        // check the size of a single chunk and multiply it by the number of chunks sent
-        let sample_chunk =
-            ChunkDataAndUpdateLight::new(state.clone(), pos_x >> 4, pos_z >> 4).await?;
-        let mut vec = vec![];
-        sample_chunk.net_encode(&mut vec).await?;
-        let chunk_rad_axis = chunk_radius * 2 + 1;
-        debug!(
+        METRICS.chunk_send.observe(start.elapsed());
+
+        // check the size of a single chunk and multiply it by the number of chunks sent. Only for
+        // the log, so a missing center chunk doesn't fail the send.
+        if let Ok(sample_chunk) =
+            ChunkDataAndUpdateLight::new(state.clone(), pos_x >> 4, pos_z >> 4).await
+        {
+            let mut vec = vec![];
+            sample_chunk.net_encode(&mut vec).await?;
+            let chunk_rad_axis = chunk_radius * 2 + 1;
+            debug!(
                 "Send {}({}x{}) chunks to player in {:?}. Approximately {} kb of data (~{} kb per chunk)",
                 chunk_rad_axis * chunk_rad_axis,
                 chunk_rad_axis,
@@ -171,8 +202,9 @@ impl ChunkSender {
                 vec.len() as i32 * chunk_rad_axis * chunk_rad_axis / 1024,
                 vec.len() as i32 / 1024
             );
+        }
 
-        Ok(())
+        Ok(sent)
     }
     async fn send_set_center_chunk(pos: &Position, conn: Arc<RwLock<Connection>>) -> Result<()> {
         let packet = SetCenterChunk::new(pos.x >> 4, pos.z >> 4);
@@ -0,0 +1,60 @@
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use tracing::{debug, error, info};
+
+use ferrumc_macros::{system, AutoGenName};
+
+use crate::net::systems::System;
+use crate::state::GlobalState;
+use crate::utils::config::{get_config_path, reload_global_config};
+
+/// How often the config file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Reloads the config when the config file changes, see [reload_global_config].
+#[system]
+#[derive(AutoGenName)]
+pub struct ConfigWatcher;
+
+#[async_trait]
+impl System for ConfigWatcher {
+    async fn run(&self, _state: GlobalState) {
+        let path = match get_config_path() {
+            Ok(path) => path,
+            Err(e) => {
+                error!("Not watching the config file: {}", e);
+                return;
+            }
+        };
+        let mut last_modified = modified(&path);
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let modified = modified(&path);
+            if modified.is_none() || modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            debug!("Config file changed, reloading");
+            match reload_global_config() {
+                Ok(true) => info!("Reloaded the config"),
+                Ok(false) => {}
+                Err(e) => error!("Not reloading the config, keeping the current one: {}", e),
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+fn modified(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
@@ -2,9 +2,10 @@ use crate::net::systems::System;
 use crate::state::GlobalState;
 use crate::utils::prelude::*;
 use async_trait::async_trait;
-use ferrumc_macros::AutoGenName;
-use tracing::{debug, error, info_span, Instrument};
+use ferrumc_macros::{system, AutoGenName};
+use tracing::{debug, error, trace};
 
+#[system]
 #[derive(AutoGenName)]
 pub struct ConnectionHandler;
 
@@ -26,13 +27,9 @@ impl System for ConnectionHandler {
 impl ConnectionHandler {
     async fn handle_connections(state: GlobalState) -> Result<()> {
         loop {
-            let (stream, _) = state.server_stream.accept().await?;
-            debug!("Accepted connection from {:?}", stream.peer_addr()?);
-            let addy = stream.peer_addr()?;
-            tokio::task::spawn(
-                Self::handle_connection(state.clone(), stream)
-                    .instrument(info_span!("conn", %addy).or_current()),
-            );
+            let (stream, addy) = state.server_stream.accept().await?;
+            trace!("New connection from {:?}", addy);
+            tokio::task::spawn(Self::handle_connection(state.clone(), stream));
         }
     }
 
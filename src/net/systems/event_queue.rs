@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use tracing::debug;
+
+use ferrumc_macros::{system, AutoGenName};
+
+use crate::net::systems::System;
+use crate::state::GlobalState;
+
+/// Dispatches the events queued with
+/// [crate::events::creation::dispatcher::EventDispatcherExt::queue_event], in the order they were
+/// queued.
+#[system]
+#[derive(AutoGenName)]
+pub struct EventQueueSystem;
+
+#[async_trait]
+impl System for EventQueueSystem {
+    async fn run(&self, state: GlobalState) {
+        debug!("EventQueueSystem is starting up");
+        state.event_dispatcher.run_queue(state.clone()).await;
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
@@ -1,106 +1,78 @@
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
-use tokio::sync::RwLockReadGuard;
 use tracing::{trace, warn};
 
-use ferrumc_macros::AutoGenName;
+use ferrumc_macros::tick_phase;
 
 use crate::net::packets::outgoing::keep_alive::KeepAlivePacketOut;
-use crate::net::systems::System;
-use crate::net::{Connection, ConnectionWrapper};
+use crate::net::systems::tick_system::{TickPhase, TICKS_PER_SECOND};
+use crate::net::{drop_conn, ConnectionWrapper};
 use crate::state::GlobalState;
 use crate::utils::components::keep_alive::KeepAlive;
 use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
 
-#[derive(AutoGenName)]
+/// Sends the players keep alives, and drops the connections that went quiet.
+#[tick_phase(reads(Player, ConnectionWrapper), writes(KeepAlive))]
 pub struct KeepAliveSystem;
 
-#[async_trait]
-impl System for KeepAliveSystem {
-    async fn run(&self, state: GlobalState) {
-        let sender = KeepAliveSystem::sender(state.clone());
-        let receiver = KeepAliveSystem::receiver(state.clone());
-        tokio::join!(sender, receiver);
-    }
+impl KeepAliveSystem {
+    /// Idle connections are looked for every this many ticks.
+    const CHECK_INTERVAL: u64 = 5 * TICKS_PER_SECOND as u64;
+    /// Keep alives are sent every this many ticks.
+    const SEND_INTERVAL: u64 = 15 * TICKS_PER_SECOND as u64;
+    const TIMEOUT: Duration = Duration::from_secs(30);
+}
 
+#[async_trait]
+impl TickPhase for KeepAliveSystem {
     fn name(&self) -> &'static str {
-        Self::type_name()
+        "KeepAliveSystem"
     }
-}
-impl KeepAliveSystem {
-    async fn sender(state: GlobalState) {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(15));
+
+    async fn tick(&self, state: &GlobalState, tick: u64) -> Result<()> {
+        if !tick.is_multiple_of(Self::CHECK_INTERVAL) {
+            return Ok(());
+        }
+        let send = tick.is_multiple_of(Self::SEND_INTERVAL);
+
+        // Dropping a connection deletes its entity, so that waits until the query is done.
+        let mut idle = Vec::new();
         let mut query = state
             .world
             .query::<(&Player, &mut KeepAlive, &ConnectionWrapper)>();
-
-        loop {
-            interval.tick().await;
-
-            while let Some((_, (player, mut keep_alive, conn))) = query.next().await {
-                if keep_alive.last_sent.elapsed().as_secs() > 30 {
-                    let conn = conn.0.read().await;
-                    warn!("Dropping connection {} due to inactivity", conn.id);
-                    if let Err(err) = conn.drop_connection(state.clone()).await {
-                        warn!(
-                            "Error dropping connection {:?}: {:?}",
-                            conn.player_uuid, err
-                        );
-                    }
-                    continue;
-                }
-
-                keep_alive.data += 1;
-                keep_alive.last_sent = std::time::Instant::now();
-
-                let keep_alive_out = KeepAlivePacketOut::new_auto(keep_alive.data);
-                let conn = conn.0.write().await;
-
-                trace!("Sending keep alive packet to player: {:?}", player);
-                if let Err(e) = conn.send_packet(keep_alive_out).await {
-                    warn!("Error sending keep alive packet: {:?}", e);
-                }
+        while let Some((entity, (player, mut keep_alive, conn))) = query.next().await {
+            if keep_alive.last_sent.elapsed() > Self::TIMEOUT {
+                idle.push((entity, player.username.clone()));
+                continue;
+            }
+            if !send {
+                continue;
             }
-        }
-    }
-    async fn receiver(state: GlobalState) {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
-        let mut query = state.world.query::<(&KeepAlive, &ConnectionWrapper)>();
-
-        loop {
-            interval.tick().await;
-
-            while let Some((_, (keep_alive, conn_wrapper))) = query.next().await {
-                if keep_alive.last_sent.elapsed().as_secs() <= 30 {
-                    continue;
-                }
 
-                let conn = conn_wrapper.0.read().await;
-                let player = state.world.get_component::<Player>(conn.id).await;
+            keep_alive.data += 1;
+            keep_alive.last_sent = Instant::now();
 
-                let username = player
-                    .as_ref()
-                    .map(|p| p.username.as_str())
-                    .unwrap_or("Unknown<!>Player");
+            let keep_alive_out = KeepAlivePacketOut::new_auto(keep_alive.data);
+            let conn = conn.0.read().await;
 
-                Self::drop_connection(conn, username, state.clone()).await;
+            trace!("Sending keep alive packet to player: {:?}", player);
+            if let Err(e) = conn.send_packet(keep_alive_out).await {
+                warn!("Error sending keep alive packet: {:?}", e);
             }
         }
-    }
+        drop(query);
 
-    async fn drop_connection(
-        conn: RwLockReadGuard<'_, Connection>,
-        username: &str,
-        state: GlobalState,
-    ) {
-        warn!(
-            "Dropping player `{}`'s connection due to inactivity",
-            username
-        );
-        if let Err(err) = conn.drop_connection(state).await {
+        for (entity, username) in idle {
             warn!(
-                "Error dropping connection {:?}: {:?}",
-                conn.player_uuid, err
+                "Dropping player `{}`'s connection due to inactivity",
+                username
             );
+            if let Err(err) = drop_conn(entity, state.clone()).await {
+                warn!("Error dropping connection {}: {:?}", entity, err);
+            }
         }
+        Ok(())
     }
 }
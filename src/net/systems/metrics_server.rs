@@ -0,0 +1,105 @@
+//! A minimal HTTP server for Prometheus to scrape [crate::utils::metrics] from, at `/metrics`.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info};
+
+use ferrumc_macros::{system, AutoGenName};
+
+use crate::net::systems::System;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::metrics::METRICS;
+use crate::utils::prelude::*;
+
+/// Requests are only a request line and a few headers, anything bigger isn't a scrape.
+const MAX_REQUEST_LENGTH: usize = 8192;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Serves metrics on `metrics.host:metrics.port` if `metrics.enabled` is set.
+#[system]
+#[derive(AutoGenName)]
+pub struct MetricsSystem;
+
+#[async_trait]
+impl System for MetricsSystem {
+    async fn run(&self, state: GlobalState) {
+        let config = get_global_config();
+        if !config.metrics.enabled {
+            debug!("Metrics are disabled");
+            return;
+        }
+
+        let address = format!("{}:{}", config.metrics.host, config.metrics.port);
+        let listener = match TcpListener::bind(&address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Failed to bind metrics to {}: {:?}", address, e);
+                return;
+            }
+        };
+        info!("Metrics available at http://{}/metrics", address);
+
+        if let Err(e) = serve(listener, state).await {
+            error!("There was an error in the MetricsSystem: {:?}", e);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+/// Answers metrics requests on `listener` until it fails.
+pub async fn serve(listener: TcpListener, state: GlobalState) -> Result<()> {
+    loop {
+        let (stream, address) = listener.accept().await?;
+        let state = state.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(REQUEST_TIMEOUT, handle_request(stream, state)).await {
+                Ok(Err(e)) => debug!("Metrics request from {} failed: {:?}", address, e),
+                Err(_) => debug!("Metrics request from {} timed out", address),
+                Ok(Ok(())) => {}
+            }
+        });
+    }
+}
+
+async fn handle_request(mut stream: TcpStream, state: GlobalState) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_LENGTH {
+            return Ok(());
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let response = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = METRICS.render(&state).await;
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                CONTENT_TYPE,
+                body.len(),
+                body
+            )
+        }
+        (Some("GET"), _) => {
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+        }
+        _ => "HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            .to_string(),
+    };
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
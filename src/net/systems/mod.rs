@@ -6,8 +6,14 @@ use crate::state::GlobalState;
 use crate::utils::prelude::*;
 
 pub mod chunk_sender;
+pub mod config_watcher;
 pub mod connection_handler;
+pub mod event_queue;
 pub mod keep_alive_system;
+pub mod metrics_server;
+pub mod query;
+pub mod rcon;
+pub mod scheduler;
 pub mod tick_system;
 
 #[async_trait]
@@ -17,16 +23,29 @@ pub trait System: Send + Sync {
     async fn kill(&self) {}
 }
 
-pub static ALL_SYSTEMS: &[&dyn System] = &[
-    &tick_system::TickSystem,
-    &keep_alive_system::KeepAliveSystem,
-    &chunk_sender::ChunkSender,
-    &connection_handler::ConnectionHandler,
-];
+/// A registered system, see `#[system]` in `ferrumc_macros`.
+pub struct SystemContainer {
+    system: &'static dyn System,
+}
+
+impl SystemContainer {
+    pub const fn new(system: &'static dyn System) -> Self {
+        Self { system }
+    }
+}
+
+inventory::collect!(SystemContainer);
+
+/// Every system registered with `#[system]`.
+pub fn all_systems() -> impl Iterator<Item = &'static dyn System> {
+    inventory::iter::<SystemContainer>
+        .into_iter()
+        .map(|container| container.system)
+}
 
 pub async fn start_all_systems(state: GlobalState) -> Result<()> {
     let handles = FuturesUnordered::new();
-    for system in ALL_SYSTEMS {
+    for system in all_systems() {
         let name = system.name();
 
         let handle = tokio::spawn(
@@ -44,7 +63,7 @@ pub async fn start_all_systems(state: GlobalState) -> Result<()> {
 
 pub async fn kill_all_systems() -> Result<()> {
     info!("Killing all systems...");
-    for system in ALL_SYSTEMS {
+    for system in all_systems() {
         system.kill().await;
     }
     Ok(())
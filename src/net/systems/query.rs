@@ -0,0 +1,392 @@
+//! A server for the GameSpy4 UDP query protocol, which server list sites use to get the MOTD, player
+//! count and player list.
+//!
+//! See <https://wiki.vg/Query>
+//!
+//! Clients first get a challenge token with a handshake, and then send it back with every stat
+//! request. Tokens are per address and expire after [CHALLENGE_LIFETIME], so the server can't be
+//! used to amplify traffic to a spoofed address.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use rand::random;
+use tokio::net::UdpSocket;
+use tracing::{debug, error, info, trace};
+
+use ferrumc_macros::{system, AutoGenName};
+
+use crate::net::packets::incoming::status::{random_motd, sample_players};
+use crate::net::protocol;
+use crate::net::systems::System;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+use crate::utils::text;
+
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const TYPE_HANDSHAKE: u8 = 9;
+const TYPE_STAT: u8 = 0;
+/// Only the lower 4 bits of each byte of the session id are used.
+const SESSION_ID_MASK: i32 = 0x0F0F0F0F;
+const CHALLENGE_LIFETIME: Duration = Duration::from_secs(30);
+/// At most this many addresses hold a token at once, so handshakes from spoofed addresses can't
+/// grow the store without limit.
+const MAX_CHALLENGE_TOKENS: usize = 4096;
+/// Full stat requests are padded with 4 extra bytes, which is how they're told apart from basic ones.
+const FULL_STAT_PADDING: usize = 4;
+const KEY_VALUE_HEADER: &[u8] = b"splitnum\0\x80\0";
+const PLAYERS_HEADER: &[u8] = b"\x01player_\0\0";
+/// The largest request we expect, a full stat request.
+const MAX_REQUEST_LENGTH: usize = 15;
+
+/// A request from a query client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryRequest {
+    Handshake { session_id: i32 },
+    BasicStat { session_id: i32, token: i32 },
+    FullStat { session_id: i32, token: i32 },
+}
+
+impl QueryRequest {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let (magic, rest) = data.split_first_chunk::<2>()?;
+        if *magic != MAGIC {
+            return None;
+        }
+        let (&packet_type, rest) = rest.split_first()?;
+        let (session_id, rest) = rest.split_first_chunk::<4>()?;
+        let session_id = i32::from_be_bytes(*session_id) & SESSION_ID_MASK;
+
+        match packet_type {
+            TYPE_HANDSHAKE => Some(QueryRequest::Handshake { session_id }),
+            TYPE_STAT => {
+                let (token, rest) = rest.split_first_chunk::<4>()?;
+                let token = i32::from_be_bytes(*token);
+                if rest.len() >= FULL_STAT_PADDING {
+                    Some(QueryRequest::FullStat { session_id, token })
+                } else {
+                    Some(QueryRequest::BasicStat { session_id, token })
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What's reported in the stat responses.
+#[derive(Debug, Clone, Default)]
+pub struct QueryInfo {
+    pub motd: String,
+    pub version: String,
+    pub map: String,
+    pub max_players: i32,
+    pub players: Vec<String>,
+    pub host_ip: String,
+    pub host_port: u16,
+}
+
+impl QueryInfo {
+    async fn collect(state: &GlobalState) -> Self {
+        let config = get_global_config();
+        Self {
+            motd: text::to_legacy_text(&random_motd()),
+            version: protocol::VERSION_NAME.to_string(),
+            map: config.world.clone(),
+            max_players: config.max_players,
+            players: sample_players(state)
+                .await
+                .into_iter()
+                .map(|sample| sample.name)
+                .collect(),
+            host_ip: config.host.clone(),
+            host_port: config.port as u16,
+        }
+    }
+
+    pub fn basic_stat(&self, session_id: i32) -> Vec<u8> {
+        let mut response = response_header(TYPE_STAT, session_id);
+        for value in [
+            self.motd.as_str(),
+            "SMP",
+            &self.map,
+            &self.players.len().to_string(),
+            &self.max_players.to_string(),
+        ] {
+            write_string(&mut response, value);
+        }
+        response.extend_from_slice(&self.host_port.to_le_bytes());
+        write_string(&mut response, &self.host_ip);
+        response
+    }
+
+    pub fn full_stat(&self, session_id: i32) -> Vec<u8> {
+        let mut response = response_header(TYPE_STAT, session_id);
+        response.extend_from_slice(KEY_VALUE_HEADER);
+        for (key, value) in [
+            ("hostname", self.motd.clone()),
+            ("gametype", "SMP".to_string()),
+            ("game_id", "MINECRAFT".to_string()),
+            ("version", self.version.clone()),
+            ("plugins", String::new()),
+            ("map", self.map.clone()),
+            ("numplayers", self.players.len().to_string()),
+            ("maxplayers", self.max_players.to_string()),
+            ("hostport", self.host_port.to_string()),
+            ("hostip", self.host_ip.clone()),
+        ] {
+            write_string(&mut response, key);
+            write_string(&mut response, &value);
+        }
+        response.push(0);
+
+        response.extend_from_slice(PLAYERS_HEADER);
+        for player in &self.players {
+            write_string(&mut response, player);
+        }
+        response.push(0);
+        response
+    }
+}
+
+fn response_header(packet_type: u8, session_id: i32) -> Vec<u8> {
+    let mut response = vec![packet_type];
+    response.extend_from_slice(&session_id.to_be_bytes());
+    response
+}
+
+fn write_string(buffer: &mut Vec<u8>, value: &str) {
+    // Null bytes would end the string early and break the rest of the response.
+    buffer.extend(value.bytes().filter(|&b| b != 0));
+    buffer.push(0);
+}
+
+/// The challenge tokens handed out to each address.
+#[derive(Default)]
+pub struct ChallengeTokens {
+    tokens: HashMap<SocketAddr, (i32, Instant)>,
+    last_pruned: Option<Instant>,
+}
+
+impl ChallengeTokens {
+    /// Hands out a new token to `address`, replacing its old one. When [MAX_CHALLENGE_TOKENS]
+    /// addresses already hold one, the oldest is forgotten.
+    pub fn issue(&mut self, address: SocketAddr, now: Instant) -> i32 {
+        let full = self.tokens.len() >= MAX_CHALLENGE_TOKENS && !self.tokens.contains_key(&address);
+        // Forget expired tokens every now and then, instead of on every handshake.
+        if full
+            || self
+                .last_pruned
+                .is_none_or(|pruned| now.duration_since(pruned) >= CHALLENGE_LIFETIME)
+        {
+            self.tokens
+                .retain(|_, (_, issued)| now.duration_since(*issued) < CHALLENGE_LIFETIME);
+            self.last_pruned = Some(now);
+        }
+        if self.tokens.len() >= MAX_CHALLENGE_TOKENS && !self.tokens.contains_key(&address) {
+            let oldest = self
+                .tokens
+                .iter()
+                .min_by_key(|(_, (_, issued))| *issued)
+                .map(|(oldest, _)| *oldest);
+            if let Some(oldest) = oldest {
+                self.tokens.remove(&oldest);
+            }
+        }
+        let token = random::<i32>() & i32::MAX;
+        self.tokens.insert(address, (token, now));
+        token
+    }
+
+    pub fn is_valid(&self, address: SocketAddr, token: i32, now: Instant) -> bool {
+        self.tokens
+            .get(&address)
+            .is_some_and(|(issued_token, issued)| {
+                *issued_token == token && now.duration_since(*issued) < CHALLENGE_LIFETIME
+            })
+    }
+}
+
+/// Answers query requests on `query_port` if `enable_query` is set.
+#[system]
+#[derive(AutoGenName)]
+pub struct QuerySystem;
+
+#[async_trait]
+impl System for QuerySystem {
+    async fn run(&self, state: GlobalState) {
+        let config = get_global_config();
+        if !config.enable_query {
+            debug!("Query is disabled");
+            return;
+        }
+
+        let address = format!("{}:{}", config.host, config.query_port);
+        let socket = match UdpSocket::bind(&address).await {
+            Ok(socket) => socket,
+            Err(e) => {
+                error!("Failed to bind query to {}: {:?}", address, e);
+                return;
+            }
+        };
+        info!("Query listening on {}", address);
+
+        if let Err(e) = serve(socket, state).await {
+            error!("There was an error in the QuerySystem: {:?}", e);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+/// Answers query requests on `socket` until it fails.
+pub async fn serve(socket: UdpSocket, state: GlobalState) -> Result<()> {
+    let mut tokens = ChallengeTokens::default();
+    // Leave room for one extra byte, so oversized requests can be told apart.
+    let mut buffer = [0u8; MAX_REQUEST_LENGTH + 1];
+
+    loop {
+        let (length, address) = socket.recv_from(&mut buffer).await?;
+        let Some(request) = QueryRequest::parse(&buffer[..length]) else {
+            trace!("Ignoring invalid query packet from {}", address);
+            continue;
+        };
+        let now = Instant::now();
+
+        let response = match request {
+            QueryRequest::Handshake { session_id } => {
+                let token = tokens.issue(address, now);
+                let mut response = response_header(TYPE_HANDSHAKE, session_id);
+                write_string(&mut response, &token.to_string());
+                response
+            }
+            QueryRequest::BasicStat { session_id, token }
+                if tokens.is_valid(address, token, now) =>
+            {
+                QueryInfo::collect(&state).await.basic_stat(session_id)
+            }
+            QueryRequest::FullStat { session_id, token }
+                if tokens.is_valid(address, token, now) =>
+            {
+                QueryInfo::collect(&state).await.full_stat(session_id)
+            }
+            _ => {
+                trace!(
+                    "Ignoring query with an invalid challenge token from {}",
+                    address
+                );
+                continue;
+            }
+        };
+
+        if let Err(e) = socket.send_to(&response, address).await {
+            debug!("Failed to send query response to {}: {:?}", address, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> QueryInfo {
+        QueryInfo {
+            motd: "A FerrumC Server".to_string(),
+            version: "1.20.1".to_string(),
+            map: "world".to_string(),
+            max_players: 20,
+            players: vec!["Notch".to_string(), "jeb_".to_string()],
+            host_ip: "127.0.0.1".to_string(),
+            host_port: 25565,
+        }
+    }
+
+    #[test]
+    fn test_parse_requests() {
+        let handshake = [0xFE, 0xFD, 0x09, 0x00, 0x00, 0x00, 0x01];
+        assert_eq!(
+            QueryRequest::parse(&handshake),
+            Some(QueryRequest::Handshake { session_id: 1 })
+        );
+
+        let basic = [
+            0xFE, 0xFD, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x91, 0x29, 0x5B,
+        ];
+        assert_eq!(
+            QueryRequest::parse(&basic),
+            Some(QueryRequest::BasicStat {
+                session_id: SESSION_ID_MASK,
+                token: 9513307
+            })
+        );
+
+        let mut full = basic.to_vec();
+        full.extend_from_slice(&[0; FULL_STAT_PADDING]);
+        assert!(matches!(
+            QueryRequest::parse(&full),
+            Some(QueryRequest::FullStat { .. })
+        ));
+
+        assert_eq!(QueryRequest::parse(&[0xFE, 0x01, 0x09, 0, 0, 0, 1]), None);
+        assert_eq!(QueryRequest::parse(&[0xFE, 0xFD, 0x00, 0, 0, 0, 1]), None);
+    }
+
+    #[test]
+    fn test_basic_stat() {
+        let response = info().basic_stat(1);
+        let mut expected = vec![0x00, 0x00, 0x00, 0x00, 0x01];
+        expected.extend_from_slice(b"A FerrumC Server\0SMP\0world\x002\x0020\0");
+        expected.extend_from_slice(&[0xDD, 0x63]);
+        expected.extend_from_slice(b"127.0.0.1\0");
+        assert_eq!(response, expected);
+    }
+
+    #[test]
+    fn test_full_stat() {
+        let response = info().full_stat(1);
+        assert_eq!(&response[5..16], KEY_VALUE_HEADER);
+        let players_start = response.len() - PLAYERS_HEADER.len() - b"Notch\0jeb_\0\0".len();
+        assert_eq!(
+            &response[players_start..],
+            b"\x01player_\0\0Notch\0jeb_\0\0".as_slice()
+        );
+        let key_values = &response[16..players_start];
+        assert!(key_values.ends_with(b"hostip\x00127.0.0.1\0\0"));
+        assert!(key_values
+            .windows(13)
+            .any(|window| window == b"numplayers\x002\0"));
+    }
+
+    #[test]
+    fn test_challenge_tokens() {
+        let address: SocketAddr = "10.0.0.1:4000".parse().unwrap();
+        let other: SocketAddr = "10.0.0.2:4000".parse().unwrap();
+        let now = Instant::now();
+        let mut tokens = ChallengeTokens::default();
+
+        let token = tokens.issue(address, now);
+        assert!(tokens.is_valid(address, token, now));
+        assert!(!tokens.is_valid(other, token, now));
+        assert!(!tokens.is_valid(address, token, now + CHALLENGE_LIFETIME));
+    }
+
+    #[test]
+    fn test_challenge_tokens_are_capped() {
+        let now = Instant::now();
+        let mut tokens = ChallengeTokens::default();
+        let first: SocketAddr = "10.0.0.1:4000".parse().unwrap();
+        let first_token = tokens.issue(first, now);
+
+        for port in 0..MAX_CHALLENGE_TOKENS as u16 {
+            let address = SocketAddr::from(([10, 0, 1, 1], port));
+            tokens.issue(address, now + Duration::from_millis(1));
+        }
+        assert_eq!(tokens.tokens.len(), MAX_CHALLENGE_TOKENS);
+        // The oldest token made room for the newest.
+        assert!(!tokens.is_valid(first, first_token, now));
+    }
+}
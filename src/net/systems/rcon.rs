@@ -0,0 +1,229 @@
+//! A server for the Source RCON protocol, which lets admin tools run commands remotely.
+//!
+//! See <https://developer.valvesoftware.com/wiki/Source_RCON_Protocol>
+//!
+//! Every packet is a little endian `i32` length, followed by a request id, a type and a null
+//! terminated body, and an empty string. A client has to authenticate with the `rcon_password`
+//! before it can run commands, which are run as [CommandSender::Console].
+
+use async_trait::async_trait;
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info, warn};
+
+use ferrumc_macros::{system, AutoGenName};
+
+use crate::commands::{dispatch_command, CommandSender};
+use crate::net::systems::System;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+pub const SERVERDATA_AUTH: i32 = 3;
+pub const SERVERDATA_AUTH_RESPONSE: i32 = 2;
+pub const SERVERDATA_EXECCOMMAND: i32 = 2;
+pub const SERVERDATA_RESPONSE_VALUE: i32 = 0;
+
+/// The request id sent back when authentication fails.
+pub const AUTH_FAILED_ID: i32 = -1;
+/// The smallest possible packet: request id, type and two null bytes.
+const MIN_PACKET_LENGTH: i32 = 10;
+/// The largest packet a client may send.
+const MAX_PACKET_LENGTH: i32 = 4096;
+/// Responses longer than this are split into multiple packets.
+const MAX_RESPONSE_BODY: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RconPacket {
+    pub request_id: i32,
+    pub packet_type: i32,
+    pub body: String,
+}
+
+impl RconPacket {
+    pub fn new(request_id: i32, packet_type: i32, body: impl Into<String>) -> Self {
+        Self {
+            request_id,
+            packet_type,
+            body: body.into(),
+        }
+    }
+
+    pub async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self> {
+        let length = reader.read_i32_le().await?;
+        if !(MIN_PACKET_LENGTH..=MAX_PACKET_LENGTH).contains(&length) {
+            return Err(Error::MalformedPacket(format!(
+                "Invalid RCON packet length: {}",
+                length
+            )));
+        }
+
+        let request_id = reader.read_i32_le().await?;
+        let packet_type = reader.read_i32_le().await?;
+        let mut body = vec![0u8; length as usize - 8];
+        reader.read_exact(&mut body).await?;
+
+        // Strip the body's null terminator and the empty string after it.
+        let end = body.iter().position(|&b| b == 0).unwrap_or(body.len());
+        body.truncate(end);
+
+        Ok(Self {
+            request_id,
+            packet_type,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        })
+    }
+
+    pub async fn write<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> Result<()> {
+        let body = self.body.as_bytes();
+        let mut buffer = Vec::with_capacity(body.len() + 14);
+        buffer.extend_from_slice(&(body.len() as i32 + MIN_PACKET_LENGTH).to_le_bytes());
+        buffer.extend_from_slice(&self.request_id.to_le_bytes());
+        buffer.extend_from_slice(&self.packet_type.to_le_bytes());
+        buffer.extend_from_slice(body);
+        buffer.extend_from_slice(&[0, 0]);
+        writer.write_all(&buffer).await?;
+        Ok(())
+    }
+}
+
+/// Listens for RCON connections on `rcon_port` if `enable_rcon` is set.
+#[system]
+#[derive(AutoGenName)]
+pub struct RconSystem;
+
+#[async_trait]
+impl System for RconSystem {
+    async fn run(&self, state: GlobalState) {
+        let config = get_global_config();
+        if !config.enable_rcon {
+            debug!("RCON is disabled");
+            return;
+        }
+        if config.rcon_password.is_empty() {
+            warn!("RCON is enabled but rcon_password is empty, not starting RCON");
+            return;
+        }
+
+        let address = format!("{}:{}", config.host, config.rcon_port);
+        let listener = match TcpListener::bind(&address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Failed to bind RCON to {}: {:?}", address, e);
+                return;
+            }
+        };
+        info!("RCON listening on {}", address);
+
+        if let Err(e) = serve(listener, config.rcon_password.clone(), state).await {
+            error!("There was an error in the RconSystem: {:?}", e);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+/// Accepts RCON clients on `listener` until it fails.
+pub async fn serve(listener: TcpListener, password: String, state: GlobalState) -> Result<()> {
+    loop {
+        let (stream, address) = listener.accept().await?;
+        debug!("New RCON connection from {}", address);
+        let password = password.clone();
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_client(stream, &password, state).await {
+                debug!("RCON connection from {} closed: {:?}", address, e);
+            }
+        });
+    }
+}
+
+async fn handle_client(mut stream: TcpStream, password: &str, state: GlobalState) -> Result<()> {
+    let address = stream.peer_addr()?;
+    let mut authenticated = false;
+
+    loop {
+        let packet = match RconPacket::read(&mut stream).await {
+            Ok(packet) => packet,
+            // The client hung up.
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        match packet.packet_type {
+            SERVERDATA_AUTH => {
+                if passwords_match(&packet.body, password) {
+                    authenticated = true;
+                    info!("RCON client {} authenticated", address);
+                    RconPacket::new(packet.request_id, SERVERDATA_AUTH_RESPONSE, "")
+                        .write(&mut stream)
+                        .await?;
+                } else {
+                    // Close the connection, so guessing the password takes a new connection for
+                    // every attempt.
+                    warn!("RCON client {} used the wrong password", address);
+                    RconPacket::new(AUTH_FAILED_ID, SERVERDATA_AUTH_RESPONSE, "")
+                        .write(&mut stream)
+                        .await?;
+                    return Ok(());
+                }
+            }
+            SERVERDATA_EXECCOMMAND if authenticated => {
+                let output = run_command(&packet.body, state.clone()).await;
+                for chunk in split_response(&output) {
+                    RconPacket::new(packet.request_id, SERVERDATA_RESPONSE_VALUE, chunk)
+                        .write(&mut stream)
+                        .await?;
+                }
+            }
+            SERVERDATA_EXECCOMMAND => {
+                RconPacket::new(AUTH_FAILED_ID, SERVERDATA_AUTH_RESPONSE, "")
+                    .write(&mut stream)
+                    .await?;
+                return Ok(());
+            }
+            packet_type => {
+                debug!("Ignoring RCON packet with unknown type {}", packet_type);
+            }
+        }
+    }
+}
+
+async fn run_command(command: &str, state: GlobalState) -> String {
+    match dispatch_command(command, &CommandSender::Console, state).await {
+        Ok(output) => output,
+        Err(Error::InvalidCommand(message)) => message,
+        Err(e) => {
+            debug!("RCON command {} failed: {:?}", command, e);
+            "An error occurred while running the command".to_string()
+        }
+    }
+}
+
+/// Compares the passwords in constant time, so how long the check takes doesn't tell a client how
+/// much of its guess was right. Only the length can be told apart.
+pub(crate) fn passwords_match(given: &str, password: &str) -> bool {
+    given.as_bytes().ct_eq(password.as_bytes()).into()
+}
+
+/// Splits a response into bodies of at most [MAX_RESPONSE_BODY] bytes, on character boundaries.
+fn split_response(output: &str) -> Vec<&str> {
+    if output.is_empty() {
+        return vec![""];
+    }
+    let mut chunks = Vec::new();
+    let mut rest = output;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(MAX_RESPONSE_BODY);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, remaining) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = remaining;
+    }
+    chunks
+}
@@ -0,0 +1,255 @@
+//! Runs the [TickPhase]s registered with `#[tick_phase]` every tick.
+//!
+//! Each tick phase declares which components it reads and writes. The phases are split into
+//! stages, where no two phases in a stage conflict, i.e. neither writes a component the other
+//! reads or writes. The phases in a stage run in parallel, and the stages run one after the other.
+//!
+//! Phases run in priority order (lower first, then by name), but only conflicting phases are
+//! guaranteed to see each other's changes in that order.
+//!
+//! Changes recorded in the world's [Commands](crate::ecs::commands::Commands) are applied after
+//! every stage, so the phases of the next stage see them. The component lifecycle events recorded
+//! during the stage are dispatched right after.
+//!
+//! The declarations aren't enforced, a phase that touches a component it didn't declare still
+//! works, but may end up running alongside a phase that holds that component's lock.
+
+use std::any::{type_name, TypeId};
+use std::time::Instant;
+
+use tracing::{debug, warn};
+
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::net::systems::tick_system::TickPhase;
+use crate::state::GlobalState;
+use crate::utils::metrics::METRICS;
+
+/// The components a [TickPhase] reads and writes.
+#[derive(Debug, Default, Clone)]
+pub struct SystemAccess {
+    reads: Vec<(TypeId, &'static str)>,
+    writes: Vec<(TypeId, &'static str)>,
+}
+
+impl SystemAccess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn read<T: 'static>(mut self) -> Self {
+        self.reads.push((TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+
+    pub fn write<T: 'static>(mut self) -> Self {
+        self.writes.push((TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+
+    /// Whether the two can't run at the same time, because one writes something the other uses.
+    pub fn conflicts_with(&self, other: &SystemAccess) -> bool {
+        let writes_any = |writes: &[(TypeId, &str)], access: &SystemAccess| {
+            writes.iter().any(|(id, _)| {
+                access.reads.iter().any(|(other, _)| other == id)
+                    || access.writes.iter().any(|(other, _)| other == id)
+            })
+        };
+        writes_any(&self.writes, other) || writes_any(&other.writes, self)
+    }
+}
+
+/// A registered tick phase, see `#[tick_phase]` in `ferrumc_macros`.
+pub struct TickPhaseContainer {
+    phase: &'static dyn TickPhase,
+    /// before <-----> after (lower = runs first)
+    /// 0 <-----> 255
+    priority: u8,
+    access: fn() -> SystemAccess,
+}
+
+impl TickPhaseContainer {
+    pub const fn new(
+        phase: &'static dyn TickPhase,
+        priority: u8,
+        access: fn() -> SystemAccess,
+    ) -> Self {
+        Self {
+            phase,
+            priority,
+            access,
+        }
+    }
+}
+
+inventory::collect!(TickPhaseContainer);
+
+struct ScheduledPhase {
+    phase: &'static dyn TickPhase,
+    access: SystemAccess,
+}
+
+/// The registered tick phases, split into stages. Built once when the tick loop starts.
+pub struct Schedule {
+    stages: Vec<Vec<ScheduledPhase>>,
+}
+
+impl Schedule {
+    /// Builds the schedule from every phase registered with `#[tick_phase]`.
+    pub fn from_registry() -> Self {
+        let mut containers = inventory::iter::<TickPhaseContainer>
+            .into_iter()
+            .collect::<Vec<_>>();
+        containers.sort_by_key(|c| (c.priority, c.phase.name()));
+
+        let schedule = Self::new(containers.into_iter().map(|c| (c.phase, (c.access)())));
+        for (index, stage) in schedule.stage_names().iter().enumerate() {
+            debug!("Tick stage {}: {}", index, stage.join(", "));
+        }
+        schedule
+    }
+
+    /// Builds a schedule from phases in the order they should run in.
+    ///
+    /// Each phase goes in the stage after the last one with a phase it conflicts with.
+    pub fn new(phases: impl IntoIterator<Item = (&'static dyn TickPhase, SystemAccess)>) -> Self {
+        let mut stages: Vec<Vec<ScheduledPhase>> = Vec::new();
+        for (phase, access) in phases {
+            let stage = stages
+                .iter()
+                .rposition(|stage| {
+                    stage
+                        .iter()
+                        .any(|scheduled| scheduled.access.conflicts_with(&access))
+                })
+                .map_or(0, |index| index + 1);
+            if stage == stages.len() {
+                stages.push(Vec::new());
+            }
+            stages[stage].push(ScheduledPhase { phase, access });
+        }
+        Self { stages }
+    }
+
+    /// The names of the phases in each stage.
+    pub fn stage_names(&self) -> Vec<Vec<&'static str>> {
+        self.stages
+            .iter()
+            .map(|stage| {
+                stage
+                    .iter()
+                    .map(|scheduled| scheduled.phase.name())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Runs every stage, and every phase in a stage in parallel. Applies the recorded commands
+    /// and dispatches the lifecycle events after each stage.
+    pub async fn run(&self, state: &GlobalState, tick: u64) {
+        for stage in &self.stages {
+            self.run_stage(stage, state, tick).await;
+            state.world.apply_commands().await;
+            state.dispatch_lifecycle_events().await;
+        }
+    }
+
+    async fn run_stage(&self, stage: &[ScheduledPhase], state: &GlobalState, tick: u64) {
+        if let [scheduled] = stage {
+            run_phase(scheduled.phase, state, tick).await;
+            return;
+        }
+
+        let handles = stage
+            .iter()
+            .map(|scheduled| {
+                let phase = scheduled.phase;
+                let state = state.clone();
+                tokio::spawn(async move { run_phase(phase, &state, tick).await })
+            })
+            .collect::<Vec<_>>();
+        for handle in futures::future::join_all(handles).await {
+            if let Err(e) = handle {
+                warn!("Tick phase panicked: {}", e);
+            }
+        }
+    }
+}
+
+async fn run_phase(phase: &'static dyn TickPhase, state: &GlobalState, tick: u64) {
+    let start = Instant::now();
+    if let Err(e) = phase.tick(state, tick).await {
+        warn!("Tick phase {} failed: {}", phase.name(), e);
+    }
+    METRICS.system_loop(phase.name(), start.elapsed());
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::utils::prelude::*;
+
+    struct Phase(&'static str);
+
+    #[async_trait]
+    impl TickPhase for Phase {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        async fn tick(&self, _state: &GlobalState, _tick: u64) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    struct Position;
+    struct Velocity;
+    struct Player;
+
+    #[test]
+    fn test_conflicts() {
+        let reads = SystemAccess::new().read::<Position>();
+        let writes = SystemAccess::new().write::<Position>();
+        assert!(!reads.conflicts_with(&reads));
+        assert!(reads.conflicts_with(&writes));
+        assert!(writes.conflicts_with(&reads));
+        assert!(writes.conflicts_with(&writes));
+        assert!(!writes.conflicts_with(&SystemAccess::new().write::<Velocity>()));
+    }
+
+    #[test]
+    fn test_stages() {
+        static MOVE: Phase = Phase("move");
+        static GRAVITY: Phase = Phase("gravity");
+        static TRACK: Phase = Phase("track");
+        static BRAND: Phase = Phase("brand");
+
+        let schedule = Schedule::new([
+            (
+                &MOVE as &dyn TickPhase,
+                SystemAccess::new().read::<Velocity>().write::<Position>(),
+            ),
+            (&GRAVITY, SystemAccess::new().write::<Velocity>()),
+            (
+                &TRACK,
+                SystemAccess::new().read::<Position>().read::<Player>(),
+            ),
+            (&BRAND, SystemAccess::new().read::<Player>()),
+        ]);
+
+        assert_eq!(
+            schedule.stage_names(),
+            vec![vec!["move", "brand"], vec!["gravity", "track"]]
+        );
+    }
+
+    #[test]
+    fn test_registered_phases() {
+        let stages = Schedule::from_registry().stage_names();
+        let phases = stages.concat();
+        for name in ["BrandAnimation", "ChunkSender", "KeepAliveSystem"] {
+            assert!(phases.contains(&name), "{} isn't scheduled", name);
+        }
+    }
+}
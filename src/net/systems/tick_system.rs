@@ -1,60 +1,231 @@
+//! The game tick loop. Runs every registered [TickPhase], [TICKS_PER_SECOND] times a second. See
+//! [crate::net::systems::scheduler] for how the phases are ordered.
+//!
+//! When a tick takes longer than [TICK_DURATION], the next ticks run back to back until the loop has
+//! caught up. If it's more than [MAX_CATCH_UP_TICKS] behind, those ticks are skipped instead, so a
+//! long pause doesn't make everything run in fast forward afterwards.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
+use parking_lot::Mutex;
+use tracing::warn;
 
+use crate::ecs::query::With;
 use crate::net::packets::outgoing::login_plugin_request::LoginPluginRequest;
+use crate::net::systems::scheduler::Schedule;
 use crate::net::systems::System;
 use crate::net::ConnectionWrapper;
 use crate::state::GlobalState;
 use crate::utils::components::player::Player;
-use ferrumc_macros::AutoGenName;
-use tracing::warn;
+use crate::utils::metrics::METRICS;
+use crate::utils::prelude::*;
+use ferrumc_macros::{system, tick_phase, AutoGenName};
+
+pub const TICKS_PER_SECOND: u32 = 20;
+pub const TICK_DURATION: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND as u64);
+/// How many ticks the loop can fall behind before it skips ticks instead of catching up.
+pub const MAX_CATCH_UP_TICKS: u32 = 10;
+/// How many ticks the TPS and MSPT are averaged over.
+const STATS_WINDOW: usize = 5 * TICKS_PER_SECOND as usize;
+
+/// Something that runs once every tick. Registered with `#[tick_phase]`, which also declares the
+/// components it accesses.
+#[async_trait]
+pub trait TickPhase: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn tick(&self, state: &GlobalState, tick: u64) -> Result<()>;
+}
+
+/// TPS and MSPT over the last [STATS_WINDOW] ticks.
+#[derive(Default)]
+pub struct TickStats {
+    inner: Mutex<TickStatsInner>,
+}
+
+#[derive(Default)]
+struct TickStatsInner {
+    tick: u64,
+    skipped: u64,
+    /// When each recent tick started, and how long it took.
+    recent: VecDeque<(Instant, Duration)>,
+}
+
+impl TickStats {
+    pub fn record_tick(&self, start: Instant, duration: Duration) {
+        let mut inner = self.inner.lock();
+        inner.tick += 1;
+        if inner.recent.len() == STATS_WINDOW {
+            inner.recent.pop_front();
+        }
+        inner.recent.push_back((start, duration));
+    }
 
+    pub fn record_skipped(&self, ticks: u64) {
+        let mut inner = self.inner.lock();
+        inner.tick += ticks;
+        inner.skipped += ticks;
+    }
+
+    /// The number of ticks so far, including skipped ones.
+    pub fn current_tick(&self) -> u64 {
+        self.inner.lock().tick
+    }
+
+    pub fn skipped_ticks(&self) -> u64 {
+        self.inner.lock().skipped
+    }
+
+    /// Ticks per second, at most [TICKS_PER_SECOND].
+    pub fn tps(&self) -> f64 {
+        let inner = self.inner.lock();
+        let (Some((first, _)), Some((last, _))) = (inner.recent.front(), inner.recent.back())
+        else {
+            return TICKS_PER_SECOND as f64;
+        };
+        let elapsed = last.duration_since(*first).as_secs_f64();
+        if elapsed == 0.0 {
+            return TICKS_PER_SECOND as f64;
+        }
+        ((inner.recent.len() - 1) as f64 / elapsed).min(TICKS_PER_SECOND as f64)
+    }
+
+    /// The average milliseconds per tick.
+    pub fn mspt(&self) -> f64 {
+        let inner = self.inner.lock();
+        if inner.recent.is_empty() {
+            return 0.0;
+        }
+        let total = inner
+            .recent
+            .iter()
+            .map(|(_, duration)| *duration)
+            .sum::<Duration>();
+        total.as_secs_f64() * 1000.0 / inner.recent.len() as f64
+    }
+}
+
+#[system]
 #[derive(AutoGenName)]
 pub struct TickSystem;
 
 #[async_trait]
 impl System for TickSystem {
     async fn run(&self, state: GlobalState) {
-        let mut query = state.world.query::<(&ConnectionWrapper, &Player)>();
-
-        let width = 40;
-        let total_width = width * 2;
-        let mut offset = 0;
-
+        let schedule = Schedule::from_registry();
+        let mut next_tick = Instant::now();
         loop {
-            let mut crab_wave = vec![" "; total_width];
+            tokio::time::sleep_until(next_tick.into()).await;
 
-            for (index, wave) in crab_wave.iter_mut().enumerate().take(total_width) {
-                let wave_height = ((index as f64 * 0.2).sin() + 1.0) * 2.0;
-                if wave_height.round() as usize == 2 {
-                    *wave = "🦀";
-                }
-            }
+            let start = Instant::now();
+            let tick = state.tick_stats.current_tick();
+            schedule.run(&state, tick).await;
+            state.world.increment_change_tick();
+            let duration = start.elapsed();
+            state.tick_stats.record_tick(start, duration);
+            METRICS.system_loop(self.name(), duration);
 
-            let visible_wave: String = crab_wave
-                .iter()
-                .cycle()
-                .skip(offset)
-                .take(width)
-                .cloned()
-                .collect();
-
-            while let Some((_, (conn, _))) = query.next().await {
-                let packet = LoginPluginRequest::server_brand(&visible_wave).await;
-                let conn = conn.0.read().await;
-                if let Err(e) = conn.send_packet(packet).await {
-                    warn!("Failed to send packet: {}", e);
-                    continue;
+            next_tick += TICK_DURATION;
+            let now = Instant::now();
+            if now > next_tick {
+                let behind = ((now - next_tick).as_nanos() / TICK_DURATION.as_nanos()) as u64;
+                if behind > MAX_CATCH_UP_TICKS as u64 {
+                    warn!(
+                        "Can't keep up! The last tick took {:?}, skipping {} ticks",
+                        duration, behind
+                    );
+                    state.tick_stats.record_skipped(behind);
+                    next_tick = now;
                 }
-                // trace!("Ticked connection for player `{}`", player.get_username());
             }
+        }
+    }
 
-            offset = (offset + 1) % total_width;
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
 
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+/// Animates a crab wave in the server brand shown in the F3 screen.
+#[tick_phase(reads(ConnectionWrapper, Player))]
+pub struct BrandAnimation;
+
+impl BrandAnimation {
+    const WIDTH: usize = 40;
+    /// The wave moves every this many ticks.
+    const TICKS_PER_FRAME: u64 = 2;
+
+    fn frame(offset: usize) -> String {
+        let total_width = Self::WIDTH * 2;
+        let mut crab_wave = vec![" "; total_width];
+
+        for (index, wave) in crab_wave.iter_mut().enumerate().take(total_width) {
+            let wave_height = ((index as f64 * 0.2).sin() + 1.0) * 2.0;
+            if wave_height.round() as usize == 2 {
+                *wave = "🦀";
+            }
         }
+
+        crab_wave
+            .iter()
+            .cycle()
+            .skip(offset % total_width)
+            .take(Self::WIDTH)
+            .cloned()
+            .collect()
     }
+}
 
+#[async_trait]
+impl TickPhase for BrandAnimation {
     fn name(&self) -> &'static str {
-        Self::type_name()
+        "BrandAnimation"
+    }
+
+    async fn tick(&self, state: &GlobalState, tick: u64) -> Result<()> {
+        if !tick.is_multiple_of(Self::TICKS_PER_FRAME) {
+            return Ok(());
+        }
+        let visible_wave = Self::frame((tick / Self::TICKS_PER_FRAME) as usize);
+
+        let mut query = state
+            .world
+            .query_filtered::<&ConnectionWrapper, With<Player>>();
+        while let Some((_, conn)) = query.next().await {
+            let packet = LoginPluginRequest::server_brand(&visible_wave).await;
+            let conn = conn.0.read().await;
+            if let Err(e) = conn.send_packet(packet).await {
+                warn!("Failed to send packet: {}", e);
+                continue;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_stats() {
+        let stats = TickStats::default();
+        assert_eq!(stats.tps(), TICKS_PER_SECOND as f64);
+
+        let start = Instant::now();
+        // 11 ticks over a second, at 10 TPS, taking 4ms each.
+        for i in 0..11 {
+            stats.record_tick(
+                start + Duration::from_millis(100 * i),
+                Duration::from_millis(4),
+            );
+        }
+        assert!((stats.tps() - 10.0).abs() < 0.001);
+        assert!((stats.mspt() - 4.0).abs() < 0.001);
+
+        stats.record_skipped(5);
+        assert_eq!(stats.current_tick(), 16);
+        assert_eq!(stats.skipped_ticks(), 5);
     }
 }
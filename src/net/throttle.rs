@@ -0,0 +1,328 @@
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use dashmap::DashMap;
+use tracing::debug;
+
+use crate::utils::config::ConnectionLimits;
+
+/// How often (in accepted connections) IPs without open connections, and with full buckets, are
+/// forgotten.
+const PRUNE_INTERVAL: u64 = 1024;
+
+/// A token bucket that refills `rate` tokens per second, up to `burst` tokens.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    pub fn new(burst: u32) -> Self {
+        Self {
+            tokens: burst as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, rate: f64, burst: u32, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst as f64);
+        self.last_refill = now;
+    }
+
+    /// Takes a token if there is one. A rate of 0 means there is no limit.
+    pub fn try_take(&mut self, rate: f64, burst: u32, now: Instant) -> bool {
+        if rate <= 0.0 {
+            return true;
+        }
+        self.refill(rate, burst, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_full(&mut self, rate: f64, burst: u32, now: Instant) -> bool {
+        rate <= 0.0 || {
+            self.refill(rate, burst, now);
+            self.tokens >= burst as f64
+        }
+    }
+}
+
+#[derive(Debug)]
+struct IpState {
+    open_connections: u32,
+    connections: TokenBucket,
+    logins: TokenBucket,
+}
+
+/// Why a connection was refused by the [ConnectionThrottle].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rejection {
+    TooManyConnections,
+    RateLimited,
+}
+
+/// A snapshot of the [ConnectionThrottle] counters.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThrottleStats {
+    pub accepted_connections: u64,
+    /// Refused because the IP already had `max_connections_per_ip` connections open.
+    pub rejected_too_many_connections: u64,
+    /// Refused because the IP made new connections too fast.
+    pub rejected_connection_rate: u64,
+    /// Logins refused because the IP tried to log in too fast.
+    pub rejected_login_rate: u64,
+    /// Connections dropped because they didn't reach the play state in time.
+    pub login_timeouts: u64,
+    /// The amount of IPs currently being tracked.
+    pub tracked_ips: u64,
+}
+
+/// Limits the connections and login attempts per IP. See [ConnectionLimits] for the settings.
+///
+/// Connections are checked in [crate::net::init_connection] before anything is allocated for
+/// them, and logins in
+/// [crate::net::packets::incoming::login_start::LoginStart].
+#[derive(Default)]
+pub struct ConnectionThrottle {
+    ips: DashMap<IpAddr, IpState>,
+    accepted_connections: AtomicU64,
+    rejected_too_many_connections: AtomicU64,
+    rejected_connection_rate: AtomicU64,
+    rejected_login_rate: AtomicU64,
+    login_timeouts: AtomicU64,
+}
+
+/// Counts as an open connection for its IP until it's dropped.
+pub struct ConnectionPermit {
+    throttle: Arc<ConnectionThrottle>,
+    ip: IpAddr,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        if let Some(mut entry) = self.throttle.ips.get_mut(&self.ip) {
+            entry.open_connections = entry.open_connections.saturating_sub(1);
+        }
+    }
+}
+
+impl ConnectionThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks whether a new connection from `ip` is allowed.
+    ///
+    /// The returned permit has to be kept around for as long as the connection is open.
+    pub fn try_connect(
+        self: &Arc<Self>,
+        ip: IpAddr,
+        limits: &ConnectionLimits,
+    ) -> Result<ConnectionPermit, Rejection> {
+        let now = Instant::now();
+        {
+            let mut entry = self.get_ip_state(ip, limits);
+
+            if limits.max_connections_per_ip > 0
+                && entry.open_connections >= limits.max_connections_per_ip
+            {
+                self.rejected_too_many_connections
+                    .fetch_add(1, Ordering::Relaxed);
+                debug!(
+                    "Refusing connection from {}: {} connections already open",
+                    ip, entry.open_connections
+                );
+                return Err(Rejection::TooManyConnections);
+            }
+
+            if !entry.connections.try_take(
+                limits.connections_per_second,
+                limits.connection_burst,
+                now,
+            ) {
+                self.rejected_connection_rate
+                    .fetch_add(1, Ordering::Relaxed);
+                debug!("Refusing connection from {}: rate limited", ip);
+                return Err(Rejection::RateLimited);
+            }
+
+            entry.open_connections += 1;
+        }
+
+        let accepted = self.accepted_connections.fetch_add(1, Ordering::Relaxed) + 1;
+        if accepted.is_multiple_of(PRUNE_INTERVAL) {
+            self.prune(limits, now);
+        }
+
+        Ok(ConnectionPermit {
+            throttle: self.clone(),
+            ip,
+        })
+    }
+
+    /// Checks whether `ip` is allowed to make another login attempt.
+    pub fn try_login(&self, ip: IpAddr, limits: &ConnectionLimits) -> bool {
+        let mut entry = self.get_ip_state(ip, limits);
+        let allowed =
+            entry
+                .logins
+                .try_take(limits.logins_per_second, limits.login_burst, Instant::now());
+        if !allowed {
+            self.rejected_login_rate.fetch_add(1, Ordering::Relaxed);
+            debug!("Refusing login from {}: rate limited", ip);
+        }
+        allowed
+    }
+
+    pub fn record_login_timeout(&self) {
+        self.login_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> ThrottleStats {
+        ThrottleStats {
+            accepted_connections: self.accepted_connections.load(Ordering::Relaxed),
+            rejected_too_many_connections: self
+                .rejected_too_many_connections
+                .load(Ordering::Relaxed),
+            rejected_connection_rate: self.rejected_connection_rate.load(Ordering::Relaxed),
+            rejected_login_rate: self.rejected_login_rate.load(Ordering::Relaxed),
+            login_timeouts: self.login_timeouts.load(Ordering::Relaxed),
+            tracked_ips: self.ips.len() as u64,
+        }
+    }
+
+    fn get_ip_state(
+        &self,
+        ip: IpAddr,
+        limits: &ConnectionLimits,
+    ) -> dashmap::mapref::one::RefMut<'_, IpAddr, IpState> {
+        self.ips.entry(ip).or_insert_with(|| IpState {
+            open_connections: 0,
+            connections: TokenBucket::new(limits.connection_burst),
+            logins: TokenBucket::new(limits.login_burst),
+        })
+    }
+
+    /// Forgets IPs without open connections whose buckets have refilled.
+    fn prune(&self, limits: &ConnectionLimits, now: Instant) {
+        self.ips.retain(|_, entry| {
+            entry.open_connections > 0
+                || !entry.connections.is_full(
+                    limits.connections_per_second,
+                    limits.connection_burst,
+                    now,
+                )
+                || !entry
+                    .logins
+                    .is_full(limits.logins_per_second, limits.login_burst, now)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn limits() -> ConnectionLimits {
+        ConnectionLimits {
+            max_connections_per_ip: 2,
+            connections_per_second: 1.0,
+            connection_burst: 3,
+            logins_per_second: 1.0,
+            login_burst: 1,
+            login_timeout_secs: 30,
+        }
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2);
+        bucket.last_refill = start;
+
+        assert!(bucket.try_take(1.0, 2, start));
+        assert!(bucket.try_take(1.0, 2, start));
+        assert!(!bucket.try_take(1.0, 2, start));
+        assert!(bucket.try_take(1.0, 2, start + Duration::from_secs(1)));
+        assert!(!bucket.try_take(1.0, 2, start + Duration::from_secs(1)));
+        // Never refills past the burst size.
+        let later = start + Duration::from_secs(60);
+        assert!(bucket.try_take(1.0, 2, later));
+        assert!(bucket.try_take(1.0, 2, later));
+        assert!(!bucket.try_take(1.0, 2, later));
+        // A rate of 0 is unlimited.
+        assert!(bucket.try_take(0.0, 2, later));
+    }
+
+    #[test]
+    fn test_max_connections_per_ip() {
+        let throttle = Arc::new(ConnectionThrottle::new());
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let other_ip: IpAddr = "10.0.0.2".parse().unwrap();
+        let limits = limits();
+
+        let first = throttle.try_connect(ip, &limits).unwrap();
+        let _second = throttle.try_connect(ip, &limits).unwrap();
+        assert_eq!(
+            throttle.try_connect(ip, &limits).err(),
+            Some(Rejection::TooManyConnections)
+        );
+        let _other = throttle.try_connect(other_ip, &limits).unwrap();
+
+        drop(first);
+        let _third = throttle.try_connect(ip, &limits).unwrap();
+        // The burst of 3 is used up now.
+        drop(_third);
+        assert_eq!(
+            throttle.try_connect(ip, &limits).err(),
+            Some(Rejection::RateLimited)
+        );
+
+        let stats = throttle.stats();
+        assert_eq!(stats.accepted_connections, 4);
+        assert_eq!(stats.rejected_too_many_connections, 1);
+        assert_eq!(stats.rejected_connection_rate, 1);
+    }
+
+    #[test]
+    fn test_login_rate_limit() {
+        let throttle = ConnectionThrottle::new();
+        let ip: IpAddr = "::1".parse().unwrap();
+        let limits = limits();
+
+        assert!(throttle.try_login(ip, &limits));
+        assert!(!throttle.try_login(ip, &limits));
+        assert_eq!(throttle.stats().rejected_login_rate, 1);
+    }
+
+    #[test]
+    fn test_idle_ips_are_forgotten() {
+        let throttle = Arc::new(ConnectionThrottle::new());
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let limits = ConnectionLimits {
+            connections_per_second: 0.0,
+            logins_per_second: 0.0,
+            ..limits()
+        };
+
+        let permit = throttle.try_connect(ip, &limits).unwrap();
+        throttle.prune(&limits, Instant::now());
+        assert_eq!(throttle.stats().tracked_ips, 1);
+        drop(permit);
+        throttle.prune(&limits, Instant::now());
+        assert_eq!(throttle.stats().tracked_ips, 0);
+    }
+}
@@ -1,10 +1,11 @@
 use std::env;
-use std::env::current_exe;
 
 use crate::setup;
+use crate::utils::config::get_config_path;
 use crate::utils::error::Error;
+use crate::utils::get_root_path;
 use tokio::fs;
-use tracing::{error, info};
+use tracing::info;
 
 /// Handles the setup of the server
 ///
@@ -12,52 +13,38 @@ use tracing::{error, info};
 ///
 /// Returns True if the server should exit after setup
 ///
-/// Runs [setup::setup] if the server needs setting up
+/// Runs [setup::setup] if the config file doesn't exist yet. `ferrumc setup` runs it directly.
 pub async fn handle_setup() -> crate::utils::prelude::Result<bool> {
     // This env var will be present if the server is running in a CI environment
     // This will lead to set up not running, but we just need to check for compilation success, not actual functionality
     if env::var("GITHUB_ACTIONS").is_ok() {
         env::set_var("RUST_LOG", "info");
         Ok(false)
-        // If the setup flag is passed, run the setup regardless of the config file
-    } else if env::args().any(|x| x == "setup") {
-        setup::setup().await?;
-        return Ok(true);
         // Check if the config file exists already and run the setup if it doesn't
     } else {
-        // Get the path to the current executable
-        let exe = env::current_exe()?;
-        // This should be the directory the executable is in.
-        // This should always work but if it doesn't, we'll just return an error
-        let dir = exe.parent();
-        match dir {
-            Some(dir) => {
-                let config_path = dir.join("config.toml");
-                if !config_path.exists() {
-                    setup::setup().await?;
-                }
-                Ok(false)
-            }
-            None => {
-                error!("Failed to get the directory of the executable. Exiting...");
-                return Ok(true);
-            }
+        if !get_config_path()?.exists() {
+            setup::setup().await?;
         }
+        Ok(false)
     }
 }
 
 /// Handles the setup of the server
 ///
-/// This function is called when the server is started with the `--setup` flag or when the server is run for the first time
+/// This function is called when the server is started with the `setup` subcommand or when the server is run for the first time
 ///
-/// This function will create the necessary files and directories for the server to run. Also generates a default config file
-pub(crate) async fn setup() -> Result<(), Error> {
+/// This function will create the necessary files and directories for the server to run in the
+/// root directory (see [get_root_path]). Also generates a default config file
+pub async fn setup() -> Result<(), Error> {
     info!("Creating files...");
-    let exe = current_exe()?;
-    let dir = exe.parent().unwrap();
-    fs::write(dir.join("config.toml"), BASE_CONFIG.as_bytes()).await?;
-    fs::create_dir(dir.join("logs")).await?;
-    fs::create_dir(dir.join("plugins")).await?;
+    let dir = get_root_path()?;
+    let config_path = get_config_path()?;
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(config_path, BASE_CONFIG.as_bytes()).await?;
+    fs::create_dir_all(dir.join("logs")).await?;
+    fs::create_dir_all(dir.join("plugins")).await?;
     fs::write(
         dir.join("plugins").join("README.txt"),
         "Unfortunately plugins are not yet available",
@@ -71,11 +58,18 @@ pub(crate) async fn setup() -> Result<(), Error> {
 /// The default configuration file
 /// Not using ServerConfig::default(), since it doesn't have documentation on the usage of each field.
 pub static BASE_CONFIG: &str = r#"
+# Settings missing from this file use their default value. Any setting can be overridden with an environment
+# variable: FERRUMC_<SETTING>, e.g. FERRUMC_PORT=25566 or FERRUMC_DATABASE__CACHE_SIZE=2048 for sections.
+# Changes to motd, max_players and network_tick_rate are picked up while the server is running, other
+# settings need a restart.
+
 # The network address to bind to. Usually just 0.0.0.0 or 127.0.0.1 if you don't want to expose the server to the internet.
 host = "0.0.0.0"
 # The port to bind to. Default is 25565.
 port = 25565
-# The message displayed in the server list.
+# The message displayed in the server list. One is picked at random for every ping.
+# Each entry is either text with `&` colour codes (e.g. "&6Gold &lbold"), or a JSON text component
+# (e.g. '{"text": "Red", "color": "red"}').
 motd = ["A supersonic FerrumC server."]
 # The maximum number of players that can be connected at once.
 max_players = 20
@@ -85,6 +79,22 @@ max_players = 20
 network_tick_rate = 0
 # The default world name. You can switch between mutliple worlds by changing this value.
 world = "world"
+# Only allow players in whitelist.json to join. Use the `whitelist` command to edit it.
+whitelist = false
+# Players that are allowed to use commands like `ban` and `whitelist` in game.
+operators = []
+# Expect a PROXY protocol (v1 or v2) header at the start of every connection, to get the players'
+# real addresses when running behind a load balancer like HAProxy. Connections without one are refused,
+# so only enable this if every connection goes through the load balancer.
+proxy_protocol = false
+# Allow admin tools to run commands remotely over the RCON protocol, on `rcon_port`.
+# RCON isn't encrypted, so don't expose it to the internet. It won't start without a password.
+enable_rcon = false
+rcon_port = 25575
+rcon_password = ""
+# Answer GameSpy4 query requests (used by some server list sites) over UDP on `query_port`.
+enable_query = false
+query_port = 25565
 
 [database]
 # The cache size in KB. We recommend leaving this at the default value.
@@ -92,4 +102,67 @@ cache_size = 1024
 # The compression algorithm to use. "fast" is recommended for most use cases.
 # "best" is slower but may provide better compression ratio.
 compression = "fast"
+
+[disconnect_messages]
+# Sent to players that are refused during login, as JSON text components.
+server_full = '{"text": "The server is full!"}'
+not_whitelisted = '{"text": "You are not whitelisted on this server!"}'
+# {reason} is replaced with the reason given when banning.
+banned = '{"text": "You are banned from this server.\nReason: {reason}"}'
+rate_limited = '{"text": "You are logging in too fast, try again later."}'
+
+[connection_limits]
+# How many connections a single IP can have open at once. 0 means no limit.
+max_connections_per_ip = 5
+# How many new connections per second a single IP can make on average, and in a burst.
+# Status pings from the server list count as connections too. 0 means no limit.
+connections_per_second = 2.0
+connection_burst = 10
+# How many login attempts per second a single IP can make on average, and in a burst. 0 means no limit.
+logins_per_second = 0.5
+login_burst = 3
+# Connections that haven't finished logging in after this many seconds are dropped. 0 means no timeout.
+login_timeout_secs = 30
+
+[server_list]
+# The icon shown in the server list. Has to be a 64x64 PNG. Leave empty for no icon.
+favicon = "icon-64.png"
+# How many online players to show when hovering over the player count, picked at random.
+sample_size = 12
+# Don't show any online players, only the count.
+hide_players = false
+
+[logging]
+# trace, debug, info, warn or error. The --log flag overrides this.
+level = "debug"
+# Extra filters for specific modules, e.g. ["ferrumc::net=trace", "heed=warn"].
+filters = []
+# Log to the console and/or to logs/latest.log. Either can be "text" or "json" (one object per line).
+console = true
+console_format = "text"
+file = true
+file_format = "text"
+# The directory for log files, relative to the server directory.
+directory = "logs"
+# When latest.log is archived: "daily", "size" (once it reaches max_file_size_mb) or "never".
+rotation = "daily"
+max_file_size_mb = 64
+# How many archived log files to keep. 0 keeps all of them.
+max_files = 14
+
+[metrics]
+# Serve Prometheus metrics (connections, packets, chunk and database latency, ...) over HTTP at /metrics.
+enabled = false
+# Keep this on 127.0.0.1 unless the metrics should be reachable from other machines.
+host = "127.0.0.1"
+port = 9225
+
+[forwarding]
+# Accept player info (real UUID, IP and skin) forwarded by a proxy in front of the server.
+# "none", "bungeecord" (legacy IP forwarding) or "velocity" (modern forwarding).
+# Players can join with any name when this is enabled, so make sure only the proxy can reach the server.
+mode = "none"
+# The forwarding secret configured in the proxy. Required for "velocity". For "bungeecord", setting it
+# requires a matching BungeeGuard token from the proxy.
+secret = ""
 "#;
@@ -1,15 +1,17 @@
-use crate::database::Database;
+use crate::access::AccessLists;
 use crate::ecs::world::World;
-use crate::net::ConnectionList;
+use crate::net::systems::tick_system::TickStats;
+use crate::net::throttle::ConnectionThrottle;
 use std::sync::Arc;
 use crate::events::creation::dispatcher::EventDispatcher;
 
 pub struct ServerState {
     pub world: Arc<World>,
-    pub connections: ConnectionList,
-    pub database: Database,
     pub server_stream: tokio::net::TcpListener,
     pub event_dispatcher: Arc<EventDispatcher>,
+    pub access: AccessLists,
+    pub throttle: Arc<ConnectionThrottle>,
+    pub tick_stats: Arc<TickStats>,
 }
 
 pub type GlobalState = Arc<ServerState>;
@@ -1,7 +1,12 @@
 mod chunk_stuff;
 mod nbt_de;
+mod metrics;
 mod nbt_ser;
 pub mod query;
+mod events;
+mod harness;
+mod login;
+mod rcon;
 
 use std::io::Cursor;
 
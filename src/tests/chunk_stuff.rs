@@ -1,3 +1,4 @@
+use crate::database::Database;
 use ferrumc_codec::enc::NetEncode;
 
 #[tokio::test]
@@ -5,13 +6,16 @@ use ferrumc_codec::enc::NetEncode;
 pub async fn dump_heightmaps() -> Result<(), Box<dyn std::error::Error>> {
     use crate::utils::setup_logger;
     use tokio::net::TcpListener;
-    setup_logger().unwrap();
+    setup_logger(None, &Default::default()).unwrap();
     let state = crate::create_state(TcpListener::bind("0.0.0.0:0").await.unwrap())
         .await
         .unwrap();
 
     let chunk = state
-        .database
+        .world
+        .resource::<Database>()
+        .await
+        .unwrap()
         .get_chunk(0, 0, "overworld".to_string())
         .await
         .unwrap()
@@ -0,0 +1,297 @@
+use std::sync::Arc;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::events::creation::event::Cancellable;
+use crate::events::server_events::{ConnectionOpenedEvent, HandshakeEvent};
+use crate::events::world_events::{
+    ChunkSentEvent, ClientSettingsChangedEvent, PlayerLeaveEvent, PlayerMoveEvent,
+    PlayerRotateEvent,
+};
+use crate::net::packets::incoming::client_info::ClientInfo;
+use crate::net::packets::incoming::handshake::Handshake;
+use crate::net::packets::incoming::set_player_position::move_player;
+use crate::net::packets::IncomingPacket;
+use crate::net::{drop_conn, init_connection, ConnectionList, State};
+use crate::tests::harness::{connect, connected, connection, new_state, read_packet, record};
+use crate::utils::components::player::Player;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::position::Position;
+
+/// Reads a Synchronize Player Position packet, returning the position it teleports the player to.
+async fn read_teleport(client: &mut TcpStream) -> (f64, f64, f64, f32, f32) {
+    let (id, mut body) = read_packet(client).await;
+    assert_eq!(id, 0x3C);
+    (
+        body.read_f64().await.unwrap(),
+        body.read_f64().await.unwrap(),
+        body.read_f64().await.unwrap(),
+        body.read_f32().await.unwrap(),
+        body.read_f32().await.unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_player_move_event() {
+    let (state, entity, mut client) = connected(State::Play).await;
+
+    // A region the player can't leave: past x = 10 they're pushed back, past z = 10 the move is
+    // cancelled.
+    state
+        .event_dispatcher
+        .handlers()
+        .register::<PlayerMoveEvent, _>("keep_in_region", 128, false, |event, _state| {
+            Box::pin(async move {
+                if event.to.z > 10 {
+                    event.cancel();
+                } else if event.to.x > 10 {
+                    event.to.x = 10;
+                }
+            })
+        });
+
+    // Allowed, so stored as is, without a teleport
+    move_player(&state, entity, Some((Position::new(5, 64, 5), true)), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        *state.world.get_component::<Position>(entity).await.unwrap(),
+        Position::new(5, 64, 5)
+    );
+
+    // Changed by the handler
+    move_player(&state, entity, Some((Position::new(20, 64, 5), true)), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        *state.world.get_component::<Position>(entity).await.unwrap(),
+        Position::new(10, 64, 5)
+    );
+    // The first packet the client gets, so the allowed move didn't send one
+    assert_eq!(
+        read_teleport(&mut client).await,
+        (10.0, 64.0, 5.0, 0.0, 0.0)
+    );
+
+    // Cancelled, so the player stays where they were
+    move_player(&state, entity, Some((Position::new(5, 64, 20), true)), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        *state.world.get_component::<Position>(entity).await.unwrap(),
+        Position::new(10, 64, 5)
+    );
+    assert_eq!(
+        read_teleport(&mut client).await,
+        (10.0, 64.0, 5.0, 0.0, 0.0)
+    );
+}
+
+#[tokio::test]
+async fn test_player_rotate_event() {
+    let (state, entity, mut client) = connected(State::Play).await;
+
+    state
+        .event_dispatcher
+        .handlers()
+        .register::<PlayerRotateEvent, _>("no_looking_down", 128, false, |event, _state| {
+            Box::pin(async move {
+                if event.to.pitch > 45.0 {
+                    event.cancel();
+                }
+            })
+        });
+
+    move_player(&state, entity, None, Some(Rotation::new(90.0, 30.0)))
+        .await
+        .unwrap();
+    assert_eq!(
+        *state.world.get_component::<Rotation>(entity).await.unwrap(),
+        Rotation::new(90.0, 30.0)
+    );
+
+    // The position is stored even though the rotation is cancelled
+    move_player(
+        &state,
+        entity,
+        Some((Position::new(1, 64, 1), false)),
+        Some(Rotation::new(180.0, 60.0)),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        *state.world.get_component::<Rotation>(entity).await.unwrap(),
+        Rotation::new(90.0, 30.0)
+    );
+    assert_eq!(
+        read_teleport(&mut client).await,
+        (1.0, 64.0, 1.0, 90.0, 30.0)
+    );
+}
+
+fn handshake(next_state: i32) -> Handshake {
+    Handshake {
+        protocol_version: VarInt::new(763),
+        server_address: "localhost".to_string(),
+        server_port: 25565,
+        next_state: VarInt::new(next_state),
+    }
+}
+
+#[tokio::test]
+async fn test_handshake_event() {
+    let state = new_state().await;
+    let handlers = state.event_dispatcher.handlers();
+    let seen = record(
+        &state,
+        "record_handshake",
+        0,
+        |event: &mut HandshakeEvent| (event.protocol_version, event.next_state.clone()),
+    );
+
+    let (entity, _client) = connect(&state, State::Handshake).await;
+    handshake(1).handle(entity, state.clone()).await.unwrap();
+    let conn = connection(&state, entity).await;
+    assert_eq!(conn.read().await.state, State::Status);
+    assert!(!conn.read().await.drop);
+
+    // Cancelling drops the connection before it switches states
+    let cancel =
+        handlers.register::<HandshakeEvent, _>("cancel_handshake", 128, false, |event, _state| {
+            Box::pin(async move { event.cancel() })
+        });
+    let (entity, _client) = connect(&state, State::Handshake).await;
+    handshake(2).handle(entity, state.clone()).await.unwrap();
+    let conn = connection(&state, entity).await;
+    assert_eq!(conn.read().await.state, State::Handshake);
+    assert!(conn.read().await.drop);
+    handlers.unregister(cancel);
+
+    // Handlers can't skip the login
+    handlers.register::<HandshakeEvent, _>("skip_login", 128, false, |event, _state| {
+        Box::pin(async move { event.next_state = State::Play })
+    });
+    let (entity, _client) = connect(&state, State::Handshake).await;
+    handshake(2).handle(entity, state.clone()).await.unwrap();
+    let conn = connection(&state, entity).await;
+    assert_eq!(conn.read().await.state, State::Handshake);
+    assert!(conn.read().await.drop);
+
+    assert_eq!(
+        *seen.lock(),
+        vec![
+            (763, State::Status),
+            (763, State::Login),
+            (763, State::Login)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_connection_opened_event() {
+    let state = new_state().await;
+    let seen = record(
+        &state,
+        "refuse_connections",
+        128,
+        |event: &mut ConnectionOpenedEvent| {
+            event.cancel();
+            event.address
+        },
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (socket, _) = listener.accept().await.unwrap();
+    init_connection(socket, state.clone()).await.unwrap();
+
+    assert_eq!(*seen.lock(), vec![client.local_addr().unwrap()]);
+    // Never registered, and closed
+    let connections = state.world.resource::<ConnectionList>().await.unwrap();
+    assert!(connections.connections.is_empty());
+    assert_eq!(client.read(&mut [0u8; 1]).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_player_leave_event() {
+    let (state, player, _client) = connected(State::Play).await;
+    let world = Arc::clone(&state.world);
+    let left = record(
+        &state,
+        "record_leave",
+        128,
+        move |event: &mut PlayerLeaveEvent| {
+            // The entity is still there
+            (
+                event.entity,
+                world
+                    .get_component_storage()
+                    .contains::<Position>(event.entity),
+            )
+        },
+    );
+
+    let (status_ping, _client) = connect(&state, State::Status).await;
+    drop_conn(status_ping, state.clone()).await.unwrap();
+    drop_conn(player, state.clone()).await.unwrap();
+
+    // Only for players
+    assert_eq!(*left.lock(), vec![(player, true)]);
+    assert!(!state.world.entity_exists(player).await);
+}
+
+#[tokio::test]
+async fn test_client_settings_and_chunk_sent_events() {
+    let (state, entity, _client) = connected(State::Play).await;
+    let previous = record(
+        &state,
+        "cap_view_distance",
+        128,
+        |event: &mut ClientSettingsChangedEvent| {
+            event.settings.view_distance = event.settings.view_distance.min(2);
+            event.previous.as_ref().map(|info| info.view_distance)
+        },
+    );
+    let sent = record(
+        &state,
+        "record_chunks_sent",
+        128,
+        |event: &mut ChunkSentEvent| (event.entity, event.center),
+    );
+
+    state
+        .world
+        .get_component_storage()
+        .insert(entity, Player::new(0, "Steve".to_string()))
+        .insert(entity, Position::new(-100_000, 64, 48));
+    let settings = |view_distance| ClientInfo {
+        locale: "en_us".to_string(),
+        view_distance,
+        chat_mode: 0,
+        chat_colors: true,
+        displayed_skin_parts: 0,
+        main_hand: 1,
+    };
+
+    settings(12).handle(entity, state.clone()).await.unwrap();
+    settings(1).handle(entity, state.clone()).await.unwrap();
+
+    // The previous settings are the ones the handler left
+    assert_eq!(*previous.lock(), vec![None, Some(2)]);
+    let stored = state
+        .world
+        .get_component::<ClientInfo>(entity)
+        .await
+        .unwrap();
+    assert_eq!(stored.view_distance, 1);
+    drop(stored);
+
+    assert_eq!(
+        *sent.lock(),
+        vec![(entity, (-6250, 3)), (entity, (-6250, 3))]
+    );
+}
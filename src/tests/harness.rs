@@ -0,0 +1,115 @@
+use std::io::Cursor;
+use std::sync::atomic;
+use std::sync::Arc;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use parking_lot::Mutex;
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::RwLock;
+
+use crate::create_state;
+use crate::ecs::entity::Entity;
+use crate::events::creation::event::Event;
+use crate::net::{
+    Connection, ConnectionList, ConnectionMetadata, ConnectionWrapper, NetStream, State,
+};
+use crate::state::GlobalState;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::position::Position;
+
+pub(super) async fn new_state() -> GlobalState {
+    create_state(TcpListener::bind("127.0.0.1:0").await.unwrap())
+        .await
+        .unwrap()
+}
+
+/// Adds a connection in the given state to the server, with a player at the origin, and returns
+/// its entity and the client's end of the socket.
+pub(super) async fn connect(state: &GlobalState, conn_state: State) -> (Entity, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (socket, _) = listener.accept().await.unwrap();
+    let address = socket.peer_addr().unwrap();
+    let (in_stream, out_stream) = socket.into_split();
+
+    let entity = state
+        .world
+        .create_entity()
+        .await
+        .with(Position::new(0, 64, 0))
+        .with(Rotation::new(0.0, 0.0))
+        .build();
+    let conn = Arc::new(RwLock::new(Connection {
+        id: entity,
+        stream: NetStream {
+            in_stream: tokio::sync::Mutex::new(in_stream),
+            out_stream: tokio::sync::Mutex::new(out_stream),
+        },
+        player_uuid: None,
+        state: conn_state,
+        metadata: ConnectionMetadata {
+            address: Some(address),
+            ..Default::default()
+        },
+        drop: false,
+    }));
+    state
+        .world
+        .get_component_storage()
+        .insert(entity, ConnectionWrapper(conn.clone()));
+    let connections = state.world.resource::<ConnectionList>().await.unwrap();
+    connections.connections.insert(entity, conn);
+    connections
+        .connection_count
+        .fetch_add(1, atomic::Ordering::Relaxed);
+
+    (entity, client)
+}
+
+/// A new server with a single connection in the given state, see [connect].
+pub(super) async fn connected(conn_state: State) -> (GlobalState, Entity, TcpStream) {
+    let state = new_state().await;
+    let (entity, client) = connect(&state, conn_state).await;
+    (state, entity, client)
+}
+
+/// The connection of the given entity.
+pub(super) async fn connection(state: &GlobalState, entity: Entity) -> Arc<RwLock<Connection>> {
+    let connections = state.world.resource::<ConnectionList>().await.unwrap();
+    connections.get_connection(entity).unwrap()
+}
+
+/// Reads a packet sent to the client, returning its id and body.
+pub(super) async fn read_packet(client: &mut TcpStream) -> (i32, Cursor<Vec<u8>>) {
+    let length = VarInt::read(&mut *client).await.unwrap();
+    let mut buffer = vec![0u8; length.get_val() as usize];
+    client.read_exact(&mut buffer).await.unwrap();
+    let mut buffer = Cursor::new(buffer);
+    let id = VarInt::read(&mut buffer).await.unwrap();
+    (id.get_val(), buffer)
+}
+
+/// Registers a handler for `E` that records what `handler` returns for every event, and returns
+/// the records. `handler` can also change or cancel the event.
+pub(super) fn record<E: Event, T: Send + 'static>(
+    state: &GlobalState,
+    name: &'static str,
+    priority: u8,
+    handler: impl Fn(&mut E) -> T + Send + Sync + 'static,
+) -> Arc<Mutex<Vec<T>>> {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&records);
+    state.event_dispatcher.handlers().register::<E, _>(
+        name,
+        priority,
+        false,
+        move |event, _state| {
+            recorded.lock().push(handler(event));
+            Box::pin(async {})
+        },
+    );
+    records
+}
@@ -0,0 +1,57 @@
+use std::io::Read;
+use std::time::Duration;
+
+use ferrumc_codec::network_types::varint::VarInt;
+
+use crate::net::packets::incoming::handshake::Handshake;
+use crate::net::packets::incoming::login_start::LoginStart;
+use crate::net::packets::IncomingPacket;
+use crate::net::{protocol, State};
+use crate::tests::harness::{connected, connection, read_packet};
+use crate::utils::config::get_global_config;
+
+#[tokio::test]
+async fn test_refused_login_is_disconnected() {
+    let (state, entity, mut client) = connected(State::Login).await;
+
+    // Use up the client's login attempts, so the login is refused
+    let ip = client.local_addr().unwrap().ip();
+    let limits = get_global_config().connection_limits.clone();
+    while state.throttle.try_login(ip, &limits) {}
+
+    let login = LoginStart {
+        username: "Steve".to_string(),
+        uuid: 0,
+    };
+    tokio::time::timeout(Duration::from_secs(5), login.handle(entity, state.clone()))
+        .await
+        .expect("the refused login hung")
+        .unwrap();
+
+    let (id, _) = read_packet(&mut client).await;
+    assert_eq!(id, 0x00);
+    let conn = connection(&state, entity).await;
+    assert!(conn.read().await.drop);
+    assert_eq!(conn.read().await.state, State::Login);
+}
+
+#[tokio::test]
+async fn test_unsupported_version_is_refused() {
+    let (state, entity, mut client) = connected(State::Handshake).await;
+
+    let handshake = Handshake {
+        protocol_version: VarInt::new(protocol::PROTOCOL_VERSION - 1),
+        server_address: "localhost".to_string(),
+        server_port: 25565,
+        next_state: VarInt::new(2),
+    };
+    handshake.handle(entity, state.clone()).await.unwrap();
+
+    let (id, mut body) = read_packet(&mut client).await;
+    assert_eq!(id, 0x00);
+    let _length = VarInt::read(&mut body).await.unwrap();
+    let mut reason = String::new();
+    body.read_to_string(&mut reason).unwrap();
+    assert!(reason.contains(protocol::VERSION_NAME));
+    assert!(connection(&state, entity).await.read().await.drop);
+}
@@ -0,0 +1,36 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::create_state;
+use crate::net::systems::metrics_server::serve;
+use crate::utils::metrics::METRICS;
+
+async fn get(address: std::net::SocketAddr, path: &str) -> String {
+    let mut client = TcpStream::connect(address).await.unwrap();
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+    client.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    client.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    let state = create_state(TcpListener::bind("127.0.0.1:0").await.unwrap())
+        .await
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(serve(listener, state));
+
+    METRICS.packet_received(0x14);
+
+    let response = get(address, "/metrics").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("# TYPE ferrumc_connections gauge\nferrumc_connections 0\n"));
+    assert!(response.contains("ferrumc_packets_received_total{id=\"0x14\"}"));
+    assert!(response.contains("ferrumc_entities "));
+
+    let response = get(address, "/").await;
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
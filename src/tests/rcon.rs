@@ -0,0 +1,72 @@
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::create_state;
+use crate::net::systems::rcon::{
+    passwords_match, serve, RconPacket, AUTH_FAILED_ID, SERVERDATA_AUTH, SERVERDATA_AUTH_RESPONSE,
+    SERVERDATA_EXECCOMMAND, SERVERDATA_RESPONSE_VALUE,
+};
+
+const PASSWORD: &str = "correct horse battery staple";
+
+async fn request(stream: &mut TcpStream, packet: RconPacket) -> RconPacket {
+    packet.write(stream).await.unwrap();
+    RconPacket::read(stream).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_rcon_commands() {
+    let state = create_state(TcpListener::bind("127.0.0.1:0").await.unwrap())
+        .await
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(serve(listener, PASSWORD.to_string(), state));
+
+    // The wrong password is refused, and the connection closed.
+    let mut client = TcpStream::connect(address).await.unwrap();
+    let response = request(&mut client, RconPacket::new(1, SERVERDATA_AUTH, "hunter2")).await;
+    assert_eq!(response.request_id, AUTH_FAILED_ID);
+    assert!(RconPacket::read(&mut client).await.is_err());
+
+    // Commands aren't run before authenticating.
+    let mut client = TcpStream::connect(address).await.unwrap();
+    let response = request(
+        &mut client,
+        RconPacket::new(2, SERVERDATA_EXECCOMMAND, "connections"),
+    )
+    .await;
+    assert_eq!(response.request_id, AUTH_FAILED_ID);
+
+    let mut client = TcpStream::connect(address).await.unwrap();
+    let response = request(&mut client, RconPacket::new(3, SERVERDATA_AUTH, PASSWORD)).await;
+    assert_eq!(
+        response,
+        RconPacket::new(3, SERVERDATA_AUTH_RESPONSE, String::new())
+    );
+
+    let response = request(
+        &mut client,
+        RconPacket::new(4, SERVERDATA_EXECCOMMAND, "connections"),
+    )
+    .await;
+    assert_eq!(response.request_id, 4);
+    assert_eq!(response.packet_type, SERVERDATA_RESPONSE_VALUE);
+    assert!(response.body.starts_with("Accepted: "), "{}", response.body);
+
+    // Errors from the dispatcher are sent back as the output.
+    let response = request(
+        &mut client,
+        RconPacket::new(5, SERVERDATA_EXECCOMMAND, "definitely-not-a-command"),
+    )
+    .await;
+    assert_eq!(response.body, "Unknown command: definitely-not-a-command");
+}
+
+#[test]
+fn test_passwords_match() {
+    assert!(passwords_match(PASSWORD, PASSWORD));
+    assert!(!passwords_match("correct horse battery stapler", PASSWORD));
+    assert!(!passwords_match("correct horse battery", PASSWORD));
+    assert!(!passwords_match("", PASSWORD));
+    assert!(passwords_match("", ""));
+}
@@ -1,6 +1,8 @@
 use ferrumc_macros::{Component, Constructor, Getter};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Component, Getter, Constructor)]
+#[derive(Debug, Default, Component, Getter, Constructor, Serialize, Deserialize)]
+#[component(serialize)]
 pub struct Grounded {
     pub is_grounded: bool,
 }
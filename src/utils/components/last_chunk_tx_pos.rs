@@ -1,6 +1,8 @@
 use ferrumc_macros::{Component, Constructor, Getter};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Component, Getter, Constructor, Default)]
+#[derive(Debug, Component, Getter, Constructor, Default, Serialize, Deserialize)]
+#[component(serialize)]
 pub struct LastChunkTxPos {
     pub x: i32,
     pub z: i32,
@@ -1,7 +1,11 @@
 use ferrumc_macros::{Component, Constructor};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-#[derive(Component, Constructor, Debug)]
+#[derive(Component, Constructor, Debug, Serialize, Deserialize)]
+#[component(serialize)]
 pub struct Player {
+    #[serde(with = "uuid_string")]
     pub uuid: u128,
     pub username: String,
 }
@@ -14,4 +18,27 @@ impl Player {
     pub fn get_username(&self) -> &str {
         &self.username
     }
+
+    /// The UUID the server gives a player with this username, since there's no authentication.
+    pub fn offline_uuid(username: &str) -> Uuid {
+        let namespace_uuid = Uuid::new_v5(&Uuid::NAMESPACE_URL, "OfflinePlayer".as_bytes());
+        Uuid::new_v3(&namespace_uuid, username.as_bytes())
+    }
+}
+
+/// UUIDs don't fit in a JSON number, so snapshots store them as strings instead.
+mod uuid_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use uuid::Uuid;
+
+    pub fn serialize<S: Serializer>(uuid: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&Uuid::from_u128(*uuid).hyphenated())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        let uuid = String::deserialize(deserializer)?;
+        Uuid::parse_str(&uuid)
+            .map(|uuid| uuid.as_u128())
+            .map_err(serde::de::Error::custom)
+    }
 }
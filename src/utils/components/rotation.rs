@@ -1,6 +1,8 @@
 use ferrumc_macros::{Component, Constructor, Getter};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Component, Getter, Constructor, Clone)]
+#[derive(Debug, Component, Getter, Constructor, Clone, PartialEq, Serialize, Deserialize)]
+#[component(serialize)]
 pub struct Rotation {
     pub yaw: f32,
     pub pitch: f32,
@@ -1,18 +1,20 @@
-use std::io::ErrorKind::NotFound;
 use std::io::Write;
-use std::sync::OnceLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 
+use crate::setup::BASE_CONFIG;
 use crate::utils::constants::{
-    DEFAULT_CONFIG_FILE, DEFAULT_MAX_PLAYERS, DEFAULT_MOTD, DEFAULT_SERVER_HOST,
-    DEFAULT_SERVER_PORT,
+    DEFAULT_CONFIG_FILE, DEFAULT_LOG_LEVEL, DEFAULT_MAX_PLAYERS, DEFAULT_METRICS_PORT,
+    DEFAULT_MOTD, DEFAULT_QUERY_PORT, DEFAULT_RCON_PORT, DEFAULT_SERVER_HOST, DEFAULT_SERVER_PORT,
 };
 use crate::utils::error::Error;
-use config::{Config, ConfigError};
+use crate::utils::get_root_path;
+use config::{Config, FileFormat, Map, Source, Value, ValueKind};
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
-use crate::setup::BASE_CONFIG;
+use tracing::{info, warn};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u32,
@@ -21,96 +23,436 @@ pub struct ServerConfig {
     pub network_tick_rate: u32,
     pub database: Database,
     pub world: String,
+    #[serde(default)]
+    pub whitelist: bool,
+    #[serde(default)]
+    pub operators: Vec<String>,
+    #[serde(default)]
+    pub disconnect_messages: DisconnectMessages,
+    #[serde(default)]
+    pub connection_limits: ConnectionLimits,
+    #[serde(default)]
+    pub proxy_protocol: bool,
+    #[serde(default)]
+    pub forwarding: ForwardingConfig,
+    #[serde(default)]
+    pub server_list: ServerListConfig,
+    #[serde(default)]
+    pub enable_rcon: bool,
+    #[serde(default = "default_rcon_port")]
+    pub rcon_port: u16,
+    #[serde(default)]
+    pub rcon_password: String,
+    #[serde(default)]
+    pub enable_query: bool,
+    #[serde(default = "default_query_port")]
+    pub query_port: u16,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn default_rcon_port() -> u16 {
+    DEFAULT_RCON_PORT
+}
+
+fn default_query_port() -> u16 {
+    DEFAULT_QUERY_PORT
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Database {
     pub cache_size: u32,
     pub compression: String,
 }
 
+/// The reasons sent to players that aren't allowed to join, as JSON text components.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisconnectMessages {
+    pub server_full: String,
+    pub not_whitelisted: String,
+    /// `{reason}` is replaced with the reason given when banning.
+    pub banned: String,
+    pub rate_limited: String,
+}
+
+impl Default for DisconnectMessages {
+    fn default() -> Self {
+        Self {
+            server_full: r#"{"text": "The server is full!"}"#.to_string(),
+            not_whitelisted: r#"{"text": "You are not whitelisted on this server!"}"#.to_string(),
+            banned: r#"{"text": "You are banned from this server.\nReason: {reason}"}"#.to_string(),
+            rate_limited: r#"{"text": "You are logging in too fast, try again later."}"#
+                .to_string(),
+        }
+    }
+}
+
+impl DisconnectMessages {
+    /// The `banned` message, with `{reason}` replaced by the (JSON escaped) reason.
+    pub fn banned_message(&self, reason: &str) -> String {
+        let escaped = serde_json::Value::String(reason.to_string()).to_string();
+        self.banned
+            .replace("{reason}", &escaped[1..escaped.len() - 1])
+    }
+}
+
+/// What the server list shows, besides the MOTD.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerListConfig {
+    /// Path to a 64x64 PNG. Empty for no icon.
+    pub favicon: String,
+    /// How many online players to show when hovering over the player count.
+    pub sample_size: usize,
+    /// Don't show any online players, only the count.
+    pub hide_players: bool,
+}
+
+impl Default for ServerListConfig {
+    fn default() -> Self {
+        Self {
+            favicon: "icon-64.png".to_string(),
+            sample_size: 12,
+            hide_players: false,
+        }
+    }
+}
+
+/// Where logs go and what they look like. See [crate::utils::setup_logger].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// trace, debug, info, warn or error. The `--log` flag takes precedence.
+    pub level: String,
+    /// Extra filter directives, e.g. `ferrumc::net=trace` or `heed=warn`.
+    pub filters: Vec<String>,
+    pub console: bool,
+    pub console_format: LogFormat,
+    pub file: bool,
+    pub file_format: LogFormat,
+    /// Relative to the root directory.
+    pub directory: String,
+    pub rotation: LogRotation,
+    /// The size a log file is rotated at, with size based rotation.
+    pub max_file_size_mb: u64,
+    /// How many rotated log files to keep. 0 keeps all of them.
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_LOG_LEVEL.to_string(),
+            filters: vec![],
+            console: true,
+            console_format: LogFormat::Text,
+            file: true,
+            file_format: LogFormat::Text,
+            directory: "logs".to_string(),
+            rotation: LogRotation::Daily,
+            max_file_size_mb: 64,
+            max_files: 14,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per line, see [crate::utils::logging::JsonFormat].
+    Json,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    #[default]
+    Daily,
+    Size,
+    Never,
+}
+
+/// The Prometheus metrics endpoint. See [crate::utils::metrics].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// The address to serve the metrics on. Keep it on localhost unless the metrics should be
+    /// public.
+    pub host: String,
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: DEFAULT_METRICS_PORT,
+        }
+    }
+}
+
+/// Player info forwarding from a proxy in front of the server. See [crate::net::forwarding].
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForwardingConfig {
+    pub mode: ForwardingMode,
+    /// The secret shared with the proxy. Required for Velocity, and optional (BungeeGuard) for
+    /// BungeeCord.
+    pub secret: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardingMode {
+    #[default]
+    None,
+    BungeeCord,
+    Velocity,
+}
+
+/// Limits on how many connections/logins a single IP can make. See [crate::net::throttle].
+///
+/// A value of 0 disables that limit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionLimits {
+    /// How many connections a single IP can have open at once.
+    pub max_connections_per_ip: u32,
+    /// How many new connections per second a single IP can make, on average.
+    pub connections_per_second: f64,
+    /// How many new connections a single IP can make in a burst.
+    pub connection_burst: u32,
+    /// How many login attempts per second a single IP can make, on average.
+    pub logins_per_second: f64,
+    /// How many login attempts a single IP can make in a burst.
+    pub login_burst: u32,
+    /// Connections that haven't reached the play state after this many seconds are dropped.
+    pub login_timeout_secs: u64,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            max_connections_per_ip: 5,
+            connections_per_second: 2.0,
+            connection_burst: 10,
+            logins_per_second: 0.5,
+            login_burst: 3,
+            login_timeout_secs: 30,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Server {
     endpoint: String,
 }
 
 impl ServerConfig {
-    /// Load the server configuration from the config file
+    /// Load the server configuration from the config file (see [get_config_path]), creating it
+    /// if it doesn't exist yet
     pub fn new() -> Result<Self, Error> {
+        let path = get_config_path()?;
+        if !path.exists() {
+            info!("Config file wasn't found, creating a new one.");
+            create_config_file()?;
+        }
+        Self::load(&path)
+    }
+
+    /// Load the server configuration from `path`.
+    ///
+    /// Fields missing from the file get their default value, and are then overridden by
+    /// `FERRUMC_*` environment variables (e.g. `FERRUMC_PORT`, or `FERRUMC_DATABASE__CACHE_SIZE`
+    /// for nested fields) and the command line flags, in that order.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let (config, warnings) = Self::load_with_warnings(path)?;
+        for warning in warnings {
+            warn!("{}", warning);
+        }
+        Ok(config)
+    }
+
+    /// Like [ServerConfig::load], but returns the warnings instead of logging them, for when the
+    /// logger isn't set up yet.
+    pub fn load_with_warnings(path: &Path) -> Result<(Self, Vec<String>), Error> {
+        let defaults = Config::try_from(&ServerConfig::default())?;
+        let file = Config::builder()
+            .add_source(config::File::from(path).format(FileFormat::Toml))
+            .build()?;
+        let warnings = missing_fields(&defaults.collect()?, &file.collect()?, "")
+            .into_iter()
+            .map(|field| {
+                format!(
+                    "Missing field \"{}\" in config file, using the default value",
+                    field
+                )
+            })
+            .collect();
+
         let settings = Config::builder()
-            .add_source(config::File::with_name("config"))
-            .build()
-            .or_else(|err| {
-                if is_not_found(&err) {
-                    info!("Config file wasn't found, creating a new one.");
-                    create_config_file()?;
-                    return Config::builder()
-                        .add_source(config::File::with_name("config"))
-                        .build()
-                        .map_err(Error::from);
-                }
-                Err(Error::from(err))
-            })?;
-
-        let de_settings: ServerConfig = settings.try_deserialize().or_else(|e| {
-            error!("There was an error deserializing the config.");
-            if let Some(field) = missing_field(&e) {
-                info!("Missing field \"{}\" in config file", field);
-                info!("Would you like to create a new config file? (y/n)");
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input)?;
-                return if input.trim() == "y" {
-                    info!("Creating new config file...");
-                    create_config_file()?;
-                    Config::builder()
-                        .add_source(config::File::with_name("config"))
-                        .build()
-                        .map_err(Error::from)
-                        .and_then(|settings| settings.try_deserialize().map_err(Error::from))
-                } else {
-                    error!("Aborting...");
-                    Err(Error::Generic(format!("The config file has a missing field: {}", field)))
-                };
-            }
-            Err(Error::from(e))
-        })?;
+            .add_source(defaults)
+            .add_source(file)
+            .add_source(
+                config::Environment::with_prefix("FERRUMC")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .list_separator(",")
+                    .with_list_parse_key("motd")
+                    .with_list_parse_key("operators")
+                    .with_list_parse_key("logging.filters")
+                    .try_parsing(true),
+            )
+            .build()?;
+        let mut config: ServerConfig = settings.try_deserialize()?;
 
-        Ok(de_settings)
+        if let Some(overrides) = CONFIG_OVERRIDES.get() {
+            overrides.apply(&mut config);
+        }
+        config.validate()?;
+
+        Ok((config, warnings))
+    }
+
+    /// Check the values that can't be expressed by the types alone
+    pub fn validate(&self) -> Result<(), ConfigValidationError> {
+        if !(1..=u16::MAX as u32).contains(&self.port) {
+            return Err(ConfigValidationError::InvalidPort(self.port));
+        }
+        if self.network_tick_rate > MAX_NETWORK_TICK_RATE {
+            return Err(ConfigValidationError::InvalidTickRate(
+                self.network_tick_rate,
+            ));
+        }
+        if self.max_players < 0 {
+            return Err(ConfigValidationError::InvalidMaxPlayers(self.max_players));
+        }
+        // The world name is used as a directory name, so it can't point anywhere else.
+        let world = self.world.trim();
+        if world.is_empty() || world == "." || world == ".." || world.contains(['/', '\\', ':']) {
+            return Err(ConfigValidationError::InvalidWorldName(self.world.clone()));
+        }
+        if self.logging.level.trim().parse::<tracing::Level>().is_err() {
+            return Err(ConfigValidationError::InvalidLogLevel(
+                self.logging.level.clone(),
+            ));
+        }
+        Ok(())
     }
 }
 
-/// Check if the error is a not found error
-fn is_not_found(err: &ConfigError) -> bool {
-    let ConfigError::Foreign(foreign_error) = err else {
-        error!("Error wasn't foreign: {:?}", err);
-        return false;
-    };
+/// The network tick rate is turned into a delay in milliseconds, so anything above this would be
+/// the same as no limit.
+pub const MAX_NETWORK_TICK_RATE: u32 = 1000;
 
-    let Some(io_error) = foreign_error.downcast_ref::<std::io::Error>() else {
-        error!("Foreign error wasn't an IO error: {:?}", foreign_error);
-        return false;
-    };
+/// A config value that's the right type, but not a valid value.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ConfigValidationError {
+    #[error("port has to be between 1 and 65535, but is {0}")]
+    InvalidPort(u32),
+    #[error(
+        "network_tick_rate can be at most {}, but is {0}",
+        MAX_NETWORK_TICK_RATE
+    )]
+    InvalidTickRate(u32),
+    #[error("max_players can't be negative, but is {0}")]
+    InvalidMaxPlayers(i32),
+    #[error("world has to be a directory name, but is \"{0}\"")]
+    InvalidWorldName(String),
+    #[error("logging.level has to be trace, debug, info, warn or error, but is \"{0}\"")]
+    InvalidLogLevel(String),
+}
 
-    io_error.kind() == NotFound
+/// The fields in `defaults` that aren't in `file`, as dotted paths (e.g. `database.cache_size`).
+fn missing_fields(
+    defaults: &Map<String, Value>,
+    file: &Map<String, Value>,
+    prefix: &str,
+) -> Vec<String> {
+    let mut missing = Vec::new();
+    for (key, default) in defaults {
+        let path = format!("{}{}", prefix, key);
+        match (&default.kind, file.get(key).map(|value| &value.kind)) {
+            (_, None) => missing.push(path),
+            (ValueKind::Table(defaults), Some(ValueKind::Table(file))) => {
+                missing.extend(missing_fields(defaults, file, &format!("{}.", path)));
+            }
+            _ => {}
+        }
+    }
+    missing.sort();
+    missing
 }
 
-/// Check if the error is a missing field error
-fn missing_field(err: &ConfigError) -> Option<String> {
-    if let ConfigError::Message(message) = err {
-        if message.contains("missing field") {
-            return message.split('`').nth(1).map(String::from);
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+static CONFIG_OVERRIDES: OnceLock<ConfigOverrides> = OnceLock::new();
+
+/// Overrides the config file location, e.g. with the `--config` flag.
+///
+/// Has to be called before the config is loaded.
+pub fn set_config_path(path: PathBuf) {
+    if CONFIG_PATH.set(path).is_err() {
+        tracing::warn!("The config path was already set, ignoring the new one");
+    }
+}
+
+/// The config file, which is `config.toml` in the root directory unless it's been overridden.
+pub fn get_config_path() -> Result<PathBuf, Error> {
+    match CONFIG_PATH.get() {
+        Some(path) => Ok(path.clone()),
+        None => Ok(get_root_path()?.join(DEFAULT_CONFIG_FILE)),
+    }
+}
+
+/// Config values given on the command line, which take precedence over the config file.
+#[derive(Debug, Default, Clone)]
+pub struct ConfigOverrides {
+    pub host: Option<String>,
+    pub port: Option<u32>,
+    pub max_players: Option<i32>,
+    pub world: Option<String>,
+}
+
+impl ConfigOverrides {
+    fn apply(&self, config: &mut ServerConfig) {
+        if let Some(host) = &self.host {
+            config.host = host.clone();
+        }
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+        if let Some(max_players) = self.max_players {
+            config.max_players = max_players;
+        }
+        if let Some(world) = &self.world {
+            config.world = world.clone();
         }
     }
-    None
+}
+
+/// Sets the overrides from the command line.
+///
+/// Has to be called before the config is loaded.
+pub fn set_config_overrides(overrides: ConfigOverrides) {
+    if CONFIG_OVERRIDES.set(overrides).is_err() {
+        tracing::warn!("The config overrides were already set, ignoring the new ones");
+    }
 }
 
 /// Create a new config file
 fn create_config_file() -> Result<(), Error> {
-    let path = std::path::Path::new(DEFAULT_CONFIG_FILE);
-    if path.exists() {
-        std::fs::remove_file(path)?;
+    let path = get_config_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
     }
-    let mut file = std::fs::File::create(path)?;
+    let mut file = std::fs::File::create(&path)?;
     // let contents = toml::to_string(&ServerConfig::default())?;
     let contents = BASE_CONFIG;
     file.write_all(contents.as_bytes())?;
@@ -133,12 +475,180 @@ impl Default for ServerConfig {
                 cache_size: 1024,
                 compression: "fast".to_string(),
             },
+            whitelist: false,
+            operators: vec![],
+            disconnect_messages: DisconnectMessages::default(),
+            connection_limits: ConnectionLimits::default(),
+            proxy_protocol: false,
+            forwarding: ForwardingConfig::default(),
+            server_list: ServerListConfig::default(),
+            enable_rcon: false,
+            rcon_port: DEFAULT_RCON_PORT,
+            rcon_password: String::new(),
+            enable_query: false,
+            query_port: DEFAULT_QUERY_PORT,
+            metrics: MetricsConfig::default(),
+            logging: LoggingConfig::default(),
         }
     }
 }
 
+static CONFIG: OnceLock<RwLock<Arc<ServerConfig>>> = OnceLock::new();
+
+fn global_config() -> &'static RwLock<Arc<ServerConfig>> {
+    CONFIG.get_or_init(|| {
+        RwLock::new(Arc::new(
+            ServerConfig::new().expect("Failed to load config"),
+        ))
+    })
+}
+
+/// Sets the global config, so it isn't loaded again when it's first used.
+///
+/// Has to be called before the config is first used.
+pub fn set_global_config(config: ServerConfig) {
+    if CONFIG.set(RwLock::new(Arc::new(config))).is_err() {
+        warn!("The config was already loaded, ignoring the new one");
+    }
+}
+
 /// Get the global server configuration
-pub fn get_global_config() -> &'static ServerConfig {
-    static CONFIG: OnceLock<ServerConfig> = OnceLock::new();
-    CONFIG.get_or_init(|| ServerConfig::new().expect("Failed to load config"))
+///
+/// This is a snapshot, the config can be swapped out when the config file is reloaded (see
+/// [reload_global_config]). Get it again rather than holding on to it.
+pub fn get_global_config() -> Arc<ServerConfig> {
+    global_config().read().clone()
+}
+
+/// The fields that take effect without a restart when the config file changes.
+pub const RELOADABLE_FIELDS: &[&str] = &["motd", "max_players", "network_tick_rate"];
+
+fn copy_reloadable_fields(from: &ServerConfig, to: &mut ServerConfig) {
+    to.motd.clone_from(&from.motd);
+    to.max_players = from.max_players;
+    to.network_tick_rate = from.network_tick_rate;
+}
+
+/// Reloads the config file and applies the [RELOADABLE_FIELDS] to the global config.
+///
+/// Returns whether anything changed. If the new config is invalid, the current one is kept.
+pub fn reload_global_config() -> Result<bool, Error> {
+    let new = ServerConfig::load(&get_config_path()?)?;
+    let current = get_global_config();
+
+    let mut needs_restart = new.clone();
+    copy_reloadable_fields(&current, &mut needs_restart);
+    if needs_restart != *current {
+        warn!(
+            "Only {} can be changed without a restart, restart the server to apply the other \
+             changes",
+            RELOADABLE_FIELDS.join(", ")
+        );
+    }
+
+    let mut updated = (*current).clone();
+    copy_reloadable_fields(&new, &mut updated);
+    if updated == *current {
+        return Ok(false);
+    }
+    *global_config().write() = Arc::new(updated);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loading a config reads the environment, so it can't happen while a test changes it.
+    static ENV_LOCK: parking_lot::Mutex<()> = parking_lot::Mutex::new(());
+
+    #[test]
+    fn test_load_partial_config() {
+        let _env = ENV_LOCK.lock();
+        let path =
+            std::env::temp_dir().join(format!("ferrumc-config-{}.toml", rand::random::<u64>()));
+        std::fs::write(&path, "port = 25566\n[database]\ncompression = \"best\"\n").unwrap();
+
+        let config = ServerConfig::load(&path).unwrap();
+        assert_eq!(config.port, 25566);
+        assert_eq!(config.database.compression, "best");
+        assert_eq!(
+            config.database.cache_size,
+            ServerConfig::default().database.cache_size
+        );
+        assert_eq!(config.motd, ServerConfig::default().motd);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let _env = ENV_LOCK.lock();
+        let path =
+            std::env::temp_dir().join(format!("ferrumc-config-{}.toml", rand::random::<u64>()));
+        std::fs::write(&path, "port = 25566\n").unwrap();
+
+        std::env::set_var("FERRUMC_PORT", "25570");
+        std::env::set_var("FERRUMC_DATABASE__CACHE_SIZE", "2048");
+        let config = ServerConfig::load(&path);
+        std::env::remove_var("FERRUMC_PORT");
+        std::env::remove_var("FERRUMC_DATABASE__CACHE_SIZE");
+        std::fs::remove_file(path).unwrap();
+
+        let config = config.unwrap();
+        // Over the value from the file.
+        assert_eq!(config.port, 25570);
+        // Over the default.
+        assert_eq!(config.database.cache_size, 2048);
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let defaults = Config::try_from(&ServerConfig::default())
+            .unwrap()
+            .collect()
+            .unwrap();
+        let file = Config::builder()
+            .add_source(config::File::from_str(
+                "port = 1\n[database]\ncache_size = 1\n",
+                FileFormat::Toml,
+            ))
+            .build()
+            .unwrap()
+            .collect()
+            .unwrap();
+
+        let missing = missing_fields(&defaults, &file, "");
+        assert!(missing.contains(&"host".to_string()));
+        assert!(missing.contains(&"database.compression".to_string()));
+        assert!(!missing.contains(&"port".to_string()));
+        assert!(!missing.contains(&"database.cache_size".to_string()));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(ServerConfig::default().validate(), Ok(()));
+
+        let invalid = |edit: fn(&mut ServerConfig)| {
+            let mut config = ServerConfig::default();
+            edit(&mut config);
+            config.validate().unwrap_err()
+        };
+        assert_eq!(
+            invalid(|c| c.port = 70000),
+            ConfigValidationError::InvalidPort(70000)
+        );
+        assert_eq!(
+            invalid(|c| c.network_tick_rate = 5000),
+            ConfigValidationError::InvalidTickRate(5000)
+        );
+        assert_eq!(
+            invalid(|c| c.max_players = -1),
+            ConfigValidationError::InvalidMaxPlayers(-1)
+        );
+        assert!(matches!(
+            invalid(|c| c.world = "../other".to_string()),
+            ConfigValidationError::InvalidWorldName(_)
+        ));
+    }
 }
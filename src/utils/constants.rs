@@ -3,6 +3,11 @@ pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
 pub const DEFAULT_SERVER_HOST: &str = "localhost";
 // Default port for a Minecraft server
 pub const DEFAULT_SERVER_PORT: u32 = 25565;
+// Default port for RCON, same as vanilla
+pub const DEFAULT_RCON_PORT: u16 = 25575;
+// Default port for the UDP query protocol, same as vanilla
+pub const DEFAULT_QUERY_PORT: u16 = 25565;
+pub const DEFAULT_METRICS_PORT: u16 = 9225;
 pub const DEFAULT_MOTD: &str = "A FerrumC Server";
 pub const DEFAULT_MAX_PLAYERS: u32 = 20;
 
@@ -1,5 +1,6 @@
 pub mod bitset;
 pub mod position;
+pub mod remaining_bytes;
 pub mod velocity;
 
 /*impl<S: NBTSerialize> Encode for &S {
@@ -4,12 +4,14 @@ use ferrumc_codec::enc::NetEncode;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 use ferrumc_macros::Component;
+use serde::{Deserialize, Serialize};
 
 /// Represents a position in the world
 ///
 /// Check out the [Position::net_encode] and [Position::net_decode]
 /// implementations for more information on how this struct is encoded and decoded
-#[derive(Clone, Component, Debug)]
+#[derive(Clone, Component, Debug, PartialEq, Serialize, Deserialize)]
+#[component(serialize)]
 pub struct Position {
     // Encoded as a 26 bit int
     pub x: i32,
@@ -0,0 +1,22 @@
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::utils::error::Error;
+use crate::utils::impls::packet_impls::NetDecode;
+
+/// Everything left in the packet, for fields whose length is implied by the packet length
+/// (e.g. the data of a plugin message).
+///
+/// Has to be the last field of the packet.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RemainingBytes(pub Vec<u8>);
+
+impl NetDecode for RemainingBytes {
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
+    where
+        T: AsyncRead + Unpin,
+    {
+        let mut data = Vec::new();
+        bytes.read_to_end(&mut data).await?;
+        Ok(Box::new(RemainingBytes(data)))
+    }
+}
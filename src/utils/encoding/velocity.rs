@@ -1,8 +1,10 @@
 use ferrumc_macros::{Component, Constructor};
+use serde::{Deserialize, Serialize};
 
 /// Represents a velocity in the world
 /// FIXME: Just to test the ecs system for now
-#[derive(Debug, Component, Constructor)]
+#[derive(Debug, Component, Constructor, Serialize, Deserialize)]
+#[component(serialize)]
 pub struct Velocity {
     // Encoded as a 26 bit int
     pub x: i32,
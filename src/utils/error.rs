@@ -10,6 +10,8 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error(transparent)]
     Config(#[from] ConfigError),
+    #[error("Invalid config: {0}")]
+    InvalidConfig(#[from] crate::utils::config::ConfigValidationError),
     #[error(transparent)]
     TomlSe(#[from] toml::ser::Error),
     #[error(transparent)]
@@ -18,13 +20,28 @@ pub enum Error {
     TokioJoin(#[from] tokio::task::JoinError),
 
     #[error("Connection not found: {0}")]
-    ConnectionNotFound(usize),
+    ConnectionNotFound(crate::net::packets::ConnectionId),
     #[error("Invalid packet id: {0}")]
     InvalidPacketId(u32),
     #[error("Invalid state: {0:x}")]
     InvalidState(i32),
     #[error("Invalid Connection Metadata: {0}")]
     InvalidConnectionMetadata(String),
+    #[error("Malformed packet: {0}")]
+    MalformedPacket(String),
+
+    #[error("{0}")]
+    InvalidCommand(String),
+    #[error("Connection didn't finish logging in within {0} seconds")]
+    LoginTimedOut(u64),
+    #[error("Invalid PROXY header: {0}")]
+    InvalidProxyHeader(String),
+    #[error("Invalid forwarded player info: {0}")]
+    InvalidForwarding(String),
+    #[error("Invalid favicon: {0}")]
+    InvalidFavicon(String),
+    #[error("The event queue is full")]
+    EventQueueFull,
 
     #[error(transparent)]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
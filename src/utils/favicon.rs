@@ -0,0 +1,100 @@
+//! The server icon shown in the server list.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use base64::Engine;
+use tracing::{error, info};
+
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+/// The client only accepts 64x64 icons.
+pub const FAVICON_SIZE: u32 = 64;
+
+/// Checks that `data` is a 64x64 PNG, going by its header.
+pub fn validate_favicon(data: &[u8]) -> Result<()> {
+    if data.len() < 24 || data[..8] != PNG_SIGNATURE || &data[12..16] != b"IHDR" {
+        return Err(Error::InvalidFavicon("not a PNG image".to_string()));
+    }
+    let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
+    let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+    if width != FAVICON_SIZE || height != FAVICON_SIZE {
+        return Err(Error::InvalidFavicon(format!(
+            "the image is {}x{}, but has to be {}x{}",
+            width, height, FAVICON_SIZE, FAVICON_SIZE
+        )));
+    }
+    Ok(())
+}
+
+/// Reads and validates the favicon at `path`, and encodes it as a data URI.
+///
+/// Returns `None` if there's no file at `path`.
+pub fn load_favicon(path: &Path) -> Result<Option<String>> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    validate_favicon(&data)?;
+    let data = base64::engine::general_purpose::STANDARD.encode(&data);
+    Ok(Some(format!("data:image/png;base64,{}", data)))
+}
+
+/// The favicon from the `server_list.favicon` config, as a data URI.
+///
+/// Loaded on first use, which [init_favicon] does at startup so problems are reported early.
+pub fn get_favicon() -> Option<&'static str> {
+    static FAVICON: OnceLock<Option<String>> = OnceLock::new();
+    FAVICON
+        .get_or_init(|| {
+            let path = &get_global_config().server_list.favicon;
+            if path.is_empty() {
+                return None;
+            }
+            match load_favicon(Path::new(path)) {
+                Ok(Some(favicon)) => Some(favicon),
+                Ok(None) => {
+                    info!(
+                        "No favicon found at {}, the server list won't show one",
+                        path
+                    );
+                    None
+                }
+                Err(e) => {
+                    error!("Not using the favicon at {}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .as_deref()
+}
+
+/// Loads and validates the favicon.
+pub fn init_favicon() {
+    get_favicon();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut data = PNG_SIGNATURE.to_vec();
+        data.extend_from_slice(&13u32.to_be_bytes());
+        data.extend_from_slice(b"IHDR");
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn test_validate_favicon() {
+        assert!(validate_favicon(&png_header(64, 64)).is_ok());
+        assert!(validate_favicon(&png_header(128, 128)).is_err());
+        assert!(validate_favicon(b"GIF89a, definitely not a PNG").is_err());
+        assert!(validate_favicon(&[]).is_err());
+    }
+}
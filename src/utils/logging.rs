@@ -0,0 +1,366 @@
+//! Log outputs used by [crate::utils::setup_logger]: a log file that's rotated into the `logs`
+//! directory, and a JSON format for log pipelines.
+//!
+//! Like the vanilla server, the current log is `logs/latest.log`. When it's rotated (and on
+//! startup) it's renamed to `<date>-<n>.log`, and the oldest archives past `max_files` are deleted.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::FormatTime;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::utils::config::LogRotation;
+
+const LATEST_LOG: &str = "latest.log";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// A log file that's rotated daily or when it reaches a size.
+pub struct RollingFile {
+    dir: PathBuf,
+    rotation: LogRotation,
+    max_size: u64,
+    max_files: usize,
+    inner: Mutex<RollingFileInner>,
+}
+
+struct RollingFileInner {
+    file: File,
+    size: u64,
+    day: u64,
+}
+
+impl RollingFile {
+    /// Opens `latest.log` in `dir`, archiving the previous one.
+    ///
+    /// `max_size` is only used with [LogRotation::Size], and `max_files` of 0 keeps every archive.
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        rotation: LogRotation,
+        max_size: u64,
+        max_files: usize,
+    ) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+
+        let latest = dir.join(LATEST_LOG);
+        if latest.exists() {
+            let modified = std::fs::metadata(&latest)?.modified()?;
+            archive(&dir, &latest, day_of(modified))?;
+            prune(&dir, max_files)?;
+        }
+
+        Ok(Self {
+            inner: Mutex::new(RollingFileInner {
+                file: open_latest(&dir)?,
+                size: 0,
+                day: day_of(SystemTime::now()),
+            }),
+            dir,
+            rotation,
+            max_size,
+            max_files,
+        })
+    }
+
+    fn needs_rotation(&self, inner: &RollingFileInner, incoming: usize, today: u64) -> bool {
+        match self.rotation {
+            LogRotation::Daily => inner.day != today,
+            LogRotation::Size => inner.size > 0 && inner.size + incoming as u64 > self.max_size,
+            LogRotation::Never => false,
+        }
+    }
+
+    fn rotate(&self, inner: &mut RollingFileInner, today: u64) -> std::io::Result<()> {
+        inner.file.flush()?;
+        archive(&self.dir, &self.dir.join(LATEST_LOG), inner.day)?;
+        prune(&self.dir, self.max_files)?;
+        inner.file = open_latest(&self.dir)?;
+        inner.size = 0;
+        inner.day = today;
+        Ok(())
+    }
+}
+
+impl Write for &RollingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut inner = self.inner.lock();
+        let today = day_of(SystemTime::now());
+        if self.needs_rotation(&inner, buf.len(), today) {
+            // Keep logging to the old file rather than losing the line.
+            if let Err(e) = self.rotate(&mut inner, today) {
+                eprintln!("Failed to rotate the log file: {}", e);
+            }
+        }
+        let written = inner.file.write(buf)?;
+        inner.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.lock().file.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RollingFile {
+    type Writer = &'a RollingFile;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
+fn open_latest(dir: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LATEST_LOG))
+}
+
+/// Renames `latest` to `<date>-<n>.log`, with the first free `n`.
+fn archive(dir: &Path, latest: &Path, day: u64) -> std::io::Result<()> {
+    let date = format_date(day);
+    let mut n = 1;
+    while dir.join(format!("{}-{}.log", date, n)).exists() {
+        n += 1;
+    }
+    std::fs::rename(latest, dir.join(format!("{}-{}.log", date, n)))
+}
+
+/// Deletes the oldest archived logs, keeping `max_files`.
+fn prune(dir: &Path, max_files: usize) -> std::io::Result<()> {
+    if max_files == 0 {
+        return Ok(());
+    }
+    let mut archives = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.ends_with(".log") && name != LATEST_LOG
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect::<Vec<_>>();
+    archives.sort();
+    let excess = archives.len().saturating_sub(max_files);
+    for (_, path) in archives.into_iter().take(excess) {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn day_of(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or_default()
+}
+
+/// Formats days since the unix epoch as `YYYY-MM-DD` (UTC).
+fn format_date(day: u64) -> String {
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = day as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The fields of a span, as JSON. Recorded by [SpanFieldsLayer] for [JsonFormat].
+struct SpanFields(Map<String, Value>);
+
+/// Records span fields (like the `conn` span's `address`) so [JsonFormat] can include them.
+pub struct SpanFieldsLayer;
+
+impl<S> Layer<S> for SpanFieldsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = JsonVisitor::default();
+        attrs.record(&mut fields);
+        span.extensions_mut().insert(SpanFields(fields.0));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            let mut visitor = JsonVisitor(std::mem::take(fields));
+            values.record(&mut visitor);
+            *fields = visitor.0;
+        }
+    }
+}
+
+/// Formats events as one JSON object per line, with the fields of the spans they're in.
+///
+/// Needs [SpanFieldsLayer] to include span fields.
+pub struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> std::fmt::Result {
+        let mut timestamp = String::new();
+        tracing_subscriber::fmt::time::SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+
+        let mut fields = JsonVisitor::default();
+        event.record(&mut fields);
+
+        let spans = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| {
+                let mut object = span
+                    .extensions()
+                    .get::<SpanFields>()
+                    .map(|fields| fields.0.clone())
+                    .unwrap_or_default();
+                object.insert("name".to_string(), span.name().into());
+                Value::Object(object)
+            })
+            .collect::<Vec<_>>();
+
+        let metadata = event.metadata();
+        let line = serde_json::json!({
+            "timestamp": timestamp,
+            "level": metadata.level().as_str(),
+            "target": metadata.target(),
+            "fields": fields.0,
+            "spans": spans,
+        });
+        writeln!(writer, "{}", line)
+    }
+}
+
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(11016), "2000-02-29");
+        assert_eq!(format_date(20744), "2026-10-18");
+    }
+
+    #[test]
+    fn test_json_format() {
+        use std::sync::Arc;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(SpanFieldsLayer).with(
+            tracing_subscriber::fmt::Layer::default()
+                .event_format(JsonFormat)
+                .with_writer(move || writer.clone()),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("conn", address = "127.0.0.1:1234").entered();
+            tracing::info!(players = 3, "Hello");
+        });
+
+        let line: Value = serde_json::from_slice(&buffer.0.lock()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["fields"]["message"], "Hello");
+        assert_eq!(line["fields"]["players"], 3);
+        assert_eq!(line["spans"][0]["name"], "conn");
+        assert_eq!(line["spans"][0]["address"], "127.0.0.1:1234");
+    }
+
+    #[test]
+    fn test_size_rotation() {
+        let dir = std::env::temp_dir().join(format!("ferrumc-logs-{}", rand::random::<u64>()));
+        let file = RollingFile::new(&dir, LogRotation::Size, 10, 2).unwrap();
+        for _ in 0..5 {
+            (&file).write_all(b"0123456789").unwrap();
+        }
+
+        let mut names = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        // latest.log plus the 2 newest archives.
+        assert_eq!(names.len(), 3);
+        assert!(names.contains(&LATEST_LOG.to_string()));
+        assert_eq!(std::fs::read(dir.join(LATEST_LOG)).unwrap(), b"0123456789");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,369 @@
+//! Server health metrics, in the Prometheus text format.
+//!
+//! See <https://prometheus.io/docs/instrumenting/exposition_formats/>
+//!
+//! Everything is recorded into the global [METRICS] with atomics, so recording is cheap enough to
+//! always do, whether or not the metrics endpoint ([crate::net::systems::metrics_server]) is
+//! enabled. Values that are already tracked elsewhere, like the connection count, are read when
+//! the metrics are rendered.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use dashmap::DashMap;
+
+use crate::net::ConnectionList;
+use crate::state::GlobalState;
+
+/// The upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+#[derive(Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A latency histogram with [LATENCY_BUCKETS].
+#[derive(Default)]
+pub struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_nanos: AtomicU64,
+}
+
+impl Histogram {
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&bound| seconds <= bound) {
+            self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    /// Packets received, by packet id.
+    pub packets_received: DashMap<u8, Counter>,
+    /// Packets sent, by packet id.
+    pub packets_sent: DashMap<i32, Counter>,
+    pub bytes_sent: Counter,
+    /// How long it takes to send the chunks around a player.
+    pub chunk_send: Histogram,
+    pub database_get: Histogram,
+    pub database_put: Histogram,
+    pub cache_hits: Counter,
+    pub cache_misses: Counter,
+    /// How long each iteration of a [crate::net::systems::System]'s loop takes, by system name.
+    pub system_loop: DashMap<&'static str, Histogram>,
+    /// How long each event handler takes, by handler name.
+    pub event_handler: DashMap<&'static str, Histogram>,
+    /// Events dropped because the event queue was full.
+    pub events_dropped: Counter,
+}
+
+impl Metrics {
+    pub fn packet_received(&self, packet_id: u8) {
+        self.packets_received.entry(packet_id).or_default().inc();
+    }
+
+    pub fn packet_sent(&self, packet_id: i32) {
+        self.packets_sent.entry(packet_id).or_default().inc();
+    }
+
+    pub fn system_loop(&self, system: &'static str, duration: Duration) {
+        self.system_loop
+            .entry(system)
+            .or_default()
+            .observe(duration);
+    }
+
+    pub fn event_handler(&self, handler: &'static str, duration: Duration) {
+        self.event_handler
+            .entry(handler)
+            .or_default()
+            .observe(duration);
+    }
+
+    /// Renders all metrics in the Prometheus text format.
+    pub async fn render(&self, state: &GlobalState) -> String {
+        let mut out = String::new();
+
+        let connections = state
+            .world
+            .resource::<ConnectionList>()
+            .await
+            .map_or(0, |list| list.connection_count.load(Ordering::Relaxed));
+        write_gauge(
+            &mut out,
+            "ferrumc_connections",
+            "Open connections",
+            connections as f64,
+        );
+        write_gauge(
+            &mut out,
+            "ferrumc_entities",
+            "Entities in the ECS world",
+            state.world.entity_count().await as f64,
+        );
+
+        write_gauge(
+            &mut out,
+            "ferrumc_tps",
+            "Ticks per second",
+            state.tick_stats.tps(),
+        );
+        write_gauge(
+            &mut out,
+            "ferrumc_mspt",
+            "Average milliseconds per tick",
+            state.tick_stats.mspt(),
+        );
+        write_counter(
+            &mut out,
+            "ferrumc_skipped_ticks_total",
+            "Ticks skipped because the server couldn't keep up",
+            state.tick_stats.skipped_ticks(),
+        );
+
+        write_header(
+            &mut out,
+            "ferrumc_packets_received_total",
+            "Packets received, by packet id",
+            "counter",
+        );
+        let mut received = self
+            .packets_received
+            .iter()
+            .map(|entry| (*entry.key() as i32, entry.value().get()))
+            .collect::<Vec<_>>();
+        received.sort();
+        for (id, count) in received {
+            let _ = writeln!(
+                out,
+                "ferrumc_packets_received_total{{id=\"0x{:02x}\"}} {}",
+                id, count
+            );
+        }
+
+        write_header(
+            &mut out,
+            "ferrumc_packets_sent_total",
+            "Packets sent, by packet id",
+            "counter",
+        );
+        let mut sent = self
+            .packets_sent
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().get()))
+            .collect::<Vec<_>>();
+        sent.sort();
+        for (id, count) in sent {
+            let _ = writeln!(
+                out,
+                "ferrumc_packets_sent_total{{id=\"0x{:02x}\"}} {}",
+                id, count
+            );
+        }
+
+        write_counter(
+            &mut out,
+            "ferrumc_bytes_sent_total",
+            "Bytes sent to clients",
+            self.bytes_sent.get(),
+        );
+
+        write_histogram(
+            &mut out,
+            "ferrumc_chunk_send_seconds",
+            "Time taken to send the chunks around a player",
+            "",
+            &[("", &self.chunk_send)],
+        );
+        write_histogram(
+            &mut out,
+            "ferrumc_database_get_seconds",
+            "Time taken to read a chunk from the database",
+            "",
+            &[("", &self.database_get)],
+        );
+        write_histogram(
+            &mut out,
+            "ferrumc_database_put_seconds",
+            "Time taken to write chunks to the database",
+            "",
+            &[("", &self.database_put)],
+        );
+
+        write_counter(
+            &mut out,
+            "ferrumc_cache_hits_total",
+            "Chunk lookups answered by the cache",
+            self.cache_hits.get(),
+        );
+        write_counter(
+            &mut out,
+            "ferrumc_cache_misses_total",
+            "Chunk lookups that had to go to the database",
+            self.cache_misses.get(),
+        );
+        let lookups = self.cache_hits.get() + self.cache_misses.get();
+        let hit_rate = if lookups == 0 {
+            0.0
+        } else {
+            self.cache_hits.get() as f64 / lookups as f64
+        };
+        write_gauge(
+            &mut out,
+            "ferrumc_cache_hit_ratio",
+            "Share of chunk lookups answered by the cache",
+            hit_rate,
+        );
+
+        let system_loops = self.system_loop.iter().collect::<Vec<_>>();
+        let mut labelled = system_loops
+            .iter()
+            .map(|entry| (*entry.key(), entry.value()))
+            .collect::<Vec<_>>();
+        labelled.sort_by_key(|(name, _)| *name);
+        write_histogram(
+            &mut out,
+            "ferrumc_system_loop_seconds",
+            "Time taken by one iteration of a system's loop",
+            "system",
+            &labelled,
+        );
+
+        write_gauge(
+            &mut out,
+            "ferrumc_event_queue_depth",
+            "Events waiting in the event queue",
+            state.event_dispatcher.queue().depth() as f64,
+        );
+        write_counter(
+            &mut out,
+            "ferrumc_events_dropped_total",
+            "Events dropped because the event queue was full",
+            self.events_dropped.get(),
+        );
+        let event_handlers = self.event_handler.iter().collect::<Vec<_>>();
+        let mut labelled = event_handlers
+            .iter()
+            .map(|entry| (*entry.key(), entry.value()))
+            .collect::<Vec<_>>();
+        labelled.sort_by_key(|(name, _)| *name);
+        write_histogram(
+            &mut out,
+            "ferrumc_event_handler_seconds",
+            "Time taken by an event handler",
+            "handler",
+            &labelled,
+        );
+
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, metric_type: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, metric_type);
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, value: u64) {
+    write_header(out, name, help, "counter");
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn write_gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    write_header(out, name, help, "gauge");
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Writes histograms, each labelled with `label_name` unless the label is empty.
+fn write_histogram(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label_name: &str,
+    histograms: &[(&str, &Histogram)],
+) {
+    write_header(out, name, help, "histogram");
+    for (label, histogram) in histograms {
+        let labels = if label.is_empty() {
+            String::new()
+        } else {
+            format!("{}=\"{}\",", label_name, label)
+        };
+        let mut cumulative = 0;
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}le=\"{}\"}} {}",
+                name, labels, bound, cumulative
+            );
+        }
+        let count = histogram.count();
+        let _ = writeln!(out, "{}_bucket{{{}le=\"+Inf\"}} {}", name, labels, count);
+        let labels = labels.trim_end_matches(',');
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", labels)
+        };
+        let sum = histogram.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+        let _ = writeln!(out, "{}_sum{} {}", name, labels, sum);
+        let _ = writeln!(out, "{}_count{} {}", name, labels, count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Histogram::default();
+        histogram.observe(Duration::from_micros(100));
+        histogram.observe(Duration::from_millis(20));
+        histogram.observe(Duration::from_secs(10));
+
+        let mut out = String::new();
+        write_histogram(
+            &mut out,
+            "test_seconds",
+            "A test",
+            "system",
+            &[("Tick", &histogram)],
+        );
+        assert!(out.contains("test_seconds_bucket{system=\"Tick\",le=\"0.0005\"} 1\n"));
+        assert!(out.contains("test_seconds_bucket{system=\"Tick\",le=\"0.025\"} 2\n"));
+        assert!(out.contains("test_seconds_bucket{system=\"Tick\",le=\"2.5\"} 2\n"));
+        assert!(out.contains("test_seconds_bucket{system=\"Tick\",le=\"+Inf\"} 3\n"));
+        assert!(out.contains("test_seconds_count{system=\"Tick\"} 3\n"));
+    }
+}
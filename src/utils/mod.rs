@@ -1,8 +1,13 @@
-use crate::utils::constants::DEFAULT_LOG_LEVEL;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::utils::config::{LogFormat, LoggingConfig};
+use crate::utils::logging::SpanFieldsLayer;
 use crate::utils::prelude::*;
 use tracing_subscriber::filter::Directive;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{Layer, Registry};
 
 pub mod binary_utils;
 pub mod components;
@@ -10,25 +15,20 @@ pub mod config;
 pub mod constants;
 pub mod encoding;
 pub mod error;
+pub mod favicon;
 pub mod hash;
 pub mod impls;
+pub mod logging;
+pub mod metrics;
 pub mod prelude;
+pub mod text;
 
 /// Sets up the logger. Needs to be run before anything else in order for logging to run end.
-pub fn setup_logger() -> Result<()> {
-    let trace_level = std::env::args()
-        .find(|arg| arg.starts_with("--log="))
-        .map(|arg| arg.replace("--log=", ""));
-
-    let mut trace_level: &str = trace_level.as_deref().unwrap_or("");
-    if trace_level.is_empty() {
-        eprintln!(
-            "No log level specified, using default: {}",
-            DEFAULT_LOG_LEVEL
-        );
-        trace_level = DEFAULT_LOG_LEVEL;
-    }
-
+///
+/// `level` is the `--log` flag, if given, which takes precedence over the configured level. Logs
+/// go to the console and/or a rotated file in the logs directory, see [logging].
+pub fn setup_logger(level: Option<&str>, config: &LoggingConfig) -> Result<()> {
+    let trace_level = level.unwrap_or(&config.level);
     let trace_level = match trace_level.trim().parse::<tracing::Level>() {
         Ok(level) => level,
         Err(_) => {
@@ -38,28 +38,84 @@ pub fn setup_logger() -> Result<()> {
         }
     };
 
-    let env_filter = tracing_subscriber::EnvFilter::from_default_env()
+    let mut env_filter = tracing_subscriber::EnvFilter::from_default_env()
         .add_directive(trace_level.into())
         .add_directive(str_to_directive("sled=off")?);
+    for filter in &config.filters {
+        env_filter = env_filter.add_directive(str_to_directive(filter)?);
+    }
 
-    let mut fmt_layer = tracing_subscriber::fmt::Layer::default();
+    let mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>> = vec![Box::new(SpanFieldsLayer)];
 
-    if trace_level == tracing::Level::INFO {
-        // remove path from logs if log level is info
-        fmt_layer = tracing_subscriber::fmt::Layer::default()
-            .with_target(false)
-            .with_thread_ids(false)
-            .with_thread_names(false);
-    };
+    if config.console {
+        layers.push(match config.console_format {
+            LogFormat::Json => Box::new(
+                tracing_subscriber::fmt::Layer::default().event_format(logging::JsonFormat),
+            ),
+            // remove path from logs if log level is info
+            LogFormat::Text if trace_level == tracing::Level::INFO => Box::new(
+                tracing_subscriber::fmt::Layer::default()
+                    .with_target(false)
+                    .with_thread_ids(false)
+                    .with_thread_names(false),
+            ),
+            LogFormat::Text => Box::new(tracing_subscriber::fmt::Layer::default()),
+        });
+    }
+
+    if config.file {
+        let file = logging::RollingFile::new(
+            get_root_path()?.join(&config.directory),
+            config.rotation,
+            config.max_file_size_mb * 1024 * 1024,
+            config.max_files,
+        )?;
+        let layer = tracing_subscriber::fmt::Layer::default()
+            .with_ansi(false)
+            .with_writer(file);
+        layers.push(match config.file_format {
+            LogFormat::Json => Box::new(layer.event_format(logging::JsonFormat)),
+            LogFormat::Text => Box::new(layer),
+        });
+    }
 
     tracing_subscriber::registry()
+        .with(layers)
         .with(env_filter)
-        .with(fmt_layer)
         .init();
 
     Ok(())
 }
 
+static ROOT_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Overrides the root directory, e.g. with the `--root` flag.
+///
+/// Has to be called before anything uses [get_root_path].
+pub fn set_root_path(root: PathBuf) {
+    if ROOT_PATH.set(root).is_err() {
+        tracing::warn!("The root directory was already set, ignoring the new one");
+    }
+}
+
+/// The directory the server keeps its data in (config, world data, ban lists, ...).
+///
+/// This is the `--root` flag if it was given, then the `FERRUMC_ROOT` environment variable if it's
+/// set, and otherwise the directory the executable is in.
+pub fn get_root_path() -> Result<PathBuf> {
+    if let Some(root) = ROOT_PATH.get() {
+        return Ok(root.clone());
+    }
+    if let Ok(root) = std::env::var("FERRUMC_ROOT") {
+        return Ok(PathBuf::from(root));
+    }
+    let exe = std::env::current_exe()?;
+    let dir = exe
+        .parent()
+        .ok_or(Error::Generic("Failed to get exe directory".to_string()))?;
+    Ok(dir.to_path_buf())
+}
+
 fn str_to_directive(s: &str) -> Result<Directive> {
     s.parse()
         .map_err(|_| Error::InvalidDirective(s.to_string()))
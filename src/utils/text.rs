@@ -0,0 +1,106 @@
+//! Helpers for text shown to players, like the MOTD.
+//!
+//! Text in the config can either be a JSON text component, or plain text with `&` colour codes
+//! (e.g. `&aGreen &lbold`), which are turned into `§` codes.
+
+use serde_json::Value;
+
+/// The characters that can follow `&`/`§` in a formatting code.
+const FORMATTING_CODES: &str = "0123456789abcdefklmnor";
+
+/// Replaces `&` colour codes with `§` ones. An `&` that isn't followed by a valid code is kept.
+pub fn translate_colour_codes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let is_code = chars
+            .peek()
+            .is_some_and(|next| FORMATTING_CODES.contains(next.to_ascii_lowercase()));
+        if c == '&' && is_code {
+            result.push('§');
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Parses text from the config into a JSON text component.
+///
+/// Anything that looks like a JSON object or array and parses as one is used as is, everything else
+/// is plain text with `&` colour codes.
+pub fn parse_text_component(text: &str) -> Value {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        if let Ok(component) = serde_json::from_str::<Value>(text) {
+            return component;
+        }
+    }
+    serde_json::json!({ "text": translate_colour_codes(text) })
+}
+
+/// Flattens a text component into plain text (keeping any `§` codes in it), for protocols that
+/// don't support components.
+pub fn to_legacy_text(component: &Value) -> String {
+    let mut text = String::new();
+    append_legacy_text(component, &mut text);
+    text
+}
+
+fn append_legacy_text(component: &Value, text: &mut String) {
+    match component {
+        Value::String(string) => text.push_str(string),
+        Value::Array(components) => {
+            for component in components {
+                append_legacy_text(component, text);
+            }
+        }
+        Value::Object(object) => {
+            if let Some(Value::String(string)) = object.get("text") {
+                text.push_str(string);
+            }
+            if let Some(extra) = object.get("extra") {
+                append_legacy_text(extra, text);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_translate_colour_codes() {
+        assert_eq!(
+            translate_colour_codes("&aGreen &LBold & Co &z"),
+            "§aGreen §LBold & Co &z"
+        );
+    }
+
+    #[test]
+    fn test_parse_text_component() {
+        assert_eq!(parse_text_component("&6Gold"), json!({ "text": "§6Gold" }));
+        assert_eq!(
+            parse_text_component(r#"{"text": "Hi", "color": "red"}"#),
+            json!({ "text": "Hi", "color": "red" })
+        );
+        // Not valid JSON, so it's plain text.
+        assert_eq!(
+            parse_text_component("{ not json"),
+            json!({ "text": "{ not json" })
+        );
+    }
+
+    #[test]
+    fn test_to_legacy_text() {
+        let component = json!([
+            { "text": "A ", "extra": [{ "text": "§cred" }, " server"] },
+            "!"
+        ]);
+        assert_eq!(to_legacy_text(&component), "A §cred server!");
+    }
+}
@@ -1,5 +1,6 @@
 use tracing::debug;
 
+use crate::database::Database;
 use crate::state::GlobalState;
 use crate::utils::binary_utils::read_n_bits_u16;
 use crate::utils::error::Error;
@@ -14,7 +15,9 @@ pub async fn read_block(
     let (chunk_x, chunk_z) = (x / 16, z / 16);
     debug!("Getting chunk: {} {}", chunk_x, chunk_z);
     let chunk = state
-        .database
+        .world
+        .resource::<Database>()
+        .await?
         .get_chunk(chunk_x, chunk_z, dimension)
         .await?;
     if chunk.is_none() {
@@ -121,7 +124,7 @@ mod tests {
     #[tokio::test]
     #[ignore]
     async fn test_reading() {
-        if setup_logger().is_ok() {
+        if setup_logger(None, &Default::default()).is_ok() {
             warn!("Logger already set up");
         }
         let state = crate::create_state(TcpListener::bind("0.0.0.0:0").await.unwrap())
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use fastanvil::Region;
+use indicatif::{ProgressBar, ProgressStyle};
+use nbt_lib::NBTSerialize;
+use tracing::{debug, info, warn};
+
+use crate::database::Database;
+use crate::utils::prelude::*;
+use crate::world::chunk_format::Chunk;
+
+/// Chunks are stored in regions of 32x32 chunks.
+const REGION_SIZE: i32 = 32;
+/// Only the overworld is exported, since region files don't say which dimension they're for.
+const EXPORTED_DIMENSION: &str = "overworld";
+
+/// Exports every overworld chunk in the database to region files (`r.<x>.<z>.mca`) in `dir`.
+///
+/// Existing region files in `dir` are overwritten.
+pub async fn export_regions(database: &Database, dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    debug!("Starting export to: {}", dir.display());
+
+    let start = std::time::Instant::now();
+    let keys = database.chunk_keys().await?;
+    info!("Exporting {} chunks", keys.len());
+    let bar = create_progress_bar(keys.len());
+
+    let mut regions: HashMap<(i32, i32), Region<File>> = HashMap::new();
+    let mut exported = 0usize;
+    for key in keys {
+        bar.inc(1);
+        let chunk = match database.get_chunk_by_key(key).await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("Failed to read chunk {}: {}. Skipping.", key, e);
+                continue;
+            }
+        };
+        if chunk.dimension.as_deref() != Some(EXPORTED_DIMENSION) {
+            continue;
+        }
+
+        let region_pos = (
+            chunk.x_pos.div_euclid(REGION_SIZE),
+            chunk.z_pos.div_euclid(REGION_SIZE),
+        );
+        let region = match regions.entry(region_pos) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let path = dir.join(format!("r.{}.{}.mca", region_pos.0, region_pos.1));
+                let file = File::options()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(path)?;
+                entry.insert(Region::new(file)?)
+            }
+        };
+
+        let data = serialize_chunk(&chunk)?;
+        region.write_chunk(
+            chunk.x_pos.rem_euclid(REGION_SIZE) as usize,
+            chunk.z_pos.rem_euclid(REGION_SIZE) as usize,
+            &data,
+        )?;
+        exported += 1;
+    }
+
+    bar.finish_with_message(format!("Export complete! {} chunks written.", exported));
+    info!(
+        "Exported {} chunks to {} region files in {:?}",
+        exported,
+        regions.len(),
+        start.elapsed()
+    );
+    Ok(())
+}
+
+fn serialize_chunk(chunk: &Chunk) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    chunk.nbt_serialize(&mut data).map_err(|e| {
+        Error::SerializationError(format!(
+            "Could not serialize chunk {} {}: {}",
+            chunk.x_pos, chunk.z_pos, e
+        ))
+    })?;
+    Ok(data)
+}
+
+fn create_progress_bar(total_chunks: usize) -> ProgressBar {
+    let bar = ProgressBar::new(total_chunks as u64);
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] {bar:100.cyan/blue} {pos:>7}/{len:7} {msg}")
+            .expect("Could not set progress bar style")
+            .progress_chars("##-"),
+    );
+    bar.set_message("Exporting chunks...");
+    bar
+}
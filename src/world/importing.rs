@@ -1,5 +1,5 @@
 use crate::database::encoding::ZstdCodec;
-use crate::state::GlobalState;
+use crate::database::Database;
 use crate::utils::hash::hash;
 use crate::utils::prelude::*;
 use crate::world::chunk_format::Chunk;
@@ -7,14 +7,14 @@ use fastanvil::{ChunkData, Region};
 use indicatif::{ProgressBar, ProgressStyle};
 use nbt_lib::NBTDeserializeBytes;
 use rayon::prelude::*;
-use std::env;
 use std::fs::File;
 use std::io::Cursor;
-use std::path::PathBuf;
+use std::path::Path;
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
-const DEFAULT_BATCH_SIZE: u8 = 150;
+/// How many chunks are processed at once, unless `--batch-size` is given.
+pub const DEFAULT_BATCH_SIZE: usize = 150;
 
 /// A serialized chunk is a tuple of the chunk's hash and the compressed chunk data
 /// (hash, compressed_chunk_data)
@@ -33,24 +33,6 @@ impl SerializedChunk {
     }
 }
 
-fn get_batch_size() -> i32 {
-    let batch_size = env::args()
-        .find(|x| x.starts_with("--batch_size="))
-        .and_then(|x| x.split('=').last().and_then(|s| s.parse::<i32>().ok()));
-
-    match batch_size {
-        Some(size) => {
-            info!("Using custom batch size: {}", size);
-            size
-        }
-        None => {
-            info!("Using default batch size: {}", DEFAULT_BATCH_SIZE);
-            info!("To change the batch size, use the --batch_size=<num> flag");
-            DEFAULT_BATCH_SIZE as i32
-        }
-    }
-}
-
 fn format_duration(duration: std::time::Duration) -> String {
     let secs = duration.as_secs();
     let millis = duration.subsec_millis();
@@ -66,7 +48,7 @@ fn format_duration(duration: std::time::Duration) -> String {
     }
 }
 
-async fn get_total_chunks(dir: &PathBuf) -> Result<usize> {
+async fn get_total_chunks(dir: &Path) -> Result<usize> {
     let files = std::fs::read_dir(dir)?;
     let regions: Vec<Region<File>> = files
         .filter_map(|entry| entry.ok())
@@ -127,24 +109,24 @@ async fn process_chunk(
     Ok(SerializedChunk::new(hash, chunk_data))
 }
 
+/// Imports the chunks in the region files in `dir` into the database, `batch_size` at a time.
 //noinspection RsBorrowChecker
-pub async fn import_regions(state: GlobalState) -> Result<()> {
-    let dir = get_import_directory()?;
+pub async fn import_regions(database: &Database, dir: &Path, batch_size: usize) -> Result<()> {
     debug!("Starting import from: {}", dir.display());
 
     let start = std::time::Instant::now();
     info!("Analyzing world data... (this won't take long)");
 
-    let total_chunks = get_total_chunks(&dir).await?;
+    let total_chunks = get_total_chunks(dir).await?;
     info!("Preparing to import {} chunks", total_chunks);
     info!("This process may take a while for large worlds. Please be patient.");
 
-    let batch_size = get_batch_size() as usize;
+    info!("Using a batch size of {}", batch_size);
     let bar = Arc::new(create_progress_bar(total_chunks));
 
     let mut region_files = tokio::fs::read_dir(dir)
         .await
-        .map_err(|_| Error::Generic("Could not read the imports directory".to_string()))?;
+        .map_err(|_| Error::Generic(format!("Could not read the directory {}", dir.display())))?;
 
     while let Some(dir_file) = region_files.next_entry().await? {
         let file_name = dir_file.file_name();
@@ -186,7 +168,7 @@ pub async fn import_regions(state: GlobalState) -> Result<()> {
                     .filter_map(|result| result.ok().flatten())
                     .collect();
 
-            insert_chunks(&state, processed_chunks, &bar).await?;
+            insert_chunks(database, processed_chunks, &bar).await?;
         }
     }
 
@@ -194,17 +176,6 @@ pub async fn import_regions(state: GlobalState) -> Result<()> {
     Ok(())
 }
 
-fn get_import_directory() -> Result<PathBuf> {
-    if let Ok(root) = env::var("FERRUMC_ROOT") {
-        Ok(PathBuf::from(root).join("import"))
-    } else {
-        env::current_exe()?
-            .parent()
-            .ok_or_else(|| Error::Generic("Failed to get exe directory".to_string()))
-            .map(|path| path.join("import"))
-    }
-}
-
 fn create_progress_bar(total_chunks: usize) -> ProgressBar {
     let bar = ProgressBar::new(total_chunks as u64);
     bar.set_style(
@@ -218,18 +189,14 @@ fn create_progress_bar(total_chunks: usize) -> ProgressBar {
 }
 
 async fn insert_chunks(
-    state: &GlobalState,
+    database: &Database,
     queued_chunks: Vec<SerializedChunk>,
     bar: &ProgressBar,
 ) -> Result<()> {
-    state
-        .database
-        .batch_insert(queued_chunks)
-        .await
-        .map_err(|e| {
-            bar.abandon_with_message("Chunk insertion failed".to_string());
-            Error::Generic(format!("Could not insert chunks: {}", e))
-        })?;
+    database.batch_insert(queued_chunks).await.map_err(|e| {
+        bar.abandon_with_message("Chunk insertion failed".to_string());
+        Error::Generic(format!("Could not insert chunks: {}", e))
+    })?;
     Ok(())
 }
 
@@ -248,6 +215,7 @@ fn finalize_import(bar: &ProgressBar, total_chunks: usize, elapsed: std::time::D
 #[cfg(test)]
 mod test {
     use crate::create_state;
+    use crate::database::Database;
     use crate::utils::prelude::*;
     use crate::utils::setup_logger;
     use tokio::net::TcpListener;
@@ -256,12 +224,14 @@ mod test {
     #[ignore]
     async fn get_chunk_at() -> Result<()> {
         // set environment variable "FERRUMC_ROOT" to the root of the ferrumc project
-        setup_logger()?;
+        setup_logger(None, &Default::default())?;
         let listener = TcpListener::bind("0.0.0.0:0").await?;
         let state = create_state(listener).await?;
 
         let chunk = state
-            .database
+            .world
+            .resource::<Database>()
+            .await?
             .get_chunk(0, 0, "overworld".to_string())
             .await?
             .unwrap();
@@ -1,6 +1,7 @@
 pub mod blocks;
 pub mod chunk_format;
 pub mod conversions;
+pub mod exporting;
 pub mod importing;
 
 /// Since we don't know the exact amount of bytes, the first byte is the number of u8s in the last i64,